@@ -2,7 +2,14 @@
 import * as path from 'path';
 
 import * as vscode from 'vscode';
-import { LanguageClient, LanguageClientOptions, ServerOptions, TransportKind } from 'vscode-languageclient/node';
+import {
+    LanguageClient,
+    LanguageClientOptions,
+    Location,
+    Position,
+    ServerOptions,
+    TransportKind,
+} from 'vscode-languageclient/node';
 
 let client: LanguageClient;
 
@@ -11,10 +18,48 @@ export function activate(context: vscode.ExtensionContext) {
     const clientOptions: LanguageClientOptions = {
         documentSelector: [{ scheme: 'file', language: 'thrift' }],
         synchronize: { fileEvents: vscode.workspace.createFileSystemWatcher('**/*.thrift') },
+        initializationOptions: {
+            deprecationAnnotation: vscode.workspace.getConfiguration('thrift-ls').get<string>('deprecationAnnotation'),
+        },
+        middleware: {
+            // asks what the server needs to compute the edit of a refactoring: the name of
+            // an extracted typedef, or the file a definition is moved to
+            executeCommand: async (command, args, next) => {
+                if (command === 'thrift-ls.extractTypedef' && args.length < 3) {
+                    const name = await vscode.window.showInputBox({ prompt: 'Name of the typedef', value: 'NewType' });
+                    if (name === undefined) {
+                        return;
+                    }
+                    args = [...args, name];
+                }
+                if (command === 'thrift-ls.moveDefinition' && args.length < 3) {
+                    const files = await vscode.workspace.findFiles('**/*.thrift');
+                    const items = files
+                        .filter((file) => file.toString() !== args[0])
+                        .map((file) => ({ label: vscode.workspace.asRelativePath(file), uri: file.toString() }));
+                    const item = await vscode.window.showQuickPick(items, { placeHolder: 'Destination file' });
+                    if (item === undefined) {
+                        return;
+                    }
+                    args = [...args, item.uri];
+                }
+                return next(command, args);
+            },
+        },
     };
 
     client = new LanguageClient('thriftLanguageServer', 'Thrift Language Server', serverOptions, clientOptions);
     client.start();
+
+    // used by code lenses, whose arguments are LSP types
+    context.subscriptions.push(vscode.commands.registerCommand('thrift-ls.showReferences',
+        (uri: string, position: Position, locations: Location[]) => {
+            const converter = client.protocol2CodeConverter;
+            return vscode.commands.executeCommand('editor.action.showReferences',
+                converter.asUri(uri),
+                converter.asPosition(position),
+                locations.map((location) => converter.asLocation(location)));
+        }));
 }
 
 function getServerOptions(context: vscode.ExtensionContext): ServerOptions {
@@ -8,22 +8,128 @@ import {
     DidChangeTextDocumentParams,
     SemanticTokensParams,
     SemanticTokens,
+    SemanticTokensDeltaParams,
+    SemanticTokensDelta,
+    SemanticTokensRangeParams,
     DefinitionParams,
+    TypeDefinitionParams,
     Location,
     DidCloseTextDocumentParams,
     Diagnostic,
     CompletionParams,
     CompletionItem,
     CompletionItemKind,
+    HoverParams,
+    Hover,
+    ReferenceParams,
+    PrepareRenameParams,
+    RenameParams,
+    Range,
+    WorkspaceEdit,
+    ResponseError,
+    ErrorCodes,
+    DocumentSymbolParams,
+    DocumentHighlightParams,
+    DocumentHighlight,
+    DocumentSymbol,
+    WorkspaceSymbolParams,
+    SymbolInformation,
+    DocumentLinkParams,
+    FoldingRangeParams,
+    FoldingRange,
+    SelectionRangeParams,
+    SelectionRange,
+    CodeLensParams,
+    CodeLens,
+    InlayHintParams,
+    InlayHint,
+    DocumentLink,
+    TypeHierarchyPrepareParams,
+    TypeHierarchySupertypesParams,
+    TypeHierarchySubtypesParams,
+    TypeHierarchyItem,
+    CallHierarchyPrepareParams,
+    CallHierarchyIncomingCallsParams,
+    CallHierarchyOutgoingCallsParams,
+    CallHierarchyItem,
+    CallHierarchyIncomingCall,
+    CallHierarchyOutgoingCall,
+    CodeActionParams,
+    CodeAction,
+    CodeActionKind,
+    ExecuteCommandParams,
+    DocumentFormattingParams,
+    DocumentRangeFormattingParams,
+    DocumentOnTypeFormattingParams,
+    TextEdit,
 } from 'vscode-languageserver/node';
 import { Analyzer } from 'thrift_analyzer';
-import { uriToPath, pathToUri, Error, Location as UtilsLocation, toLspDiagnostic, toLspLocation, readFile } from './utils';
+import {
+    uriToPath,
+    pathToUri,
+    Error,
+    Location as UtilsLocation,
+    Hover as UtilsHover,
+    Range as UtilsRange,
+    WorkspaceEdit as UtilsWorkspaceEdit,
+    DocumentSymbol as UtilsDocumentSymbol,
+    DocumentHighlight as UtilsDocumentHighlight,
+    WorkspaceSymbol as UtilsWorkspaceSymbol,
+    DocumentLink as UtilsDocumentLink,
+    FoldingRange as UtilsFoldingRange,
+    SelectionRange as UtilsSelectionRange,
+    CodeLens as UtilsCodeLens,
+    InlayHint as UtilsInlayHint,
+    SemanticTokens as UtilsSemanticTokens,
+    SemanticTokensDelta as UtilsSemanticTokensDelta,
+    TypeHierarchyItem as UtilsTypeHierarchyItem,
+    CallHierarchyItem as UtilsCallHierarchyItem,
+    CallHierarchyIncomingCall as UtilsCallHierarchyIncomingCall,
+    CallHierarchyOutgoingCall as UtilsCallHierarchyOutgoingCall,
+    TextEdit as UtilsTextEdit,
+    CodeAction as UtilsCodeAction,
+    toLspDiagnostic,
+    toLspLocation,
+    toLspHover,
+    toLspSemanticTokens,
+    toLspSemanticTokensDelta,
+    toLspRange,
+    toLspTextEdit,
+    toLspCodeAction,
+    EXTRACT_TYPEDEF_COMMAND,
+    MOVE_DEFINITION_COMMAND,
+    REFACTOR_MOVE_KIND,
+    toLspWorkspaceEdit,
+    toLspDocumentSymbol,
+    toLspDocumentHighlight,
+    toLspSymbolInformation,
+    toLspDocumentLink,
+    toLspFoldingRange,
+    toLspSelectionRange,
+    toLspCodeLens,
+    toLspInlayHint,
+    toLspTypeHierarchyItem,
+    toLspCallHierarchyItem,
+    findThriftFiles,
+    readFile,
+} from './utils';
 
 const connection = createConnection(ProposedFeatures.all);
 const analyzer = Analyzer.new();
 analyzer.set_wasm_read_file(readFile);
 
-connection.onInitialize((_params: InitializeParams): InitializeResult => {
+// workspace folders, indexed once the client is initialized
+let workspaceRoots: string[] = [];
+
+connection.onInitialize((params: InitializeParams): InitializeResult => {
+    workspaceRoots = params.workspaceFolders?.map((folder) => uriToPath(folder.uri))
+        ?? (params.rootUri ? [uriToPath(params.rootUri)] : []);
+
+    const deprecationAnnotation = params.initializationOptions?.deprecationAnnotation;
+    if (typeof deprecationAnnotation === 'string') {
+        analyzer.set_deprecation_annotation(deprecationAnnotation);
+    }
+
     return {
         capabilities: {
             textDocumentSync: TextDocumentSyncKind.Full,
@@ -32,15 +138,60 @@ connection.onInitialize((_params: InitializeParams): InitializeResult => {
                     tokenTypes: analyzer.semantic_token_types(),
                     tokenModifiers: analyzer.semantic_token_modifiers(),
                 },
-                full: true
+                full: {
+                    delta: true
+                },
+                range: true
             },
             definitionProvider: true,
+            typeDefinitionProvider: true,
             completionProvider: {
                 resolveProvider: false,
                 triggerCharacters: ['.'],
             },
+            hoverProvider: true,
+            referencesProvider: true,
+            documentHighlightProvider: true,
+            documentSymbolProvider: true,
+            workspaceSymbolProvider: true,
+            foldingRangeProvider: true,
+            selectionRangeProvider: true,
+            inlayHintProvider: true,
+            codeLensProvider: {
+                resolveProvider: true,
+            },
+            typeHierarchyProvider: true,
+            callHierarchyProvider: true,
+            documentLinkProvider: {
+                resolveProvider: false,
+            },
+            renameProvider: {
+                prepareProvider: true,
+            },
+            codeActionProvider: {
+                codeActionKinds: [CodeActionKind.QuickFix, CodeActionKind.RefactorExtract, CodeActionKind.RefactorInline, REFACTOR_MOVE_KIND, CodeActionKind.RefactorRewrite],
+            },
+            executeCommandProvider: {
+                commands: [EXTRACT_TYPEDEF_COMMAND, MOVE_DEFINITION_COMMAND],
+            },
+            documentFormattingProvider: true,
+            documentRangeFormattingProvider: true,
+            documentOnTypeFormattingProvider: {
+                firstTriggerCharacter: '\n',
+                moreTriggerCharacter: ['}'],
+            },
+        }
+    }
+});
+
+connection.onInitialized(() => {
+    // index workspace files, so their symbols can be found before they are opened
+    for (const root of workspaceRoots) {
+        for (const path of findThriftFiles(root, (name) => analyzer.is_skipped_directory(name))) {
+            analyzer.index_file(path);
         }
     }
+    workspaceRoots = [];
 });
 
 connection.onDidOpenTextDocument((params: DidOpenTextDocumentParams) => {
@@ -65,7 +216,33 @@ connection.onDidCloseTextDocument((params: DidCloseTextDocumentParams) => {
 
 connection.onRequest("textDocument/semanticTokens/full", (params: SemanticTokensParams): SemanticTokens => {
     const path = uriToPath(params.textDocument.uri);
-    const result = analyzer.semantic_tokens(path);
+    const result: UtilsSemanticTokens | null = analyzer.semantic_tokens(path);
+
+    return result ? toLspSemanticTokens(result) : { data: [] };
+});
+
+connection.onRequest("textDocument/semanticTokens/full/delta", (params: SemanticTokensDeltaParams): SemanticTokens | SemanticTokensDelta => {
+    const path = uriToPath(params.textDocument.uri);
+    const delta: UtilsSemanticTokensDelta | null = analyzer.semantic_tokens_delta(path, params.previousResultId);
+    if (delta) {
+        return toLspSemanticTokensDelta(delta);
+    }
+
+    // all tokens if the previous result is unknown
+    const result: UtilsSemanticTokens | null = analyzer.semantic_tokens(path);
+    return result ? toLspSemanticTokens(result) : { data: [] };
+});
+
+connection.onRequest("textDocument/semanticTokens/range", (params: SemanticTokensRangeParams): SemanticTokens => {
+    const path = uriToPath(params.textDocument.uri);
+    const range = params.range;
+    const result = analyzer.semantic_tokens_range(
+        path,
+        range.start.line + 1,
+        range.start.character + 1,
+        range.end.line + 1,
+        range.end.character + 1
+    );
 
     return {
         data: result ? Array.from(result) : []
@@ -80,6 +257,14 @@ connection.onDefinition((params: DefinitionParams): Location | null => {
     return toLspLocation(result);
 });
 
+connection.onTypeDefinition((params: TypeDefinitionParams): Location | null => {
+    const path = uriToPath(params.textDocument.uri);
+    const position = params.position;
+    const result: UtilsLocation = analyzer.type_definition(path, position.line + 1, position.character + 1);
+
+    return toLspLocation(result);
+});
+
 connection.onCompletion((params: CompletionParams): CompletionItem[] => {
     const path = uriToPath(params.textDocument.uri);
     const position = params.position;
@@ -99,6 +284,221 @@ connection.onCompletion((params: CompletionParams): CompletionItem[] => {
     return completionItems;
 });
 
+connection.onHover((params: HoverParams): Hover | null => {
+    const path = uriToPath(params.textDocument.uri);
+    const position = params.position;
+    const result: UtilsHover = analyzer.hover(path, position.line + 1, position.character + 1);
+
+    return toLspHover(result);
+});
+
+connection.onReferences((params: ReferenceParams): Location[] => {
+    const path = uriToPath(params.textDocument.uri);
+    const position = params.position;
+    const includeDeclaration = params.context.includeDeclaration;
+    const result: UtilsLocation[] = analyzer.references(path, position.line + 1, position.character + 1, includeDeclaration);
+
+    return result.map((location) => toLspLocation(location)).filter((location): location is Location => location != null);
+});
+
+connection.onDocumentHighlight((params: DocumentHighlightParams): DocumentHighlight[] => {
+    const path = uriToPath(params.textDocument.uri);
+    const position = params.position;
+    const result: UtilsDocumentHighlight[] = analyzer.document_highlights(path, position.line + 1, position.character + 1);
+
+    return result.map(toLspDocumentHighlight);
+});
+
+connection.onDocumentSymbol((params: DocumentSymbolParams): DocumentSymbol[] => {
+    const path = uriToPath(params.textDocument.uri);
+    const result: UtilsDocumentSymbol[] = analyzer.document_symbols(path);
+
+    return result.map(toLspDocumentSymbol);
+});
+
+connection.onFoldingRanges((params: FoldingRangeParams): FoldingRange[] => {
+    const path = uriToPath(params.textDocument.uri);
+    const result: UtilsFoldingRange[] = analyzer.folding_ranges(path);
+
+    return result.map(toLspFoldingRange);
+});
+
+connection.onSelectionRanges((params: SelectionRangeParams): SelectionRange[] => {
+    const path = uriToPath(params.textDocument.uri);
+    const lines = Uint32Array.from(params.positions.map((position) => position.line + 1));
+    const columns = Uint32Array.from(params.positions.map((position) => position.character + 1));
+    const result: UtilsSelectionRange[] = analyzer.selection_ranges(path, lines, columns);
+
+    return result.map(toLspSelectionRange);
+});
+
+connection.onCodeLens((params: CodeLensParams): CodeLens[] => {
+    const path = uriToPath(params.textDocument.uri);
+    const result: UtilsCodeLens[] = analyzer.code_lenses(path);
+
+    return result.map((codeLens) => toLspCodeLens(codeLens, params.textDocument.uri));
+});
+
+connection.onCodeLensResolve((params: CodeLens): CodeLens => {
+    const { uri, codeLens } = params.data as { uri: string, codeLens: UtilsCodeLens };
+    const result: UtilsCodeLens | null = analyzer.resolve_code_lens(uriToPath(uri), codeLens);
+
+    return result ? toLspCodeLens(result, uri) : params;
+});
+
+connection.languages.inlayHint.on((params: InlayHintParams): InlayHint[] => {
+    const path = uriToPath(params.textDocument.uri);
+    const { start, end } = params.range;
+    const result: UtilsInlayHint[] = analyzer.inlay_hints(path, start.line + 1, start.character + 1, end.line + 1, end.character + 1);
+
+    return result.map(toLspInlayHint);
+});
+
+connection.languages.typeHierarchy.onPrepare((params: TypeHierarchyPrepareParams): TypeHierarchyItem[] | null => {
+    const path = uriToPath(params.textDocument.uri);
+    const position = params.position;
+    const result: UtilsTypeHierarchyItem | null = analyzer.prepare_type_hierarchy(path, position.line + 1, position.character + 1);
+
+    return result ? [toLspTypeHierarchyItem(result)] : null;
+});
+
+connection.languages.typeHierarchy.onSupertypes((params: TypeHierarchySupertypesParams): TypeHierarchyItem[] => {
+    const path = uriToPath(params.item.uri);
+    const result: UtilsTypeHierarchyItem[] = analyzer.supertypes(path, params.item.name);
+
+    return result.map(toLspTypeHierarchyItem);
+});
+
+connection.languages.typeHierarchy.onSubtypes((params: TypeHierarchySubtypesParams): TypeHierarchyItem[] => {
+    const path = uriToPath(params.item.uri);
+    const result: UtilsTypeHierarchyItem[] = analyzer.subtypes(path, params.item.name);
+
+    return result.map(toLspTypeHierarchyItem);
+});
+
+connection.languages.callHierarchy.onPrepare((params: CallHierarchyPrepareParams): CallHierarchyItem[] | null => {
+    const path = uriToPath(params.textDocument.uri);
+    const position = params.position;
+    const result: UtilsCallHierarchyItem | null = analyzer.prepare_call_hierarchy(path, position.line + 1, position.character + 1);
+
+    return result ? [toLspCallHierarchyItem(result)] : null;
+});
+
+connection.languages.callHierarchy.onIncomingCalls((params: CallHierarchyIncomingCallsParams): CallHierarchyIncomingCall[] => {
+    const path = uriToPath(params.item.uri);
+    const start = params.item.selectionRange.start;
+    const result: UtilsCallHierarchyIncomingCall[] = analyzer.incoming_calls(path, start.line + 1, start.character + 1);
+
+    return result.map((call) => ({
+        from: toLspCallHierarchyItem(call.from),
+        fromRanges: call.from_ranges.map(toLspRange),
+    }));
+});
+
+connection.languages.callHierarchy.onOutgoingCalls((params: CallHierarchyOutgoingCallsParams): CallHierarchyOutgoingCall[] => {
+    const path = uriToPath(params.item.uri);
+    const start = params.item.selectionRange.start;
+    const result: UtilsCallHierarchyOutgoingCall[] = analyzer.outgoing_calls(path, start.line + 1, start.character + 1);
+
+    return result.map((call) => ({
+        to: toLspCallHierarchyItem(call.to),
+        fromRanges: call.from_ranges.map(toLspRange),
+    }));
+});
+
+connection.onDocumentLinks((params: DocumentLinkParams): DocumentLink[] => {
+    const path = uriToPath(params.textDocument.uri);
+    const result: UtilsDocumentLink[] = analyzer.document_links(path);
+
+    return result.map(toLspDocumentLink);
+});
+
+connection.onWorkspaceSymbol((params: WorkspaceSymbolParams): SymbolInformation[] => {
+    const result: UtilsWorkspaceSymbol[] = analyzer.workspace_symbols(params.query);
+
+    return result.map(toLspSymbolInformation);
+});
+
+connection.onPrepareRename((params: PrepareRenameParams): Range | null => {
+    const path = uriToPath(params.textDocument.uri);
+    const position = params.position;
+    const result: UtilsRange | null = analyzer.prepare_rename(path, position.line + 1, position.character + 1);
+
+    return result ? toLspRange(result) : null;
+});
+
+connection.onRenameRequest((params: RenameParams): WorkspaceEdit | ResponseError<void> => {
+    const path = uriToPath(params.textDocument.uri);
+    const position = params.position;
+
+    try {
+        const result: UtilsWorkspaceEdit = analyzer.rename(path, position.line + 1, position.character + 1, params.newName);
+        return toLspWorkspaceEdit(result);
+    } catch (error) {
+        return new ResponseError(ErrorCodes.RequestFailed, String(error));
+    }
+});
+
+connection.onCodeAction((params: CodeActionParams): CodeAction[] => {
+    const path = uriToPath(params.textDocument.uri);
+    const { start, end } = params.range;
+    const only = params.context.only;
+    const result: UtilsCodeAction[] = analyzer.code_actions(path, start.line + 1, start.character + 1, end.line + 1, end.character + 1);
+
+    return result
+        .map(toLspCodeAction)
+        .filter(action => !only || only.some(kind => action.kind === kind || action.kind?.startsWith(kind + '.')));
+});
+
+connection.onExecuteCommand(async (params: ExecuteCommandParams): Promise<void | ResponseError<void>> => {
+    let result: UtilsWorkspaceEdit;
+    try {
+        if (params.command === EXTRACT_TYPEDEF_COMMAND) {
+            const [uri, range, name] = params.arguments ?? [];
+            const { start, end } = range as Range;
+            result = analyzer.extract_typedef(uriToPath(uri), start.line + 1, start.character + 1, end.line + 1, end.character + 1, name ?? 'NewType');
+        } else if (params.command === MOVE_DEFINITION_COMMAND) {
+            const [uri, position, destination] = params.arguments ?? [];
+            if (!destination) {
+                return new ResponseError(ErrorCodes.RequestFailed, 'Missing destination file');
+            }
+            result = analyzer.move_definition(uriToPath(uri), position.line + 1, position.character + 1, uriToPath(destination));
+        } else {
+            return new ResponseError(ErrorCodes.RequestFailed, `Unknown command: ${params.command}`);
+        }
+    } catch (error) {
+        return new ResponseError(ErrorCodes.RequestFailed, String(error));
+    }
+
+    await connection.workspace.applyEdit(toLspWorkspaceEdit(result));
+});
+
+connection.onDocumentFormatting((params: DocumentFormattingParams): TextEdit[] => {
+    const path = uriToPath(params.textDocument.uri);
+    const options = params.options;
+    const result: UtilsTextEdit[] = analyzer.formatting(path, options.tabSize, options.insertSpaces);
+
+    return result.map(toLspTextEdit);
+});
+
+connection.onDocumentRangeFormatting((params: DocumentRangeFormattingParams): TextEdit[] => {
+    const path = uriToPath(params.textDocument.uri);
+    const { start, end } = params.range;
+    const options = params.options;
+    const result: UtilsTextEdit[] = analyzer.range_formatting(path, start.line + 1, start.character + 1, end.line + 1, end.character + 1, options.tabSize, options.insertSpaces);
+
+    return result.map(toLspTextEdit);
+});
+
+connection.onDocumentOnTypeFormatting((params: DocumentOnTypeFormattingParams): TextEdit[] => {
+    const path = uriToPath(params.textDocument.uri);
+    const position = params.position;
+    const options = params.options;
+    const result: UtilsTextEdit[] = analyzer.on_type_formatting(path, position.line + 1, position.character + 1, params.ch, options.tabSize, options.insertSpaces);
+
+    return result.map(toLspTextEdit);
+});
+
 function publishDiagnostics() {
     const errors_map: Map<string, Error[]> = analyzer.errors();
 
@@ -1,10 +1,35 @@
 import * as url from "node:url"
 import * as fs from "node:fs"
+import * as nodePath from "node:path"
 import {
     Location as LspLocation,
     Range as LspRange,
     Position as LspPosition,
     Diagnostic as LspDiagnostic,
+    DiagnosticSeverity as LspDiagnosticSeverity,
+    DiagnosticTag as LspDiagnosticTag,
+    Hover as LspHover,
+    MarkupKind,
+    TextEdit as LspTextEdit,
+    WorkspaceEdit as LspWorkspaceEdit,
+    CodeAction as LspCodeAction,
+    CodeActionKind as LspCodeActionKind,
+    Command as LspCommand,
+    DocumentSymbol as LspDocumentSymbol,
+    DocumentHighlight as LspDocumentHighlight,
+    DocumentHighlightKind as LspDocumentHighlightKind,
+    SymbolKind as LspSymbolKind,
+    SymbolInformation as LspSymbolInformation,
+    DocumentLink as LspDocumentLink,
+    FoldingRange as LspFoldingRange,
+    SelectionRange as LspSelectionRange,
+    CodeLens as LspCodeLens,
+    InlayHint as LspInlayHint,
+    SemanticTokens as LspSemanticTokens,
+    SemanticTokensDelta as LspSemanticTokensDelta,
+    FoldingRangeKind as LspFoldingRangeKind,
+    TypeHierarchyItem as LspTypeHierarchyItem,
+    CallHierarchyItem as LspCallHierarchyItem,
 } from 'vscode-languageserver/node';
 
 export interface Position {
@@ -46,15 +71,319 @@ export function toLspLocation(location: Location): LspLocation | null {
     };
 }
 
+export interface Hover {
+    contents: string;
+    range: Range;
+}
+
+export function toLspHover(hover: Hover): LspHover | null {
+    if (hover == null) {
+        return null;
+    }
+    return {
+        contents: { kind: MarkupKind.Markdown, value: hover.contents },
+        range: toLspRange(hover.range)
+    };
+}
+
+export interface TextEdit {
+    range: Range;
+    new_text: string;
+}
+
+export function toLspTextEdit(edit: TextEdit): LspTextEdit {
+    return {
+        range: toLspRange(edit.range),
+        newText: edit.new_text
+    };
+}
+
+export interface WorkspaceEdit {
+    changes: Map<string, TextEdit[]>;
+}
+
+export function toLspWorkspaceEdit(edit: WorkspaceEdit): LspWorkspaceEdit {
+    const changes: { [uri: string]: LspTextEdit[] } = {};
+    for (const [path, edits] of edit.changes) {
+        changes[pathToUri(path)] = edits.map(toLspTextEdit);
+    }
+    return { changes };
+}
+
+export const EXTRACT_TYPEDEF_COMMAND = 'thrift-ls.extractTypedef';
+export const MOVE_DEFINITION_COMMAND = 'thrift-ls.moveDefinition';
+// not in the protocol version of vscode-languageserver yet
+export const REFACTOR_MOVE_KIND = 'refactor.move';
+
+export interface CodeAction {
+    title: string;
+    kind: 'QuickFix' | 'RefactorExtract' | 'RefactorInline' | 'RefactorMove' | 'RefactorRewrite';
+    diagnostics: Error[];
+    edit: WorkspaceEdit | null;
+    command: CodeActionCommand | null;
+}
+
+export type CodeActionCommand =
+    | { ExtractTypedef: { path: string, range: Range } }
+    | { MoveDefinition: { path: string, position: Position } };
+
+export function toLspCodeAction(action: CodeAction): LspCodeAction {
+    return {
+        title: action.title,
+        kind: toLspCodeActionKind(action.kind),
+        diagnostics: action.diagnostics.map(toLspDiagnostic),
+        edit: action.edit ? toLspWorkspaceEdit(action.edit) : undefined,
+        command: action.command ? toLspCommand(action.title, action.command) : undefined
+    };
+}
+
+function toLspCommand(title: string, command: CodeActionCommand): LspCommand {
+    if ('ExtractTypedef' in command) {
+        const { path, range } = command.ExtractTypedef;
+        return { title, command: EXTRACT_TYPEDEF_COMMAND, arguments: [pathToUri(path), toLspRange(range)] };
+    }
+
+    const { path, position } = command.MoveDefinition;
+    return { title, command: MOVE_DEFINITION_COMMAND, arguments: [pathToUri(path), toLspPosition(position)] };
+}
+
+function toLspCodeActionKind(kind: CodeAction['kind']): string {
+    switch (kind) {
+        case 'QuickFix':
+            return LspCodeActionKind.QuickFix;
+        case 'RefactorExtract':
+            return LspCodeActionKind.RefactorExtract;
+        case 'RefactorInline':
+            return LspCodeActionKind.RefactorInline;
+        case 'RefactorMove':
+            return REFACTOR_MOVE_KIND;
+        case 'RefactorRewrite':
+            return LspCodeActionKind.RefactorRewrite;
+    }
+}
+
+export interface DocumentHighlight {
+    range: Range;
+    kind: keyof typeof LspDocumentHighlightKind;
+}
+
+export function toLspDocumentHighlight(highlight: DocumentHighlight): LspDocumentHighlight {
+    return {
+        range: toLspRange(highlight.range),
+        kind: LspDocumentHighlightKind[highlight.kind]
+    };
+}
+
+export interface FoldingRange {
+    start_line: number;
+    end_line: number;
+    kind: 'Comment' | 'Imports' | 'Region' | null;
+}
+
+export function toLspFoldingRange(range: FoldingRange): LspFoldingRange {
+    return {
+        startLine: range.start_line - 1,
+        endLine: range.end_line - 1,
+        kind: range.kind ? LspFoldingRangeKind[range.kind] : undefined
+    };
+}
+
+export interface SelectionRange {
+    range: Range;
+    parent: SelectionRange | null;
+}
+
+export function toLspSelectionRange(selectionRange: SelectionRange): LspSelectionRange {
+    return {
+        range: toLspRange(selectionRange.range),
+        parent: selectionRange.parent ? toLspSelectionRange(selectionRange.parent) : undefined
+    };
+}
+
+export interface CodeLens {
+    range: Range;
+    kind: 'References' | 'Subtypes';
+    title: string | null;
+    locations: Location[];
+}
+
+export function toLspCodeLens(codeLens: CodeLens, uri: string): LspCodeLens {
+    return {
+        range: toLspRange(codeLens.range),
+        command: codeLens.title === null ? undefined : {
+            title: codeLens.title,
+            // registered by the extension, opens the references peek
+            command: 'thrift-ls.showReferences',
+            arguments: [uri, toLspPosition(codeLens.range.start), codeLens.locations.map(toLspLocation)]
+        },
+        data: { uri, codeLens }
+    };
+}
+
+export interface InlayHint {
+    position: Position;
+    label: string;
+    padding_left: boolean;
+    padding_right: boolean;
+}
+
+export function toLspInlayHint(hint: InlayHint): LspInlayHint {
+    return {
+        position: toLspPosition(hint.position),
+        label: hint.label,
+        paddingLeft: hint.padding_left,
+        paddingRight: hint.padding_right
+    };
+}
+
+export interface SemanticTokens {
+    result_id: string;
+    data: number[];
+}
+
+export function toLspSemanticTokens(tokens: SemanticTokens): LspSemanticTokens {
+    return {
+        resultId: tokens.result_id,
+        data: tokens.data
+    };
+}
+
+export interface SemanticTokensEdit {
+    start: number;
+    delete_count: number;
+    data: number[];
+}
+
+export interface SemanticTokensDelta {
+    result_id: string;
+    edits: SemanticTokensEdit[];
+}
+
+export function toLspSemanticTokensDelta(delta: SemanticTokensDelta): LspSemanticTokensDelta {
+    return {
+        resultId: delta.result_id,
+        edits: delta.edits.map(edit => ({
+            start: edit.start,
+            deleteCount: edit.delete_count,
+            data: edit.data
+        }))
+    };
+}
+
+export interface DocumentSymbol {
+    name: string;
+    detail: string | null;
+    kind: keyof typeof LspSymbolKind;
+    range: Range;
+    selection_range: Range;
+    children: DocumentSymbol[];
+}
+
+export function toLspDocumentSymbol(symbol: DocumentSymbol): LspDocumentSymbol {
+    return {
+        name: symbol.name,
+        detail: symbol.detail ?? undefined,
+        kind: LspSymbolKind[symbol.kind],
+        range: toLspRange(symbol.range),
+        selectionRange: toLspRange(symbol.selection_range),
+        children: symbol.children.map(toLspDocumentSymbol)
+    };
+}
+
+export interface WorkspaceSymbol {
+    name: string;
+    kind: keyof typeof LspSymbolKind;
+    location: Location;
+    container_name: string | null;
+}
+
+export function toLspSymbolInformation(symbol: WorkspaceSymbol): LspSymbolInformation {
+    return {
+        name: symbol.name,
+        kind: LspSymbolKind[symbol.kind],
+        location: {
+            uri: pathToUri(symbol.location.path),
+            range: toLspRange(symbol.location.range)
+        },
+        containerName: symbol.container_name ?? undefined
+    };
+}
+
+export interface DocumentLink {
+    range: Range;
+    path: string;
+}
+
+export function toLspDocumentLink(link: DocumentLink): LspDocumentLink {
+    return {
+        range: toLspRange(link.range),
+        target: pathToUri(link.path)
+    };
+}
+
+export interface TypeHierarchyItem {
+    name: string;
+    kind: keyof typeof LspSymbolKind;
+    detail: string | null;
+    path: string;
+    range: Range;
+    selection_range: Range;
+}
+
+export function toLspTypeHierarchyItem(item: TypeHierarchyItem): LspTypeHierarchyItem {
+    return {
+        name: item.name,
+        kind: LspSymbolKind[item.kind],
+        detail: item.detail ?? undefined,
+        uri: pathToUri(item.path),
+        range: toLspRange(item.range),
+        selectionRange: toLspRange(item.selection_range)
+    };
+}
+
+export interface CallHierarchyItem {
+    name: string;
+    kind: keyof typeof LspSymbolKind;
+    detail: string | null;
+    path: string;
+    range: Range;
+    selection_range: Range;
+}
+
+export interface CallHierarchyIncomingCall {
+    from: CallHierarchyItem;
+    from_ranges: Range[];
+}
+
+export interface CallHierarchyOutgoingCall {
+    to: CallHierarchyItem;
+    from_ranges: Range[];
+}
+
+export function toLspCallHierarchyItem(item: CallHierarchyItem): LspCallHierarchyItem {
+    return {
+        name: item.name,
+        kind: LspSymbolKind[item.kind],
+        detail: item.detail ?? undefined,
+        uri: pathToUri(item.path),
+        range: toLspRange(item.range),
+        selectionRange: toLspRange(item.selection_range)
+    };
+}
+
 export interface Error {
     range: Range;
     message: string;
+    severity: 'Error' | 'Warning';
+    tags: 'Deprecated'[];
 }
 
 export function toLspDiagnostic(error: Error): LspDiagnostic {
     return {
         range: toLspRange(error.range),
-        message: error.message
+        message: error.message,
+        severity: LspDiagnosticSeverity[error.severity],
+        tags: error.tags.length > 0 ? error.tags.map(tag => LspDiagnosticTag[tag]) : undefined
     };
 }
 
@@ -82,3 +411,23 @@ export function readFile(path: string): ReadFileResult {
         return { content: '', error: 'Unknown error' };
     }
 }
+
+export function findThriftFiles(dir: string, isSkipped: (name: string) => boolean): string[] {
+    let entries: fs.Dirent[];
+    try {
+        entries = fs.readdirSync(dir, { withFileTypes: true });
+    } catch {
+        return [];
+    }
+
+    const files: string[] = [];
+    for (const entry of entries) {
+        const path = nodePath.join(dir, entry.name);
+        if (entry.isDirectory() && !isSkipped(entry.name)) {
+            files.push(...findThriftFiles(path, isSkipped));
+        } else if (entry.isFile() && entry.name.endsWith('.thrift')) {
+            files.push(path);
+        }
+    }
+    return files;
+}
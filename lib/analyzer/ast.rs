@@ -1,4 +1,9 @@
-use std::{any::Any, fmt::Debug, ops::Deref, rc::Rc};
+use std::{
+    any::Any,
+    fmt::{self, Debug, Display, Formatter},
+    ops::Deref,
+    rc::Rc,
+};
 
 use crate::analyzer::base::{Position, Range};
 
@@ -76,6 +81,18 @@ impl DefinitionNode {
             DefinitionNode::Service(node) => &node.identifier,
         }
     }
+
+    /// Get the annotations of the definition. Consts and typedefs have none.
+    pub fn ext(&self) -> Option<&ExtNode> {
+        match self {
+            DefinitionNode::Const(_) | DefinitionNode::Typedef(_) => None,
+            DefinitionNode::Enum(node) => node.ext.as_ref(),
+            DefinitionNode::Struct(node) => node.ext.as_ref(),
+            DefinitionNode::Union(node) => node.ext.as_ref(),
+            DefinitionNode::Exception(node) => node.ext.as_ref(),
+            DefinitionNode::Service(node) => node.ext.as_ref(),
+        }
+    }
 }
 
 /// An enum representing all possible field types.
@@ -102,23 +119,46 @@ impl Deref for FieldTypeNode {
     }
 }
 
+impl Display for FieldTypeNode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldTypeNode::Identifier(node) => write!(f, "{}", node.name),
+            FieldTypeNode::BaseType(node) => write!(f, "{}", node.name),
+            FieldTypeNode::MapType(node) => {
+                write!(f, "map<{}, {}>", node.key_type, node.value_type)
+            }
+            FieldTypeNode::SetType(node) => write!(f, "set<{}>", node.type_node),
+            FieldTypeNode::ListType(node) => write!(f, "list<{}>", node.type_node),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct DocumentNode {
     pub range: Range,
     pub headers: Vec<Rc<HeaderNode>>,
     pub definitions: Vec<Rc<DefinitionNode>>,
+    pub comments: Vec<CommentNode>,
+}
+
+#[derive(Debug)]
+pub struct CommentNode {
+    pub range: Range,
+    pub text: String, // including comment markers
 }
 
 #[derive(Debug)]
 pub struct IncludeNode {
     pub range: Range,
     pub literal: String,
+    pub literal_range: Range,
 }
 
 #[derive(Debug)]
 pub struct CppIncludeNode {
     pub range: Range,
     pub literal: String,
+    pub literal_range: Range,
 }
 
 #[derive(Debug)]
@@ -169,7 +209,7 @@ impl IdentifierNode {
             range: Range {
                 start: Position {
                     line: self.range.start.line,
-                    column: self.range.start.column + dot_index as u32,
+                    column: self.range.start.column + dot_index as u32 + 1,
                 },
                 end: self.range.end,
             },
@@ -219,7 +259,34 @@ pub struct ListTypeNode {
 #[derive(Debug)]
 pub struct ConstValueNode {
     pub range: Range,
-    pub value: String,
+    pub value: String, // the value as written, with nested values normalized
+    pub kind: ConstValueKind,
+}
+
+#[derive(Debug)]
+pub enum ConstValueKind {
+    Int,
+    Double,
+    Bool,
+    Literal,
+    Identifier(IdentifierNode), // a constant or an enum member
+    List(Vec<ConstValueNode>),
+    Map(Vec<(ConstValueNode, ConstValueNode)>),
+}
+
+impl ConstValueNode {
+    /// Get all identifiers referenced by the value, including nested ones.
+    pub fn identifiers(&self) -> Vec<&IdentifierNode> {
+        match &self.kind {
+            ConstValueKind::Identifier(identifier) => vec![identifier],
+            ConstValueKind::List(values) => values.iter().flat_map(|v| v.identifiers()).collect(),
+            ConstValueKind::Map(pairs) => pairs
+                .iter()
+                .flat_map(|(k, v)| k.identifiers().into_iter().chain(v.identifiers()))
+                .collect(),
+            _ => vec![],
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -240,11 +307,27 @@ pub struct EnumNode {
 #[derive(Debug)]
 pub struct EnumValueNode {
     pub range: Range,
-    pub name: String,
+    pub identifier: IdentifierNode,
     pub value: Option<i32>,
     pub ext: Option<ExtNode>,
 }
 
+impl EnumNode {
+    /// Get the number of each value. Like the Thrift compiler, a value without a number
+    /// gets the number of the previous value plus one, starting at 0.
+    pub fn numbers(&self) -> Vec<i32> {
+        let mut next = 0;
+        self.values
+            .iter()
+            .map(|value| {
+                let number = value.value.unwrap_or(next);
+                next = number.wrapping_add(1);
+                number
+            })
+            .collect()
+    }
+}
+
 #[derive(Debug)]
 pub struct StructNode {
     pub range: Range,
@@ -264,6 +347,25 @@ pub struct FieldNode {
     pub ext: Option<ExtNode>,
 }
 
+impl FieldNode {
+    /// Get the ID of each field in a field list. Like the Thrift compiler, a field
+    /// without an ID gets a negative ID, counting down from -1.
+    pub fn ids(fields: &[FieldNode]) -> Vec<i32> {
+        let mut next = -1;
+        fields
+            .iter()
+            .map(|field| match &field.field_id {
+                Some(field_id) => field_id.id,
+                None => {
+                    let id = next;
+                    next -= 1;
+                    id
+                }
+            })
+            .collect()
+    }
+}
+
 #[derive(Debug)]
 pub struct FieldIdNode {
     pub range: Range,
@@ -312,6 +414,17 @@ pub struct ExtNode {
     pub kv_pairs: Vec<(String, String)>,
 }
 
+impl ExtNode {
+    /// Get the value of an annotation. If the key is repeated, the last value wins.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.kv_pairs
+            .iter()
+            .rev()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
 impl Node for DocumentNode {
     fn as_any(&self) -> &dyn Any {
         self
@@ -477,7 +590,15 @@ impl Node for ConstValueNode {
     }
 
     fn children(&self) -> Vec<&dyn Node> {
-        Vec::new()
+        match &self.kind {
+            ConstValueKind::Identifier(identifier) => vec![identifier as &dyn Node],
+            ConstValueKind::List(values) => values.iter().map(|v| v as &dyn Node).collect(),
+            ConstValueKind::Map(pairs) => pairs
+                .iter()
+                .flat_map(|(k, v)| [k as &dyn Node, v as &dyn Node])
+                .collect(),
+            _ => Vec::new(),
+        }
     }
 }
 
@@ -523,6 +644,7 @@ impl Node for EnumValueNode {
 
     fn children(&self) -> Vec<&dyn Node> {
         let mut children = Vec::new();
+        children.push(&self.identifier as &dyn Node);
         if let Some(ext) = &self.ext {
             children.push(ext as &dyn Node);
         }
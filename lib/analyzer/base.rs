@@ -1,5 +1,7 @@
 //! Base types for the analyzer.
 
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
 /// Represents a location in a document.
@@ -38,4 +40,270 @@ pub struct Location {
 pub struct Error {
     pub range: Range,
     pub message: String,
+    pub severity: Severity,
+    pub tags: Vec<DiagnosticTag>,
+}
+
+/// Represents the severity of an error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// Represents extra information about an error, shown by the editor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DiagnosticTag {
+    /// The error reports a usage of a deprecated symbol.
+    Deprecated,
+}
+
+/// Represents hover information for a range in the document.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Hover {
+    /// Markdown content.
+    pub contents: String,
+    pub range: Range,
+}
+
+/// Represents a textual edit applicable to a document.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TextEdit {
+    pub range: Range,
+    pub new_text: String,
+}
+
+/// Represents changes to many documents, keyed by path.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorkspaceEdit {
+    pub changes: HashMap<String, Vec<TextEdit>>,
+}
+
+/// Represents the kind of a code action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CodeActionKind {
+    QuickFix,
+    RefactorExtract,
+    RefactorInline,
+    RefactorMove,
+    RefactorRewrite,
+}
+
+/// Represents a change that can be applied to the workspace, e.g. a quick fix.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeAction {
+    pub title: String,
+    pub kind: CodeActionKind,
+    /// Errors fixed by the action.
+    pub diagnostics: Vec<Error>,
+    pub edit: Option<WorkspaceEdit>,
+    /// Command computing the edit once the user gives what it needs, e.g. a name.
+    pub command: Option<CodeActionCommand>,
+}
+
+/// Represents a code action that needs input from the user.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum CodeActionCommand {
+    /// Extract the type in a range into a typedef, with a name given by the user.
+    ExtractTypedef { path: String, range: Range },
+    /// Move the definition whose name starts at a position to a file chosen by the user.
+    MoveDefinition { path: String, position: Position },
+}
+
+/// Represents the kind of a symbol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SymbolKind {
+    File,
+    Module,
+    Namespace,
+    Class,
+    Method,
+    Field,
+    Enum,
+    Interface,
+    Constant,
+    EnumMember,
+    Struct,
+    TypeParameter,
+}
+
+/// Represents a symbol in a document, with its children.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentSymbol {
+    pub name: String,
+    pub detail: Option<String>,
+    pub kind: SymbolKind,
+    /// Range enclosing the whole symbol.
+    pub range: Range,
+    /// Range of the symbol name.
+    pub selection_range: Range,
+    pub children: Vec<DocumentSymbol>,
+}
+
+/// Represents a symbol found by a workspace symbol search.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceSymbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub location: Location,
+    /// Dot-separated name of the enclosing symbols, e.g. `UserService.getProfile`.
+    pub container_name: Option<String>,
+}
+
+/// Represents a link from a range in a document to another file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentLink {
+    pub range: Range,
+    /// Path of the linked file.
+    pub path: String,
+}
+
+/// Represents an item of a type hierarchy.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TypeHierarchyItem {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub detail: Option<String>,
+    pub path: String,
+    /// Range enclosing the whole definition.
+    pub range: Range,
+    /// Range of the definition name.
+    pub selection_range: Range,
+}
+
+/// Represents an item of a call hierarchy. Thrift has no calls, so the hierarchy
+/// shows which types and functions use a type.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallHierarchyItem {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub detail: Option<String>,
+    pub path: String,
+    /// Range enclosing the whole definition.
+    pub range: Range,
+    /// Range of the definition name.
+    pub selection_range: Range,
+}
+
+/// Represents an item using a type.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallHierarchyIncomingCall {
+    pub from: CallHierarchyItem,
+    /// Ranges of the usages, in the file of `from`.
+    pub from_ranges: Vec<Range>,
+}
+
+/// Represents a type used by an item.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallHierarchyOutgoingCall {
+    pub to: CallHierarchyItem,
+    /// Ranges of the usages, in the file of the item using the type.
+    pub from_ranges: Vec<Range>,
+}
+
+/// Represents the kind of a document highlight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DocumentHighlightKind {
+    /// A textual occurrence.
+    Text,
+    /// A usage of a symbol.
+    Read,
+    /// The definition of a symbol.
+    Write,
+}
+
+/// Represents an occurrence of a symbol in a document.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentHighlight {
+    pub range: Range,
+    pub kind: DocumentHighlightKind,
+}
+
+/// Represents a selection range, with the range enclosing it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelectionRange {
+    pub range: Range,
+    pub parent: Option<Box<SelectionRange>>,
+}
+
+/// Represents what a code lens counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CodeLensKind {
+    /// References to a type.
+    References,
+    /// Services extending a service.
+    Subtypes,
+}
+
+/// Represents a code lens above a definition. The title and the locations are
+/// only computed when the code lens is resolved.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeLens {
+    /// Range of the definition name.
+    pub range: Range,
+    pub kind: CodeLensKind,
+    pub title: Option<String>,
+    pub locations: Vec<Location>,
+}
+
+/// Represents an inlay hint: text shown in a document that is not part of it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InlayHint {
+    pub position: Position,
+    pub label: String,
+    /// Whether a space is shown before the label.
+    pub padding_left: bool,
+    /// Whether a space is shown after the label.
+    pub padding_right: bool,
+}
+
+/// Represents the semantic tokens of a document, encoded as in LSP.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SemanticTokens {
+    /// Identifies the tokens in later delta requests.
+    pub result_id: String,
+    pub data: Vec<u32>,
+}
+
+/// Represents the changes of semantic tokens since a previous result.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SemanticTokensDelta {
+    pub result_id: String,
+    pub edits: Vec<SemanticTokensEdit>,
+}
+
+/// Represents an edit of encoded semantic tokens.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SemanticTokensEdit {
+    /// Offset in the encoded tokens.
+    pub start: u32,
+    pub delete_count: u32,
+    pub data: Vec<u32>,
+}
+
+/// Represents options for formatting a document.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormattingOptions {
+    /// Size of an indentation level in spaces.
+    pub tab_size: u32,
+    /// Whether to indent with spaces instead of tabs.
+    pub insert_spaces: bool,
+}
+
+/// Represents the kind of a folding range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FoldingRangeKind {
+    Comment,
+    Imports,
+    Region,
+}
+
+/// Represents a range of lines that can be folded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FoldingRange {
+    /// First line of the range (one-based).
+    pub start_line: u32,
+    /// Last line of the range (one-based).
+    pub end_line: u32,
+    pub kind: Option<FoldingRangeKind>,
 }
@@ -0,0 +1,781 @@
+//! Document formatting.
+
+use crate::analyzer::{
+    ast::{
+        CommentNode, ConstNode, ConstValueKind, ConstValueNode, DefinitionNode, DocumentNode,
+        EnumValueNode, ExtNode, FieldNode, FieldTypeNode, FunctionNode, HeaderNode,
+    },
+    base::{FormattingOptions, Position, Range},
+    scanner::Scanner,
+    token::TokenKind,
+};
+
+/// Formats a document: indentation, spacing, aligned fields, list separators and
+/// blank lines between definitions. Comments are kept as written, and so is a
+/// definition with a comment that can not be placed, e.g. inside a field.
+///
+/// Lines end like the first line of the source.
+///
+/// Returns `None` if the formatted document would not have the same tokens, apart
+/// from list separators.
+pub fn format_document(
+    document: &DocumentNode,
+    source: &[char],
+    options: &FormattingOptions,
+) -> Option<String> {
+    let line_ending = match source.iter().position(|c| *c == '\n') {
+        Some(offset) if offset > 0 && source[offset - 1] == '\r' => "\r\n",
+        _ => "\n",
+    };
+
+    let formatter = Formatter::new(document, source, options);
+    let mut text = formatter.document().join(line_ending);
+    if !text.is_empty() {
+        text.push_str(line_ending);
+    }
+
+    let formatted: Vec<char> = text.chars().collect();
+    if tokens(source) != tokens(&formatted) {
+        return None;
+    }
+
+    Some(text)
+}
+
+/// Returns the text of one level of indentation.
+pub fn indent_unit(options: &FormattingOptions) -> String {
+    if options.insert_spaces {
+        " ".repeat(options.tab_size as usize)
+    } else {
+        "\t".to_string()
+    }
+}
+
+/// What an entry is, which decides the blank lines around it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EntryKind {
+    Header,
+    /// A const or a typedef.
+    Declaration,
+    /// A definition with a body in braces.
+    Block,
+    /// A field, enum value or function.
+    Member,
+    Comment,
+}
+
+/// The formatted lines of a header, definition, member or comment, with its range
+/// in the source.
+#[derive(Debug)]
+struct Entry {
+    kind: EntryKind,
+    range: Range,
+    lines: Vec<String>,
+}
+
+struct Formatter<'a> {
+    document: &'a DocumentNode,
+    source: &'a [char],
+    // offset of the start of each line
+    line_offsets: Vec<usize>,
+    indent: String,
+}
+
+impl<'a> Formatter<'a> {
+    fn new(document: &'a DocumentNode, source: &'a [char], options: &FormattingOptions) -> Self {
+        let mut line_offsets = vec![0];
+        for (offset, c) in source.iter().enumerate() {
+            if *c == '\n' {
+                line_offsets.push(offset + 1);
+            }
+        }
+
+        Self {
+            document,
+            source,
+            line_offsets,
+            indent: indent_unit(options),
+        }
+    }
+
+    /// Formats the headers, definitions and comments of the document.
+    fn document(&self) -> Vec<String> {
+        let mut entries = Vec::new();
+        for header in &self.document.headers {
+            entries.push(Entry {
+                kind: EntryKind::Header,
+                range: header.range(),
+                lines: self.header(header),
+            });
+        }
+        for definition in &self.document.definitions {
+            let kind = match definition.as_ref() {
+                DefinitionNode::Const(_) | DefinitionNode::Typedef(_) => EntryKind::Declaration,
+                _ => EntryKind::Block,
+            };
+            entries.push(Entry {
+                kind,
+                range: definition.range(),
+                lines: self.definition(definition),
+            });
+        }
+
+        let comments = self
+            .document
+            .comments
+            .iter()
+            .filter(|c| !entries.iter().any(|e| inside(&e.range, c.range.start)))
+            .collect();
+        let entries = self.with_comments(entries, comments, 0);
+
+        layout(&entries, top_level_blank_lines)
+    }
+
+    fn header(&self, header: &HeaderNode) -> Vec<String> {
+        if self.has_comments(&header.range()) {
+            return self.verbatim(&header.range());
+        }
+
+        let line = match header {
+            HeaderNode::Include(node) => format!("include {}", self.text(&node.literal_range)),
+            HeaderNode::CppInclude(node) => {
+                format!("cpp_include {}", self.text(&node.literal_range))
+            }
+            HeaderNode::Namespace(node) => format!(
+                "namespace {} {}{}",
+                node.scope,
+                node.identifier.name,
+                self.ext(node.ext.as_ref())
+            ),
+        };
+
+        vec![line]
+    }
+
+    fn definition(&self, definition: &DefinitionNode) -> Vec<String> {
+        let lines = match definition {
+            DefinitionNode::Const(node) => self.const_definition(node),
+            DefinitionNode::Typedef(node) if !self.has_comments(&node.range) => {
+                Some(vec![format!(
+                    "typedef {} {}",
+                    self.field_type(&node.definition_type),
+                    node.identifier.name
+                )])
+            }
+            DefinitionNode::Typedef(_) => None,
+            DefinitionNode::Enum(node) => {
+                let members: Option<Vec<Entry>> =
+                    node.values.iter().map(|v| self.enum_value(v)).collect();
+                let head = format!("enum {}", node.identifier.name);
+                members.and_then(|members| {
+                    self.block(
+                        head,
+                        &node.identifier.range,
+                        &node.range,
+                        node.ext.as_ref(),
+                        members,
+                    )
+                })
+            }
+            DefinitionNode::Struct(node) => {
+                let head = format!("struct {}", node.identifier.name);
+                let members = self.fields(&node.fields, 1);
+                self.block(
+                    head,
+                    &node.identifier.range,
+                    &node.range,
+                    node.ext.as_ref(),
+                    members,
+                )
+            }
+            DefinitionNode::Union(node) => {
+                let head = format!("union {}", node.identifier.name);
+                let members = self.fields(&node.fields, 1);
+                self.block(
+                    head,
+                    &node.identifier.range,
+                    &node.range,
+                    node.ext.as_ref(),
+                    members,
+                )
+            }
+            DefinitionNode::Exception(node) => {
+                let head = format!("exception {}", node.identifier.name);
+                let members = self.fields(&node.fields, 1);
+                self.block(
+                    head,
+                    &node.identifier.range,
+                    &node.range,
+                    node.ext.as_ref(),
+                    members,
+                )
+            }
+            DefinitionNode::Service(node) => {
+                let mut head = format!("service {}", node.identifier.name);
+                let mut head_range = &node.identifier.range;
+                if let Some(extends) = &node.extends {
+                    head.push_str(&format!(" extends {}", extends.name));
+                    head_range = &extends.range;
+                }
+                let members = node.functions.iter().map(|f| self.function(f)).collect();
+                self.block(head, head_range, &node.range, node.ext.as_ref(), members)
+            }
+        };
+
+        lines.unwrap_or_else(|| self.verbatim(&definition.range()))
+    }
+
+    fn const_definition(&self, node: &ConstNode) -> Option<Vec<String>> {
+        if self.has_comments(&node.range) {
+            return None;
+        }
+
+        let text = format!(
+            "const {} {} = {}",
+            self.field_type(&node.field_type),
+            node.identifier.name,
+            self.const_value(&node.value, 0)
+        );
+        Some(text.split('\n').map(String::from).collect())
+    }
+
+    /// Formats a definition with a body in braces, e.g. `struct Work { ... } (ext)`.
+    /// `head` is the text before the `{`, ending at `head_range` in the source.
+    /// Returns `None` if a comment can not be placed.
+    fn block(
+        &self,
+        head: String,
+        head_range: &Range,
+        range: &Range,
+        ext: Option<&ExtNode>,
+        members: Vec<Entry>,
+    ) -> Option<Vec<String>> {
+        // `{` follows the head, and `}` comes before the annotations
+        let open = self.next_char(head_range.end)?;
+        let close_end = ext.map_or(range.end, |ext| ext.range.start);
+        let close = self.prev_char(close_end)?;
+        if self.source.get(open) != Some(&'{') || self.source.get(close) != Some(&'}') {
+            return None;
+        }
+        let open_line = self.line_of(open);
+
+        let mut head_line = format!("{} {{", head);
+        let mut head_comment = false;
+        let mut comments = Vec::new();
+        for comment in &self.document.comments {
+            if !inside(range, comment.range.start) {
+                continue;
+            }
+
+            let offset = self.offset(comment.range.start)?;
+            if offset < open
+                || offset > close
+                || members
+                    .iter()
+                    .any(|m| inside(&m.range, comment.range.start))
+            {
+                return None;
+            }
+
+            let before_members = members
+                .first()
+                .is_none_or(|m| comment.range.start < m.range.start);
+            if comment.range.start.line == open_line && before_members {
+                if comment.range.start.line != comment.range.end.line {
+                    return None;
+                }
+                head_line.push(' ');
+                head_line.push_str(&comment.text);
+                head_comment = true;
+            } else {
+                comments.push(comment);
+            }
+        }
+
+        let entries = self.with_comments(members, comments, 1);
+        let close_line = format!("}}{}", self.ext(ext));
+        if entries.is_empty() && head_comment {
+            return Some(vec![head_line, close_line]);
+        }
+        if entries.is_empty() {
+            return Some(vec![format!("{} {{}}{}", head, self.ext(ext))]);
+        }
+
+        let mut lines = vec![head_line];
+        lines.extend(layout(&entries, body_blank_lines));
+        lines.push(close_line);
+        Some(lines)
+    }
+
+    /// Formats fields, one per line with a trailing comma. The IDs, types and names of
+    /// fields without a blank line between them are aligned.
+    fn fields(&self, fields: &[FieldNode], depth: usize) -> Vec<Entry> {
+        let mut groups: Vec<&[FieldNode]> = Vec::new();
+        let mut start = 0;
+        for i in 1..fields.len() {
+            if self.has_blank_line(fields[i - 1].range.end.line, fields[i].range.start.line) {
+                groups.push(&fields[start..i]);
+                start = i;
+            }
+        }
+        if start < fields.len() {
+            groups.push(&fields[start..]);
+        }
+
+        let mut entries = Vec::new();
+        for group in groups {
+            let ids: Vec<String> = group
+                .iter()
+                .map(|field| match &field.field_id {
+                    Some(field_id) => format!("{}:", field_id.id),
+                    None => String::new(),
+                })
+                .collect();
+            let types: Vec<String> = group
+                .iter()
+                .map(|field| match &field.field_req {
+                    Some(req) => format!("{} {}", req, self.field_type(&field.field_type)),
+                    None => self.field_type(&field.field_type),
+                })
+                .collect();
+            let id_width = ids.iter().map(|id| id.chars().count()).max().unwrap_or(0);
+            let type_width = types.iter().map(|t| t.chars().count()).max().unwrap_or(0);
+
+            for ((field, id), field_type) in group.iter().zip(ids).zip(types) {
+                let mut line = self.indent(depth);
+                if id_width > 0 {
+                    line.push_str(&format!("{:>width$} ", id, width = id_width));
+                }
+                line.push_str(&format!(
+                    "{:<width$} {},",
+                    field_type,
+                    self.field_rest(field, depth),
+                    width = type_width
+                ));
+
+                entries.push(Entry {
+                    kind: EntryKind::Member,
+                    range: field.range.clone(),
+                    lines: line.split('\n').map(String::from).collect(),
+                });
+            }
+        }
+
+        entries
+    }
+
+    /// Formats a field on a single line, as in argument lists.
+    fn field(&self, field: &FieldNode, depth: usize) -> String {
+        let mut text = String::new();
+        if let Some(field_id) = &field.field_id {
+            text.push_str(&format!("{}: ", field_id.id));
+        }
+        if let Some(req) = &field.field_req {
+            text.push_str(&format!("{} ", req));
+        }
+        text.push_str(&format!(
+            "{} {}",
+            self.field_type(&field.field_type),
+            self.field_rest(field, depth)
+        ));
+
+        text
+    }
+
+    /// Formats the name of a field, with its default value and annotations.
+    fn field_rest(&self, field: &FieldNode, depth: usize) -> String {
+        let mut text = field.identifier.name.clone();
+        if let Some(default_value) = &field.default_value {
+            text.push_str(&format!(" = {}", self.const_value(default_value, depth)));
+        }
+        text.push_str(&self.ext(field.ext.as_ref()));
+
+        text
+    }
+
+    /// Returns `None` if the value is not written as the parsed number, e.g. `0x10`.
+    fn enum_value(&self, value: &EnumValueNode) -> Option<Entry> {
+        let mut line = format!("{}{}", self.indent(1), value.identifier.name);
+        if let Some(number) = value.value {
+            // the number as written, which the scanner may have split, e.g. `0x10`
+            let start = self.offset(value.identifier.range.end)?;
+            let written: String = self.source[start..]
+                .iter()
+                .skip_while(|c| c.is_whitespace() || **c == '=')
+                .take_while(|c| !c.is_whitespace() && !",;(){}/#".contains(**c))
+                .collect();
+            if written != number.to_string() {
+                return None;
+            }
+            line.push_str(&format!(" = {}", number));
+        }
+        line.push_str(&self.ext(value.ext.as_ref()));
+        line.push(',');
+
+        Some(Entry {
+            kind: EntryKind::Member,
+            range: value.range.clone(),
+            lines: vec![line],
+        })
+    }
+
+    fn function(&self, function: &FunctionNode) -> Entry {
+        let function_type = match &function.function_type {
+            Some(function_type) => self.field_type(function_type),
+            None => "void".to_string(),
+        };
+        let oneway = if function.is_oneway { "oneway " } else { "" };
+        let mut lines = vec![format!(
+            "{}{}{} {}",
+            self.indent(1),
+            oneway,
+            function_type,
+            function.identifier.name
+        )];
+
+        self.field_list(&mut lines, &function.fields, 1);
+        if let Some(throws) = &function.throws {
+            push_str(&mut lines, " throws ");
+            self.field_list(&mut lines, throws, 1);
+        }
+        push_str(&mut lines, &self.ext(function.ext.as_ref()));
+        push_str(&mut lines, ",");
+
+        Entry {
+            kind: EntryKind::Member,
+            range: function.range.clone(),
+            lines,
+        }
+    }
+
+    /// Appends a field list in parentheses to the last line. The fields are put on
+    /// their own lines if they span several lines in the source.
+    fn field_list(&self, lines: &mut Vec<String>, fields: &[FieldNode], depth: usize) {
+        let multiline = match (fields.first(), fields.last()) {
+            (Some(first), Some(last)) => first.range.start.line != last.range.end.line,
+            _ => false,
+        };
+
+        if !multiline {
+            let fields: Vec<String> = fields.iter().map(|f| self.field(f, depth)).collect();
+            push_str(lines, &format!("({})", fields.join(", ")));
+            return;
+        }
+
+        push_str(lines, "(");
+        let entries = self.fields(fields, depth + 1);
+        lines.extend(layout(&entries, body_blank_lines));
+        lines.push(format!("{})", self.indent(depth)));
+    }
+
+    fn field_type(&self, field_type: &FieldTypeNode) -> String {
+        let cpp_type = |cpp_type: &Option<String>| match cpp_type {
+            Some(cpp_type) => format!(" cpp_type {}", cpp_type),
+            None => String::new(),
+        };
+
+        match field_type {
+            FieldTypeNode::Identifier(node) => node.name.clone(),
+            FieldTypeNode::BaseType(node) => node.name.clone(),
+            FieldTypeNode::MapType(node) => format!(
+                "map{}<{}, {}>",
+                cpp_type(&node.cpp_type),
+                self.field_type(&node.key_type),
+                self.field_type(&node.value_type)
+            ),
+            FieldTypeNode::SetType(node) => format!(
+                "set{}<{}>",
+                cpp_type(&node.cpp_type),
+                self.field_type(&node.type_node)
+            ),
+            FieldTypeNode::ListType(node) => format!(
+                "list{}<{}>",
+                cpp_type(&node.cpp_type),
+                self.field_type(&node.type_node)
+            ),
+        }
+    }
+
+    /// Formats a constant value. Lists and maps spanning several lines in the source
+    /// get one item per line, indented one level deeper than `depth`.
+    fn const_value(&self, value: &ConstValueNode, depth: usize) -> String {
+        let (open, close, items) = match &value.kind {
+            ConstValueKind::Identifier(identifier) => return identifier.name.clone(),
+            ConstValueKind::List(values) => {
+                let items = values
+                    .iter()
+                    .map(|v| self.const_value(v, depth + 1))
+                    .collect();
+                ("[", "]", items)
+            }
+            ConstValueKind::Map(pairs) => {
+                let items = pairs
+                    .iter()
+                    .map(|(k, v)| {
+                        format!(
+                            "{}: {}",
+                            self.const_value(k, depth + 1),
+                            self.const_value(v, depth + 1)
+                        )
+                    })
+                    .collect();
+                ("{", "}", items)
+            }
+            // numbers and literals as written
+            _ => return self.text(&value.range),
+        };
+
+        let items: Vec<String> = items;
+        if items.is_empty() || value.range.start.line == value.range.end.line {
+            return format!("{}{}{}", open, items.join(", "), close);
+        }
+
+        let mut text = open.to_string();
+        for item in items {
+            text.push_str(&format!("\n{}{},", self.indent(depth + 1), item));
+        }
+        text.push_str(&format!("\n{}{}", self.indent(depth), close));
+
+        text
+    }
+
+    /// Formats annotations, e.g. ` (cpp.type = "Foo", deprecated = "")`. The literals
+    /// are kept as written.
+    fn ext(&self, ext: Option<&ExtNode>) -> String {
+        let ext = match ext {
+            Some(ext) => ext,
+            None => return String::new(),
+        };
+
+        let text: Vec<char> = self.text(&ext.range).chars().collect();
+        let mut scanner = Scanner::new(&text);
+        let mut offset = 0;
+        let mut position = Position { line: 1, column: 1 };
+        let mut key = None;
+        let mut pairs = Vec::new();
+        loop {
+            let (token, _) = scanner.scan();
+            match token.kind {
+                TokenKind::Eof => break,
+                TokenKind::Identifier(name) => key = Some(name),
+                TokenKind::Literal(value) => {
+                    // find the offset of the literal, counting from the previous one
+                    while position < token.position && offset < text.len() {
+                        if text[offset] == '\n' {
+                            position.line += 1;
+                            position.column = 1;
+                        } else {
+                            position.column += 1;
+                        }
+                        offset += 1;
+                    }
+                    let length = value.chars().count() + 2;
+                    let literal: String = text.iter().skip(offset).take(length).collect();
+                    pairs.push(format!("{} = {}", key.take().unwrap_or_default(), literal));
+                }
+                _ => {}
+            }
+        }
+
+        format!(" ({})", pairs.join(", "))
+    }
+
+    /// Merges comments into entries, in source order. A comment on the line where
+    /// an entry ends is appended to it; other comments become entries of their own.
+    fn with_comments(
+        &self,
+        mut entries: Vec<Entry>,
+        comments: Vec<&CommentNode>,
+        depth: usize,
+    ) -> Vec<Entry> {
+        entries.extend(comments.into_iter().map(|comment| {
+            Entry {
+                kind: EntryKind::Comment,
+                range: comment.range.clone(),
+                lines: comment
+                    .text
+                    .split('\n')
+                    .map(|line| line.trim_end_matches('\r').to_string())
+                    .collect(),
+            }
+        }));
+        entries.sort_by_key(|a| a.range.start);
+
+        let mut result: Vec<Entry> = Vec::new();
+        for mut entry in entries {
+            if entry.kind == EntryKind::Comment {
+                if let Some(prev) = result.last_mut() {
+                    if prev.range.end.line == entry.range.start.line {
+                        let mut lines = entry.lines.into_iter();
+                        if let (Some(last), Some(first)) = (prev.lines.last_mut(), lines.next()) {
+                            last.push(' ');
+                            last.push_str(&first);
+                        }
+                        prev.lines.extend(lines);
+                        prev.range.end = entry.range.end;
+                        continue;
+                    }
+                }
+                entry.lines[0].insert_str(0, &self.indent(depth));
+            }
+            result.push(entry);
+        }
+
+        result
+    }
+
+    fn indent(&self, depth: usize) -> String {
+        self.indent.repeat(depth)
+    }
+
+    /// Returns the lines of a range as written.
+    fn verbatim(&self, range: &Range) -> Vec<String> {
+        self.text(range)
+            .split('\n')
+            .map(|line| line.trim_end_matches('\r').to_string())
+            .collect()
+    }
+
+    fn text(&self, range: &Range) -> String {
+        match (self.offset(range.start), self.offset(range.end)) {
+            (Some(start), Some(end)) if start <= end => self.source[start..end].iter().collect(),
+            _ => String::new(),
+        }
+    }
+
+    fn offset(&self, pos: Position) -> Option<usize> {
+        let line_offset = *self.line_offsets.get((pos.line as usize).checked_sub(1)?)?;
+        let offset = line_offset + (pos.column as usize).checked_sub(1)?;
+        (offset <= self.source.len()).then_some(offset)
+    }
+
+    /// Returns the line (one-based) of an offset.
+    fn line_of(&self, offset: usize) -> u32 {
+        self.line_offsets.partition_point(|o| *o <= offset) as u32
+    }
+
+    /// Returns the offset of the first non-whitespace character at or after a position.
+    fn next_char(&self, pos: Position) -> Option<usize> {
+        let start = self.offset(pos)?;
+        (start..self.source.len()).find(|&i| !self.source[i].is_whitespace())
+    }
+
+    /// Returns the offset of the last non-whitespace character before a position.
+    fn prev_char(&self, pos: Position) -> Option<usize> {
+        let end = self.offset(pos)?;
+        (0..end).rev().find(|&i| !self.source[i].is_whitespace())
+    }
+
+    fn has_comments(&self, range: &Range) -> bool {
+        self.document
+            .comments
+            .iter()
+            .any(|c| inside(range, c.range.start))
+    }
+
+    /// Returns true if there is a blank line strictly between two lines (one-based).
+    fn has_blank_line(&self, after: u32, before: u32) -> bool {
+        (after + 1..before).any(|line| {
+            let start = self.line_offsets[line as usize - 1];
+            let end = self
+                .line_offsets
+                .get(line as usize)
+                .copied()
+                .unwrap_or(self.source.len());
+            self.source[start..end].iter().all(|c| c.is_whitespace())
+        })
+    }
+}
+
+/// Returns true if a position is strictly inside a range.
+fn inside(range: &Range, pos: Position) -> bool {
+    range.start < pos && pos < range.end
+}
+
+/// Appends text to the last line.
+fn push_str(lines: &mut [String], text: &str) {
+    if let Some(last) = lines.last_mut() {
+        last.push_str(text);
+    }
+}
+
+/// Puts entries one after another, with the number of blank lines before each entry
+/// given by `blank_lines`.
+fn layout(entries: &[Entry], blank_lines: fn(&[Entry], usize) -> usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for (i, entry) in entries.iter().enumerate() {
+        if i > 0 {
+            lines.extend(std::iter::repeat_n(String::new(), blank_lines(entries, i)));
+        }
+        lines.extend(entry.lines.iter().cloned());
+    }
+
+    lines
+}
+
+/// Returns the number of blank lines between two entries in the source, at most one.
+fn original_blank_lines(prev: &Entry, next: &Entry) -> usize {
+    next.range
+        .start
+        .line
+        .saturating_sub(prev.range.end.line + 1)
+        .min(1) as usize
+}
+
+/// Blank lines before a top level entry: one around definitions with a body and
+/// between different kinds of entries, and the blank line of the source, if any,
+/// elsewhere. Comments right above an entry belong to it.
+fn top_level_blank_lines(entries: &[Entry], index: usize) -> usize {
+    let (prev, next) = (&entries[index - 1], &entries[index]);
+    let original = original_blank_lines(prev, next);
+    if prev.kind == EntryKind::Comment {
+        return original;
+    }
+
+    let mut owner = index;
+    while entries[owner].kind == EntryKind::Comment {
+        match entries.get(owner + 1) {
+            Some(entry) if original_blank_lines(&entries[owner], entry) == 0 => owner += 1,
+            _ => break,
+        }
+    }
+
+    match (prev.kind, entries[owner].kind) {
+        (_, EntryKind::Comment)
+        | (EntryKind::Header, EntryKind::Header)
+        | (EntryKind::Declaration, EntryKind::Declaration) => original,
+        _ => 1,
+    }
+}
+
+/// Blank lines before a member or a comment in a body: the blank line of the source, if any.
+fn body_blank_lines(entries: &[Entry], index: usize) -> usize {
+    original_blank_lines(&entries[index - 1], &entries[index])
+}
+
+/// Returns the tokens of a text without list separators, to check that formatting
+/// does not change anything else.
+fn tokens(text: &[char]) -> Vec<TokenKind> {
+    let mut scanner = Scanner::new(text);
+    let mut tokens = Vec::new();
+    loop {
+        let (token, _) = scanner.scan();
+        match token.kind {
+            TokenKind::Eof => break,
+            TokenKind::ListSeparator(_) => {}
+            // line comments end with the line break
+            TokenKind::Comment(text) => {
+                tokens.push(TokenKind::Comment(text.trim_end().to_string()));
+            }
+            TokenKind::PoundComment(text) => {
+                tokens.push(TokenKind::PoundComment(text.trim_end().to_string()));
+            }
+            TokenKind::BlockComment(text) => {
+                tokens.push(TokenKind::BlockComment(text.replace('\r', "")));
+            }
+            kind => tokens.push(kind),
+        }
+    }
+
+    tokens
+}
@@ -0,0 +1,238 @@
+//! Workspace symbol index.
+
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use crate::analyzer::{
+    ast::DocumentNode,
+    base::{DocumentSymbol, Location, WorkspaceSymbol},
+    definition_symbol,
+    symbol::SymbolTable,
+};
+
+/// Index of the ASTs and symbols of every known file, including files that are not open.
+#[derive(Debug, Default)]
+pub struct WorkspaceIndex {
+    documents: HashMap<String, Rc<DocumentNode>>,
+    symbols: HashMap<String, Vec<WorkspaceSymbol>>,
+    symbol_tables: RefCell<HashMap<String, Rc<SymbolTable>>>,
+}
+
+impl WorkspaceIndex {
+    /// Create a new empty index.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the AST and symbols of a file.
+    pub fn update(&mut self, path: &str, document: Rc<DocumentNode>) {
+        let mut symbols = Vec::new();
+        for definition in &document.definitions {
+            flatten(path, definition_symbol(definition), None, &mut symbols);
+        }
+
+        self.symbols.insert(path.to_string(), symbols);
+        self.documents.insert(path.to_string(), document);
+        self.invalidate_symbol_tables();
+    }
+
+    /// Remove a file.
+    pub fn remove(&mut self, path: &str) {
+        self.symbols.remove(path);
+        self.documents.remove(path);
+        self.invalidate_symbol_tables();
+    }
+
+    /// Check if a file is indexed.
+    pub fn contains(&self, path: &str) -> bool {
+        self.documents.contains_key(path)
+    }
+
+    /// Get the AST of an indexed file.
+    pub fn document(&self, path: &str) -> Option<&Rc<DocumentNode>> {
+        self.documents.get(path)
+    }
+
+    /// Get the ASTs of all indexed files.
+    pub fn documents(&self) -> &HashMap<String, Rc<DocumentNode>> {
+        &self.documents
+    }
+
+    /// Get the symbol table of an indexed file, building it with `build` unless
+    /// it is cached.
+    pub fn symbol_table(
+        &self,
+        path: &str,
+        build: impl FnOnce(&DocumentNode) -> SymbolTable,
+    ) -> Option<Rc<SymbolTable>> {
+        if let Some(symbol_table) = self.symbol_tables.borrow().get(path) {
+            return Some(symbol_table.clone());
+        }
+
+        let document = self.documents.get(path)?;
+        let symbol_table = Rc::new(build(document));
+        self.symbol_tables
+            .borrow_mut()
+            .insert(path.to_string(), symbol_table.clone());
+        Some(symbol_table)
+    }
+
+    /// Drop all cached symbol tables, since a table also holds the tables of
+    /// the files it includes.
+    fn invalidate_symbol_tables(&mut self) {
+        self.symbol_tables.get_mut().clear();
+    }
+
+    /// Search symbols matching a query, best matches first.
+    ///
+    /// A query containing a dot is matched against the qualified name,
+    /// e.g. `UserSer.getProf` matches `UserService.getProfile`.
+    pub fn search(&self, query: &str) -> Vec<WorkspaceSymbol> {
+        let mut matches: Vec<(i64, &WorkspaceSymbol)> = self
+            .symbols
+            .values()
+            .flatten()
+            .filter_map(|symbol| {
+                let score = if query.contains('.') {
+                    let qualified = match &symbol.container_name {
+                        Some(container) => format!("{}.{}", container, symbol.name),
+                        None => symbol.name.clone(),
+                    };
+                    fuzzy_score(query, &qualified)
+                } else {
+                    fuzzy_score(query, &symbol.name)
+                };
+                score.map(|score| (score, symbol))
+            })
+            .collect();
+
+        matches.sort_by(|(a_score, a), (b_score, b)| {
+            b_score
+                .cmp(a_score)
+                .then_with(|| a.name.cmp(&b.name))
+                .then_with(|| a.location.path.cmp(&b.location.path))
+                .then_with(|| a.location.range.cmp(&b.location.range))
+        });
+
+        matches
+            .into_iter()
+            .map(|(_, symbol)| symbol.clone())
+            .collect()
+    }
+}
+
+/// Flatten a document symbol and its children, using qualified names as container names.
+fn flatten(
+    path: &str,
+    symbol: DocumentSymbol,
+    container_name: Option<&str>,
+    result: &mut Vec<WorkspaceSymbol>,
+) {
+    let qualified_name = match container_name {
+        Some(container_name) => format!("{}.{}", container_name, symbol.name),
+        None => symbol.name.clone(),
+    };
+
+    result.push(WorkspaceSymbol {
+        name: symbol.name,
+        kind: symbol.kind,
+        location: Location {
+            path: path.to_string(),
+            range: symbol.selection_range,
+        },
+        container_name: container_name.map(|name| name.to_string()),
+    });
+
+    for child in symbol.children {
+        flatten(path, child, Some(&qualified_name), result);
+    }
+}
+
+/// Returns the fuzzy match score of a candidate, or `None` if the query is not
+/// a (case-insensitive) subsequence of it.
+///
+/// Consecutive matches and matches at word starts (first character, after `_`
+/// or `.`, or an uppercase letter) score higher; an exact prefix is best.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    if query.is_empty() {
+        return Some(0);
+    }
+
+    let query: Vec<char> = query.chars().map(lowercase).collect();
+    let candidate: Vec<char> = candidate.chars().collect();
+
+    let mut score = 0;
+    let mut query_index = 0;
+    let mut previous_match: Option<usize> = None;
+
+    for (i, c) in candidate.iter().enumerate() {
+        if query_index == query.len() {
+            break;
+        }
+        if lowercase(*c) != query[query_index] {
+            continue;
+        }
+
+        score += 1;
+        if previous_match.is_some_and(|previous| previous + 1 == i) {
+            score += 5;
+        }
+
+        let word_start = match i.checked_sub(1).map(|j| candidate[j]) {
+            None => true,
+            Some(previous) => {
+                previous == '_' || previous == '.' || (c.is_uppercase() && !previous.is_uppercase())
+            }
+        };
+        if word_start {
+            score += 10;
+        }
+
+        if let Some(previous) = previous_match {
+            score -= (i - previous - 1).min(3) as i64;
+        }
+
+        previous_match = Some(i);
+        query_index += 1;
+    }
+
+    if query_index < query.len() {
+        return None;
+    }
+
+    if candidate
+        .iter()
+        .map(|c| lowercase(*c))
+        .take(query.len())
+        .eq(query.iter().copied())
+    {
+        score += 20;
+    }
+
+    // prefer shorter candidates among equal matches
+    Some(score * 100 - candidate.len() as i64)
+}
+
+fn lowercase(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_score_ranking() {
+        assert!(fuzzy_score("UserProf", "UserProfile").is_some());
+        assert!(fuzzy_score("usrprf", "UserProfile").is_some());
+        assert!(fuzzy_score("xyz", "UserProfile").is_none());
+
+        let prefix = fuzzy_score("user", "UserProfile").unwrap();
+        let word = fuzzy_score("user", "GetUser").unwrap();
+        let scattered = fuzzy_score("user", "BusinessOwner").unwrap();
+        assert!(prefix > word);
+        assert!(word > scattered);
+
+        // shorter names win ties
+        assert!(fuzzy_score("User", "User").unwrap() > fuzzy_score("User", "UserProfile").unwrap());
+    }
+}
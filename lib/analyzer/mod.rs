@@ -2,6 +2,8 @@
 
 pub mod ast;
 pub mod base;
+pub mod format;
+pub mod index;
 pub mod macros;
 pub mod parser;
 pub mod scanner;
@@ -9,35 +11,70 @@ pub mod symbol;
 pub mod token;
 
 use std::{
+    any::Any,
     collections::{HashMap, HashSet},
     fs, io,
     path::{Path, PathBuf},
+    ptr,
     rc::Rc,
 };
 
-use ast::{DefinitionNode, FieldNode, FieldTypeNode, FunctionNode, HeaderNode};
-use base::{Location, Position};
+use ast::{
+    CommentNode, ConstValueKind, ConstValueNode, DefinitionNode, EnumNode, EnumValueNode,
+    ExceptionNode, ExtNode, FieldNode, FieldTypeNode, FunctionNode, HeaderNode, ServiceNode,
+    StructNode, UnionNode,
+};
+use base::{
+    CallHierarchyIncomingCall, CallHierarchyItem, CallHierarchyOutgoingCall, CodeAction,
+    CodeActionCommand, CodeActionKind, CodeLens, CodeLensKind, DiagnosticTag, DocumentHighlight,
+    DocumentHighlightKind, DocumentLink, DocumentSymbol, FoldingRange, FoldingRangeKind,
+    FormattingOptions, Hover, InlayHint, Location, Position, Range, SelectionRange, SemanticTokens,
+    SemanticTokensDelta, SemanticTokensEdit, Severity, SymbolKind, TextEdit, TypeHierarchyItem,
+    WorkspaceEdit, WorkspaceSymbol,
+};
+use index::WorkspaceIndex;
 
 use crate::analyzer::{
     ast::{DocumentNode, IdentifierNode, Node},
     base::Error,
     parser::Parser,
-    symbol::SymbolTable,
+    scanner::Scanner,
+    symbol::{SymbolTable, ValueDefinition},
+    token::{Token, TokenKind},
 };
 
 /// Analyzer for Thrift files.
 pub struct Analyzer {
     documents: HashMap<String, Vec<char>>,
 
+    sources: HashMap<String, Vec<char>>,
     document_nodes: HashMap<String, Rc<DocumentNode>>,
     symbol_tables: HashMap<String, Rc<SymbolTable>>,
 
     errors: HashMap<String, Vec<Error>>,
-    semantic_tokens: HashMap<String, Vec<u32>>,
+    semantic_tokens: HashMap<String, SemanticTokens>,
+    // the tokens last returned to the client, for delta requests
+    previous_semantic_tokens: HashMap<String, SemanticTokens>,
+    semantic_tokens_result_id: u64,
+
+    index: WorkspaceIndex,
+
+    // key of the annotation marking a deprecated symbol, e.g. `(deprecated = "use X")`
+    deprecation_annotation: String,
 
     pub(crate) wasm_read_file: Option<Box<dyn Fn(String) -> io::Result<String>>>,
 }
 
+/// A symbol that can be renamed.
+enum RenameTarget<'a> {
+    /// A definition, renamed together with all its usages.
+    Definition(String, Rc<DefinitionNode>),
+    /// A field, renamed within its struct, union, exception or argument list.
+    Field(&'a IdentifierNode, &'a [FieldNode]),
+    /// A function, renamed within its service.
+    Function(&'a IdentifierNode, &'a [FunctionNode]),
+}
+
 const KEYWORDS: &[&str] = &[
     "namespace",
     "include",
@@ -62,15 +99,92 @@ const KEYWORDS: &[&str] = &[
     "service",
 ];
 
+/// Semantic token types, in the order of the legend.
+#[derive(Debug, Clone, Copy)]
+enum SemanticTokenType {
+    Type,
+    Function,
+    Namespace,
+    Struct,
+    Enum,
+    EnumMember,
+    Property,
+    Parameter,
+    Keyword,
+    String,
+    Number,
+    Comment,
+    Decorator,
+    Interface,
+    Variable,
+}
+
+const SEMANTIC_TOKEN_TYPES: &[&str] = &[
+    "type",
+    "function",
+    "namespace",
+    "struct",
+    "enum",
+    "enumMember",
+    "property",
+    "parameter",
+    "keyword",
+    "string",
+    "number",
+    "comment",
+    "decorator",
+    "interface",
+    "variable",
+];
+
+// semantic token modifiers, as bits in the order of the legend
+const SEMANTIC_TOKEN_MODIFIERS: &[&str] = &["declaration", "definition", "readonly", "deprecated"];
+const DECLARATION: u32 = 1;
+const DEFINITION: u32 = 1 << 1;
+const READONLY: u32 = 1 << 2;
+const DEPRECATED: u32 = 1 << 3;
+
+/// A semantic token on a single line.
+struct SemanticToken {
+    start: Position,
+    length: u32,
+    token_type: SemanticTokenType,
+    modifiers: u32,
+}
+
+/// Default key of the annotation marking a deprecated symbol.
+const DEFAULT_DEPRECATION_ANNOTATION: &str = "deprecated";
+
+/// Directories skipped when indexing a workspace: dependencies and build outputs.
+const SKIPPED_DIRECTORIES: &[&str] = &["node_modules", "target", "build", "dist"];
+
+/// Maximum number of typedefs followed when resolving a type, which stops cyclic typedefs.
+const MAX_TYPEDEF_DEPTH: usize = 32;
+
+/// Maximum number of line pairs compared when looking for the common lines of two
+/// texts. Longer texts are compared by their common first and last lines only.
+const MAX_LINE_PAIRS: usize = 1 << 22;
+
+impl Default for Analyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Analyzer {
     /// Create a new analyzer.
     pub fn new() -> Self {
         Self {
             documents: HashMap::new(),
+            sources: HashMap::new(),
             document_nodes: HashMap::new(),
             symbol_tables: HashMap::new(),
             errors: HashMap::new(),
             semantic_tokens: HashMap::new(),
+            previous_semantic_tokens: HashMap::new(),
+            semantic_tokens_result_id: 0,
+            index: WorkspaceIndex::new(),
+            deprecation_annotation: DEFAULT_DEPRECATION_ANNOTATION.to_string(),
             wasm_read_file: None,
         }
     }
@@ -85,10 +199,69 @@ impl Analyzer {
     /// Remove a document.
     pub fn remove_document(&mut self, path: &str) {
         self.documents.remove(path);
+        self.sources.remove(path);
         self.document_nodes.remove(path);
         self.symbol_tables.remove(path);
         self.errors.remove(path);
         self.semantic_tokens.remove(path);
+        self.previous_semantic_tokens.remove(path);
+
+        // fall back to the content on disk
+        if self.index.contains(path) {
+            self.index_file(path);
+        }
+    }
+
+    /// Set the key of the annotation marking a deprecated symbol, and analyze the
+    /// opened documents again.
+    pub fn set_deprecation_annotation(&mut self, key: &str) {
+        if self.deprecation_annotation == key {
+            return;
+        }
+
+        self.deprecation_annotation = key.to_string();
+        let paths: Vec<String> = self.documents.keys().cloned().collect();
+        for path in paths {
+            self.analyze(&path);
+        }
+    }
+
+    /// Index all Thrift files under a directory, so their symbols can be
+    /// searched without opening them.
+    pub fn index_workspace(&mut self, root: &str) {
+        let mut paths = Vec::new();
+        find_thrift_files(Path::new(root), &mut paths);
+        for path in paths {
+            self.index_file(&path);
+        }
+    }
+
+    /// Index a single file, reading it from disk unless it is already parsed.
+    pub fn index_file(&mut self, path: &str) {
+        if let Some(document_node) = self.document_nodes.get(path) {
+            self.index.update(path, document_node.clone());
+            return;
+        }
+
+        match self.read_file(path) {
+            Ok(content) => {
+                let content: Vec<char> = content.chars().collect();
+                let (document_node, _) = Parser::new(&content).parse();
+                self.index.update(path, Rc::new(document_node));
+            }
+            Err(_) => self.index.remove(path),
+        }
+    }
+
+    /// Check if a directory is skipped when indexing a workspace, for clients that
+    /// walk the workspace themselves.
+    pub fn is_skipped_directory(&self, name: &str) -> bool {
+        is_skipped_directory(name)
+    }
+
+    /// Search symbols of all indexed files, best fuzzy matches first.
+    pub fn workspace_symbols(&self, query: &str) -> Vec<WorkspaceSymbol> {
+        self.index.search(query)
     }
 
     /// Get the errors for all files.
@@ -96,26 +269,84 @@ impl Analyzer {
         &self.errors
     }
 
-    /// Get semantic tokens for a specific file.
-    pub fn semantic_tokens(&self, path: &str) -> Option<&Vec<u32>> {
-        self.semantic_tokens.get(path)
+    /// Get semantic tokens for a specific file, and remember them as the result
+    /// later delta requests refer to.
+    pub fn semantic_tokens(&mut self, path: &str) -> Option<&SemanticTokens> {
+        let tokens = self.semantic_tokens.get(path)?;
+        self.previous_semantic_tokens
+            .insert(path.to_string(), tokens.clone());
+        Some(tokens)
+    }
+
+    /// Get the semantic tokens of a file in a range.
+    pub fn semantic_tokens_range(&self, path: &str, range: Range) -> Option<Vec<u32>> {
+        let tokens = self.semantic_tokens.get(path)?;
+        Some(semantic_tokens_in_range(&tokens.data, &range))
+    }
+
+    /// Get the edits turning the semantic tokens last returned into the current
+    /// ones. Returns `None` if the previous result is not the last one returned.
+    pub fn semantic_tokens_delta(
+        &mut self,
+        path: &str,
+        previous_result_id: &str,
+    ) -> Option<SemanticTokensDelta> {
+        let current = self.semantic_tokens.get(path)?;
+        let previous = self
+            .previous_semantic_tokens
+            .get(path)
+            .filter(|tokens| tokens.result_id == previous_result_id)?;
+
+        let delta = SemanticTokensDelta {
+            result_id: current.result_id.clone(),
+            edits: semantic_tokens_edits(&previous.data, &current.data),
+        };
+        self.previous_semantic_tokens
+            .insert(path.to_string(), current.clone());
+        Some(delta)
     }
 
     /// Get the semantic token types.
     pub fn semantic_token_types(&self) -> Vec<String> {
-        vec!["type".to_string(), "function".to_string()]
+        SEMANTIC_TOKEN_TYPES.iter().map(|t| t.to_string()).collect()
     }
 
     /// Get the semantic token modifiers.
     pub fn semantic_token_modifiers(&self) -> Vec<String> {
-        vec![]
+        SEMANTIC_TOKEN_MODIFIERS
+            .iter()
+            .map(|m| m.to_string())
+            .collect()
     }
 
     /// Get the definition at a specific position.
     pub fn definition(&self, path: &str, pos: Position) -> Option<Location> {
         let document_node = self.document_nodes.get(path)?.as_ref();
-        let identifier = self.find_identifier(document_node, pos)?;
+        let (identifier, is_value) = self.find_reference_identifier(document_node, pos)?;
         let symbol_table = self.symbol_tables.get(path)?;
+
+        if is_value {
+            let (value, header) = symbol_table.find_definition_of_const_value(identifier)?;
+            if identifier.position_in_namespace(pos) {
+                return match header {
+                    Some(include) => Some(Location {
+                        path: path.to_string(),
+                        range: include.range(),
+                    }),
+                    // the namespace of `Enum.MEMBER` is the enum
+                    None => Some(Location {
+                        path: value.path.clone(),
+                        range: value.definition.identifier().range(),
+                    }),
+                };
+            }
+
+            return Some(Location {
+                path: value.path.clone(),
+                range: value.identifier().range(),
+            });
+        }
+
         let (new_path, def, header) =
             symbol_table.find_definition_of_identifier_type(identifier)?;
 
@@ -135,511 +366,4006 @@ impl Analyzer {
         })
     }
 
-    /// Get the types for completion.
-    pub fn types_for_completion(&self, path: &str, pos: Position) -> Vec<String> {
-        let offset = match self.offset_at_position(path, pos) {
-            Some(offset) => offset,
-            None => return vec![],
-        };
-        let document = match self.documents.get(path) {
-            Some(document) => document,
-            None => return vec![],
-        };
-        let mut symbol_table = match self.symbol_tables.get(path) {
-            Some(symbol_table) => symbol_table.clone(),
+    /// Get the type of the symbol at a specific position, following typedefs to the
+    /// underlying definition, or to the base or container type of the last typedef.
+    pub fn type_definition(&self, path: &str, pos: Position) -> Option<Location> {
+        // the name of a definition, e.g. a typedef
+        if let Some((def_path, def)) = self.declaration_at(path, pos) {
+            return self.definition_type_location(&def_path, def.as_ref(), 0);
+        }
+
+        // the name of a field or a function
+        let document_node = self.document_nodes.get(path)?;
+        let identifier = self.find_identifier(document_node.as_ref(), pos)?;
+        for node in self.find_ancestors(document_node.as_ref(), identifier.range.start) {
+            if let Some(field) = node.as_any().downcast_ref::<FieldNode>() {
+                if ptr::eq(&field.identifier, identifier) {
+                    return self.type_location(path, &field.field_type, 0);
+                }
+            }
+            if let Some(function) = node.as_any().downcast_ref::<FunctionNode>() {
+                if ptr::eq(&function.identifier, identifier) {
+                    return self.type_location(path, function.function_type.as_ref()?, 0);
+                }
+            }
+        }
+
+        // a type or a constant used by the symbol
+        let (def_path, def) = self.definition_at(path, pos)?;
+        self.definition_type_location(&def_path, def.as_ref(), 0)
+    }
+
+    /// Get the references of the definition at a specific position.
+    pub fn references(
+        &self,
+        path: &str,
+        pos: Position,
+        include_declaration: bool,
+    ) -> Vec<Location> {
+        let (def_path, def) = match self.definition_at(path, pos) {
+            Some(x) => x,
             None => return vec![],
         };
 
-        if offset > 0 && document[offset - 1] == '.' {
-            let word = match self.idet_prev_offset(path, offset - 1) {
-                Some(word) => word,
-                None => return vec!["".to_string()],
-            };
-            let table = match symbol_table.includes().get(&word) {
-                Some(table) => table.clone(),
-                None => return vec!["".to_string()],
-            };
-            symbol_table = table;
+        let mut locations = Vec::new();
+        if include_declaration {
+            locations.push(Location {
+                path: def_path.clone(),
+                range: def.identifier().range(),
+            });
         }
+        locations.extend(self.find_references(&def_path, def.as_ref()));
 
-        return symbol_table.types().keys().cloned().collect();
+        locations
     }
 
-    /// Get the includes for completion.
-    pub fn includes_for_completion(&self, path: &str, _pos: Position) -> Vec<String> {
-        let symbol_table = match self.symbol_tables.get(path) {
-            Some(symbol_table) => symbol_table,
+    /// Get the occurrences in a file of the definition at a specific position, either
+    /// its name (a write) or a usage (a read).
+    pub fn document_highlights(&self, path: &str, pos: Position) -> Vec<DocumentHighlight> {
+        let (def_path, def) = match self
+            .declaration_at(path, pos)
+            .or_else(|| self.definition_at(path, pos))
+        {
+            Some(x) => x,
             None => return vec![],
         };
 
-        symbol_table.includes().keys().cloned().collect()
-    }
+        let mut highlights = Vec::new();
+        if def_path == path {
+            highlights.push(DocumentHighlight {
+                range: def.identifier().range(),
+                kind: DocumentHighlightKind::Write,
+            });
+        }
+        highlights.extend(
+            self.find_references(&def_path, def.as_ref())
+                .into_iter()
+                .filter(|location| location.path == path)
+                .map(|location| DocumentHighlight {
+                    range: location.range,
+                    kind: DocumentHighlightKind::Read,
+                }),
+        );
 
-    /// Get the keywords for completion.
-    pub fn keywords_for_completion(&self) -> Vec<String> {
-        KEYWORDS.iter().map(|s| s.to_string()).collect()
+        highlights
     }
-}
 
-impl Analyzer {
-    /// Analyze a document.
-    fn analyze(&mut self, path: &str) {
-        // clear previous state
-        self.document_nodes.remove(path);
-        self.symbol_tables.remove(path);
-        self.errors.remove(path);
-        self.semantic_tokens.remove(path);
+    /// Get the range of the symbol to rename at a specific position.
+    pub fn prepare_rename(&self, path: &str, pos: Position) -> Option<Range> {
+        let (identifier, _) = self.find_rename_target(path, pos)?;
+        if identifier.position_in_namespace(pos) {
+            return None;
+        }
 
-        let mut visited = HashSet::new();
-        self.parse_document(path, &mut visited, None);
-        self.static_check(path);
-        self.generate_semantic_tokens(path);
+        Some(last_segment_range(&identifier.range, &identifier.name))
     }
 
-    /// Recursively parse AST and build symbol tables for a file.
-    fn parse_document(
-        &mut self,
+    /// Rename the symbol at a specific position.
+    pub fn rename(
+        &self,
         path: &str,
-        visited: &mut HashSet<String>,
-        source: Option<(&str, &Rc<HeaderNode>)>,
-    ) -> bool {
-        // check for circular dependencies
-        if visited.contains(path) {
-            if let Some((source_path, node)) = source {
-                let error = Error {
-                    range: node.range(),
-                    message: format!("Circular dependency detected: {}", path),
-                };
-
-                self.errors
-                    .entry(source_path.to_string())
-                    .or_default()
-                    .push(error);
-            }
-            return false;
+        pos: Position,
+        new_name: &str,
+    ) -> Result<WorkspaceEdit, String> {
+        if !is_valid_identifier(new_name) {
+            return Err(format!("Invalid identifier: {}", new_name));
         }
 
-        // mark file as being processed
-        visited.insert(path.to_string());
-
-        // if file is already parsed, return
-        if self.document_nodes.contains_key(path) {
-            return true;
+        let (identifier, target) = self
+            .find_rename_target(path, pos)
+            .ok_or("No symbol to rename at this position".to_string())?;
+        if identifier.position_in_namespace(pos) {
+            return Err("Cannot rename an include namespace".to_string());
         }
 
-        // read the file
-        let content = if let Some(content) = self.documents.get(path) {
-            content
-        } else {
-            // try to read from local file system
-            match self.read_file(path) {
-                Ok(content) => &content.chars().collect(),
-                Err(e) => {
-                    if let Some((source_path, node)) = source {
-                        let error = Error {
-                            range: node.range(),
-                            message: format!("Failed to read file {}: {}", path, e),
-                        };
-
-                        self.errors
-                            .entry(source_path.to_string())
-                            .or_default()
-                            .push(error);
-                    }
-                    return false;
+        let mut edit = WorkspaceEdit::default();
+        match target {
+            RenameTarget::Definition(def_path, def) => {
+                if def.name() != new_name
+                    && self
+                        .symbol_tables
+                        .get(&def_path)
+                        .is_some_and(|table| table.types().contains_key(new_name))
+                {
+                    return Err(format!("Duplicate definition: {}", new_name));
                 }
-            }
-        };
-
-        // parse the file
-        let (document_node, errors) = Parser::new(content).parse();
-
-        // store parser errors
-        self.errors
-            .entry(path.to_string())
-            .or_default()
-            .extend(errors.into_iter().map(|e| e));
 
-        // track file dependencies
-        let mut dependencies = Vec::new();
-        for header in &document_node.headers {
-            if let HeaderNode::Include(include) = header.as_ref() {
-                if let Some(parent) = path_parent(path) {
-                    dependencies.push((
-                        parent.join(&include.literal).to_string_lossy().to_string(),
-                        header.clone(),
-                    ));
+                let references = self.find_references(&def_path, def.as_ref());
+                edit.changes.entry(def_path).or_default().push(TextEdit {
+                    range: def.identifier().range(),
+                    new_text: new_name.to_string(),
+                });
+                for location in references {
+                    edit.changes
+                        .entry(location.path)
+                        .or_default()
+                        .push(TextEdit {
+                            range: last_segment_range(&location.range, def.name()),
+                            new_text: new_name.to_string(),
+                        });
                 }
             }
-        }
-
-        // build symbol table
-        let mut symbol_table = SymbolTable::new_from_ast(path, &document_node);
+            RenameTarget::Field(identifier, fields) => {
+                if fields
+                    .iter()
+                    .any(|f| f.identifier.name == new_name && !ptr::eq(&f.identifier, identifier))
+                {
+                    return Err(format!("Duplicate field identifier: {}", new_name));
+                }
 
-        // recursively parse dependencies
-        for (dep_path, header) in dependencies.iter() {
-            let res = self.parse_document(dep_path, visited, Some((path, header)));
-            visited.remove(dep_path.as_str());
-            if !res {
-                continue;
+                edit.changes
+                    .entry(path.to_string())
+                    .or_default()
+                    .push(TextEdit {
+                        range: identifier.range(),
+                        new_text: new_name.to_string(),
+                    });
             }
+            RenameTarget::Function(identifier, functions) => {
+                if functions
+                    .iter()
+                    .any(|f| f.identifier.name == new_name && !ptr::eq(&f.identifier, identifier))
+                {
+                    return Err(format!("Duplicate function identifier: {}", new_name));
+                }
 
-            // add dependency to current symbol table
-            if let Some(dep_table) = self.symbol_tables.get(dep_path) {
-                symbol_table.add_dependency(dep_path, header.clone(), dep_table.clone());
+                edit.changes
+                    .entry(path.to_string())
+                    .or_default()
+                    .push(TextEdit {
+                        range: identifier.range(),
+                        new_text: new_name.to_string(),
+                    });
             }
         }
 
-        // store document
-        self.symbol_tables
-            .insert(path.to_string(), Rc::new(symbol_table));
-        self.document_nodes
-            .insert(path.to_string(), Rc::new(document_node));
-
-        true
+        Ok(edit)
     }
-}
 
-/// Static check
-impl Analyzer {
-    fn static_check(&mut self, path: &str) {
+    /// Get the symbols of a document as a tree.
+    pub fn document_symbols(&self, path: &str) -> Vec<DocumentSymbol> {
         let document_node = match self.document_nodes.get(path) {
-            Some(document_node) => document_node.clone(),
-            None => return,
+            Some(document_node) => document_node,
+            None => return vec![],
         };
-        let symbol_table = match self.symbol_tables.get_mut(path) {
-            Some(symbol_table) => symbol_table.clone(),
-            None => return,
+
+        let mut symbols = Vec::new();
+        symbols.extend(document_node.headers.iter().map(|h| header_symbol(h)));
+        symbols.extend(
+            document_node
+                .definitions
+                .iter()
+                .map(|d| definition_symbol(d)),
+        );
+        symbols
+    }
+
+    /// Get the code actions for a range of a document: quick fixes for the errors
+    /// touching the range, and refactorings of fields without an ID and of the
+    /// types in the range.
+    pub fn code_actions(&self, path: &str, range: &Range) -> Vec<CodeAction> {
+        let errors = match self.errors.get(path) {
+            Some(errors) => errors,
+            None => return vec![],
         };
 
-        // type check
-        symbol_table.check_document_types(document_node.as_ref());
-        self.errors
-            .entry(path.to_string())
-            .or_default()
-            .extend(symbol_table.errors().into_iter().map(|e| e));
+        let mut actions = Vec::new();
+        for error in errors
+            .iter()
+            .filter(|e| e.range.start <= range.end && range.start <= e.range.end)
+        {
+            if let Some(reference) = error.message.strip_prefix("Undefined type: ") {
+                actions.extend(self.missing_include_fixes(path, error, reference));
+                actions.extend(self.create_definition_fixes(path, error, reference));
+            }
+            if error.message.starts_with("Duplicate field ID: ") {
+                actions.extend(self.duplicate_field_id_fix(path, error));
+            }
+        }
+        actions.extend(self.missing_field_ids_actions(path, range));
+        actions.extend(self.extract_typedef_action(path, range));
+        actions.extend(self.inline_typedef_action(path, range));
+        actions.extend(self.move_definition_action(path, range));
 
-        // field check
-        self.document_check(path, document_node.as_ref());
+        actions
     }
 
-    fn document_check(&mut self, path: &str, document_node: &DocumentNode) {
+    /// Extract the container type in a range into a typedef, and use the typedef for
+    /// every identical type in the document.
+    pub fn extract_typedef(
+        &self,
+        path: &str,
+        range: &Range,
+        name: &str,
+    ) -> Result<WorkspaceEdit, String> {
+        if !is_valid_identifier(name) {
+            return Err(format!("Invalid identifier: {}", name));
+        }
+        if self
+            .symbol_tables
+            .get(path)
+            .is_some_and(|table| table.types().contains_key(name))
+        {
+            return Err(format!("Duplicate definition: {}", name));
+        }
+
+        let (document_node, source) = match (self.document_nodes.get(path), self.sources.get(path))
+        {
+            (Some(document_node), Some(source)) => (document_node, source),
+            _ => return Err("No type to extract in this range".to_string()),
+        };
+        let target = extract_target(document_node, range)
+            .ok_or("No type to extract in this range".to_string())?;
+        let type_text = text_in_range(source, &target.range())
+            .ok_or("No type to extract in this range".to_string())?;
+
+        let mut edits = Vec::new();
         for definition in &document_node.definitions {
-            match definition.as_ref() {
-                DefinitionNode::Struct(struct_node) => {
-                    self.fields_check(path, &struct_node.fields);
+            for field_type in definition_field_types(definition) {
+                if field_type.to_string() != target.to_string() {
+                    continue;
                 }
-                DefinitionNode::Union(union_node) => {
-                    self.fields_check(path, &union_node.fields);
-                }
-                DefinitionNode::Exception(exception_node) => {
-                    self.fields_check(path, &exception_node.fields);
-                }
-                DefinitionNode::Service(service_node) => {
-                    self.functions_check(path, &service_node.functions);
+
+                // the typedef goes before its first usage
+                if edits.is_empty() {
+                    let start = leading_comments_start(document_node, definition.range().start);
+                    let start = Position {
+                        line: start.line,
+                        column: 1,
+                    };
+                    edits.push(TextEdit {
+                        range: Range { start, end: start },
+                        new_text: format!("typedef {} {}\n\n", type_text, name),
+                    });
                 }
-                _ => {}
+                edits.push(TextEdit {
+                    range: field_type.range(),
+                    new_text: name.to_string(),
+                });
             }
         }
+
+        edits.sort_by(|a, b| a.range.cmp(&b.range));
+        Ok(WorkspaceEdit {
+            changes: HashMap::from([(path.to_string(), edits)]),
+        })
     }
 
-    fn fields_check(&mut self, path: &str, fields: &[FieldNode]) {
-        let mut field_ids = HashSet::new();
-        let mut field_identifiers = HashSet::new();
+    /// Move the definition at a position to another file. The destination includes the
+    /// files of the types the definition uses, and the files using the definition
+    /// include the destination.
+    pub fn move_definition(
+        &self,
+        path: &str,
+        pos: Position,
+        destination: &str,
+    ) -> Result<WorkspaceEdit, String> {
+        let (document_node, source, symbol_table) = match (
+            self.document_nodes.get(path),
+            self.sources.get(path),
+            self.symbol_tables.get(path),
+        ) {
+            (Some(document_node), Some(source), Some(symbol_table)) => {
+                (document_node, source, symbol_table)
+            }
+            _ => return Err("No definition to move at this position".to_string()),
+        };
+        let def = document_node
+            .definitions
+            .iter()
+            .find(|d| d.range().contains(pos))
+            .ok_or("No definition to move at this position".to_string())?;
+        let name = def.name();
 
-        for field in fields {
-            if let Some(field_id) = &field.field_id {
-                if field_ids.contains(&field_id.id) {
-                    let error = Error {
-                        range: field_id.range.clone(),
-                        message: format!("Duplicate field ID: {}", field_id.id),
-                    };
-                    self.errors.entry(path.to_string()).or_default().push(error);
-                } else {
-                    field_ids.insert(field_id.id);
+        if destination == path {
+            return Err(format!("`{}` is already in {}", name, file_name(path)));
+        }
+        let (dest_path, dest_document) = self
+            .index
+            .documents()
+            .get_key_value(destination)
+            .ok_or(format!("Unknown file: {}", destination))?;
+        let dest_content: Vec<char> = match self.sources.get(dest_path) {
+            Some(content) => content.clone(),
+            None => self
+                .read_file(dest_path)
+                .map_err(|e| format!("Failed to read file {}: {}", dest_path, e))?
+                .chars()
+                .collect(),
+        };
+        if dest_document.definitions.iter().any(|d| d.name() == name) {
+            return Err(format!("Duplicate definition: {}", name));
+        }
+
+        // the references in the definition, as named in the destination
+        let mut dest_dependencies: Vec<String> = dest_document
+            .headers
+            .iter()
+            .filter_map(|h| match h.as_ref() {
+                HeaderNode::Include(include) => include_path(dest_path, &include.literal),
+                _ => None,
+            })
+            .collect();
+        let mut dest_includes: Vec<String> = Vec::new();
+        let mut reference_edits = Vec::new();
+        let type_references =
+            self.definition_type_references(def)
+                .into_iter()
+                .filter_map(|identifier| {
+                    let (ref_path, ref_def, _) =
+                        symbol_table.find_definition_of_identifier_type(identifier)?;
+                    Some((identifier, ref_path, ref_def, None))
+                });
+        let value_references = self
+            .definition_value_references(def)
+            .into_iter()
+            .filter_map(|identifier| {
+                let (value, _) = symbol_table.find_definition_of_const_value(identifier)?;
+                Some((identifier, value.path, value.definition, value.member))
+            });
+        for (identifier, ref_path, ref_def, member) in type_references.chain(value_references) {
+            let namespace = if ref_path == *dest_path
+                || (ref_path == path && ref_def.name() == name)
+            {
+                None
+            } else if let Some(namespace) = included_namespace(dest_document, dest_path, &ref_path)
+            {
+                Some(namespace)
+            } else {
+                let literal = relative_path(dest_path, &ref_path);
+                let namespace = include_namespace(&literal);
+                if !dest_dependencies.contains(&ref_path) {
+                    if has_include_namespace(dest_document, &namespace)
+                        || dest_includes
+                            .iter()
+                            .any(|l| include_namespace(l) == namespace)
+                    {
+                        return Err(format!(
+                            "{} already includes another file as `{}`",
+                            file_name(dest_path),
+                            namespace
+                        ));
+                    }
+                    dest_dependencies.push(ref_path);
+                    dest_includes.push(literal);
                 }
+                Some(namespace)
+            };
+
+            reference_edits.push(TextEdit {
+                range: identifier.range(),
+                new_text: qualified_name(namespace.as_deref(), &ref_def, member),
+            });
+        }
+        reference_edits.sort_by(|a, b| a.range.cmp(&b.range));
+
+        let range = Range {
+            start: leading_comments_start(document_node, def.range().start),
+            end: line_start_or_end(source, def.range().end.line + 1),
+        };
+        let text = text_with_edits(source, &range, &reference_edits)
+            .ok_or("No definition to move at this position".to_string())?;
+
+        // the usages of the definition, named from their files
+        let mut edit = WorkspaceEdit::default();
+        let mut dependents: Vec<String> = Vec::new();
+        for (location, member) in self.find_usages(path, def.as_ref()) {
+            if location.path == path && def.range().contains(location.range.start) {
+                continue;
             }
 
-            let identifier_name = &field.identifier.name;
-            if field_identifiers.contains(identifier_name) {
-                let error = Error {
-                    range: field.identifier.range.clone(),
-                    message: format!("Duplicate field identifier: {}", identifier_name),
-                };
-                self.errors.entry(path.to_string()).or_default().push(error);
+            let namespace = if location.path == *dest_path {
+                None
             } else {
-                field_identifiers.insert(identifier_name.clone());
-            }
+                let document = match self.index.document(&location.path) {
+                    Some(document) => document,
+                    None => continue,
+                };
+                let namespace = match included_namespace(document, &location.path, dest_path) {
+                    Some(namespace) => namespace,
+                    None => {
+                        let literal = relative_path(&location.path, dest_path);
+                        let namespace = include_namespace(&literal);
+                        if !dependents.contains(&location.path) {
+                            if has_include_namespace(document, &namespace) {
+                                return Err(format!(
+                                    "{} already includes another file as `{}`",
+                                    file_name(&location.path),
+                                    namespace
+                                ));
+                            }
+                            edit.changes
+                                .entry(location.path.clone())
+                                .or_default()
+                                .push(include_edit(document, &[&literal]));
+                        }
+                        namespace
+                    }
+                };
+                if !dependents.contains(&location.path) {
+                    dependents.push(location.path.clone());
+                }
+                Some(namespace)
+            };
+
+            edit.changes
+                .entry(location.path)
+                .or_default()
+                .push(TextEdit {
+                    range: location.range,
+                    new_text: qualified_name(namespace.as_deref(), def, member),
+                });
         }
-    }
 
-    fn functions_check(&mut self, path: &str, functions: &[FunctionNode]) {
-        let mut function_identifiers = HashSet::new();
+        if let Some(file) = dependents.iter().find(|f| dest_dependencies.contains(f)) {
+            return Err(format!(
+                "Moving `{}` would make {} and {} include each other",
+                name,
+                file_name(dest_path),
+                file_name(file)
+            ));
+        }
 
-        for function in functions {
-            self.fields_check(path, &function.fields);
+        edit.changes
+            .entry(path.to_string())
+            .or_default()
+            .push(definition_removal(document_node, source, def));
 
-            let identifier_name = &function.identifier.name;
-            if function_identifiers.contains(identifier_name) {
-                let error = Error {
-                    range: function.identifier.range.clone(),
-                    message: format!("Duplicate function identifier: {}", identifier_name),
-                };
-                self.errors.entry(path.to_string()).or_default().push(error);
-            } else {
-                function_identifiers.insert(identifier_name.clone());
+        let dest_edits = edit.changes.entry(dest_path.clone()).or_default();
+        let mut append = append_edit(&dest_content, text.trim_end());
+        if !dest_includes.is_empty() {
+            let literals: Vec<&str> = dest_includes.iter().map(|l| l.as_str()).collect();
+            dest_edits.push(include_edit(dest_document, &literals));
+            // the include is not followed by a blank line in an empty document
+            if dest_document.headers.is_empty() && dest_document.definitions.is_empty() {
+                append.new_text.insert(0, '\n');
             }
         }
+        dest_edits.push(append);
+
+        for edits in edit.changes.values_mut() {
+            edits.sort_by(|a, b| a.range.cmp(&b.range));
+        }
+        Ok(edit)
     }
-}
 
-/// Semantic tokens
-impl Analyzer {
-    /// Generate semantic tokens for a document.
-    fn generate_semantic_tokens(&mut self, path: &str) {
-        let field_type_identifiers = self.find_field_type_identifiers(path);
-        let function_identifiers = self.find_function_identifiers(path);
+    /// Format a document. Returns no edits if the document has syntax errors.
+    pub fn formatting(&self, path: &str, options: &FormattingOptions) -> Vec<TextEdit> {
+        let source = match self.documents.get(path) {
+            Some(source) => source,
+            None => return vec![],
+        };
+        let (document_node, errors) = Parser::new(source).parse();
+        if !errors.is_empty() {
+            return vec![];
+        }
+
+        match format::format_document(&document_node, source, options) {
+            Some(formatted) => line_edits(source, &formatted),
+            None => vec![],
+        }
+    }
+
+    /// Format the lines of a document touched by a range. Returns no edits if the
+    /// document has syntax errors.
+    pub fn range_formatting(
+        &self,
+        path: &str,
+        range: &Range,
+        options: &FormattingOptions,
+    ) -> Vec<TextEdit> {
+        let source: String = match self.documents.get(path) {
+            Some(source) => source.iter().collect(),
+            None => return vec![],
+        };
+        let lines: Vec<&str> = source.split_inclusive('\n').collect();
+
+        self.formatting(path, options)
+            .iter()
+            .flat_map(|edit| split_line_edit(&lines, edit))
+            .filter(|edit| {
+                edit.range.start.line <= last_line(range)
+                    && last_line(&edit.range) >= range.start.line
+            })
+            .collect()
+    }
+
+    /// Format a document after a character is typed at a position: a line break after
+    /// a field starts the next field with a free ID, and a closing brace gets the
+    /// indentation of the line of its opening brace.
+    pub fn on_type_formatting(
+        &self,
+        path: &str,
+        pos: Position,
+        ch: &str,
+        options: &FormattingOptions,
+    ) -> Vec<TextEdit> {
+        let (document_node, source) =
+            match (self.document_nodes.get(path), self.documents.get(path)) {
+                (Some(document_node), Some(source)) => (document_node, source),
+                _ => return vec![],
+            };
+
+        let edit = match ch {
+            "\n" => next_field_edit(document_node, source, pos, options),
+            "}" => closing_brace_edit(source, pos),
+            _ => None,
+        };
+
+        edit.into_iter().collect()
+    }
+
+    /// Get the folding ranges of a file: definition bodies, multi-line annotations,
+    /// the header block and comment blocks.
+    pub fn folding_ranges(&self, path: &str) -> Vec<FoldingRange> {
+        let (document_node, source) = match (self.document_nodes.get(path), self.sources.get(path))
+        {
+            (Some(document_node), Some(source)) => (document_node, source),
+            _ => return vec![],
+        };
 
-        let mut identifiers: Vec<(&IdentifierNode, u32)> = Vec::new();
-        for id in field_type_identifiers {
-            identifiers.push((id, 0));
+        let mut ranges = Vec::new();
+
+        // runs of consecutive headers
+        let header_ranges: Vec<Range> = document_node.headers.iter().map(|h| h.range()).collect();
+        for run in line_runs(&header_ranges) {
+            push_folding_range(&mut ranges, &run, Some(FoldingRangeKind::Imports));
         }
-        for id in function_identifiers {
-            identifiers.push((id, 1));
+
+        for definition in &document_node.definitions {
+            push_folding_range(&mut ranges, &definition.range(), None);
         }
 
-        let new_tokens = self.convert_identifiers_to_semantic_tokens(identifiers);
-        self.semantic_tokens.insert(path.to_string(), new_tokens);
+        let mut ext_nodes: Vec<&ExtNode> = Vec::new();
+        collect_nodes(document_node.as_ref(), &mut ext_nodes);
+        for ext in ext_nodes {
+            push_folding_range(&mut ranges, &ext.range, None);
+        }
+
+        // block comments, and runs of line comments on their own lines
+        let (block_comments, line_comments): (Vec<&CommentNode>, Vec<&CommentNode>) = document_node
+            .comments
+            .iter()
+            .partition(|comment| comment.text.starts_with("/*"));
+        for comment in block_comments {
+            push_folding_range(&mut ranges, &comment.range, Some(FoldingRangeKind::Comment));
+        }
+        let line_comment_ranges: Vec<Range> = line_comments
+            .into_iter()
+            .filter(|comment| starts_line(source, comment.range.start))
+            .map(|comment| comment.range.clone())
+            .collect();
+        for run in line_runs(&line_comment_ranges) {
+            push_folding_range(&mut ranges, &run, Some(FoldingRangeKind::Comment));
+        }
+
+        ranges.sort_by_key(|range| (range.start_line, range.end_line));
+        ranges
     }
 
-    /// Find all IdentifierNode instances used as field types in the document nodes.
-    fn find_field_type_identifiers(&self, path: &str) -> Vec<&IdentifierNode> {
-        let mut result = Vec::new();
+    /// Get the chain of AST nodes containing a position, from the document node
+    /// to the innermost node.
+    pub fn ancestors(&self, path: &str, pos: Position) -> Vec<&dyn Node> {
+        match self.document_nodes.get(path) {
+            Some(document_node) => self.find_ancestors(document_node.as_ref(), pos),
+            None => vec![],
+        }
+    }
 
-        if let Some(document_node) = self.document_nodes.get(path) {
-            for definition in &document_node.definitions {
-                match definition.as_ref() {
-                    DefinitionNode::Const(const_node) => {
-                        result.extend(self.collect_field_type_identifiers(&const_node.field_type));
-                    }
-                    DefinitionNode::Typedef(typedef_node) => {
-                        result.extend(
-                            self.collect_field_type_identifiers(&typedef_node.definition_type),
-                        );
-                        result.push(&typedef_node.identifier);
-                    }
-                    DefinitionNode::Struct(struct_node) => {
-                        for field in &struct_node.fields {
-                            result.extend(self.collect_field_type_identifiers(&field.field_type));
+    /// Get the selection ranges of positions, one for each position. Each range
+    /// grows to the enclosing AST node, e.g. identifier, field, body, definition.
+    pub fn selection_ranges(&self, path: &str, positions: &[Position]) -> Vec<SelectionRange> {
+        let source = self.sources.get(path);
+
+        positions
+            .iter()
+            .map(|pos| {
+                let mut ranges = Vec::new();
+                for node in self.ancestors(path, *pos) {
+                    ranges.push(node.range());
+                    if let Some(body) = source.and_then(|source| block_range(source, node)) {
+                        if body.contains(*pos) {
+                            ranges.push(body);
                         }
                     }
-                    DefinitionNode::Union(union_node) => {
-                        for field in &union_node.fields {
-                            result.extend(self.collect_field_type_identifiers(&field.field_type));
+                }
+                ranges.dedup();
+
+                // ranges go from the document to the innermost node
+                let mut selection_range = None;
+                for range in ranges {
+                    selection_range = Some(SelectionRange {
+                        range,
+                        parent: selection_range.map(Box::new),
+                    });
+                }
+
+                selection_range.unwrap_or(SelectionRange {
+                    range: Range {
+                        start: *pos,
+                        end: *pos,
+                    },
+                    parent: None,
+                })
+            })
+            .collect()
+    }
+
+    /// Get the unresolved code lenses of a file: reference counts above types and
+    /// implementer counts above services.
+    pub fn code_lenses(&self, path: &str) -> Vec<CodeLens> {
+        let document_node = match self.document_nodes.get(path) {
+            Some(document_node) => document_node,
+            None => return vec![],
+        };
+
+        document_node
+            .definitions
+            .iter()
+            .filter_map(|definition| {
+                let kind = match definition.as_ref() {
+                    DefinitionNode::Struct(_)
+                    | DefinitionNode::Union(_)
+                    | DefinitionNode::Enum(_)
+                    | DefinitionNode::Exception(_) => CodeLensKind::References,
+                    DefinitionNode::Service(_) => CodeLensKind::Subtypes,
+                    DefinitionNode::Const(_) | DefinitionNode::Typedef(_) => return None,
+                };
+
+                Some(CodeLens {
+                    range: definition.identifier().range(),
+                    kind,
+                    title: None,
+                    locations: vec![],
+                })
+            })
+            .collect()
+    }
+
+    /// Resolve a code lens of a file, counting the usages of its definition in the workspace.
+    pub fn resolve_code_lens(&self, path: &str, code_lens: CodeLens) -> CodeLens {
+        let (def_path, def) = match self.declaration_at(path, code_lens.range.start) {
+            Some(x) => x,
+            None => return code_lens,
+        };
+
+        let (title, locations) = match code_lens.kind {
+            CodeLensKind::References => {
+                let locations = self.find_references(&def_path, def.as_ref());
+                let title = match locations.len() {
+                    1 => "1 reference".to_string(),
+                    n => format!("{} references", n),
+                };
+                (title, locations)
+            }
+            CodeLensKind::Subtypes => {
+                let locations: Vec<Location> = self
+                    .subtypes(&def_path, def.name())
+                    .into_iter()
+                    .map(|item| Location {
+                        path: item.path,
+                        range: item.selection_range,
+                    })
+                    .collect();
+                let title = match locations.len() {
+                    1 => "extended by 1 service".to_string(),
+                    n => format!("extended by {} services", n),
+                };
+                (title, locations)
+            }
+        };
+
+        CodeLens {
+            title: Some(title),
+            locations,
+            ..code_lens
+        }
+    }
+
+    /// Get the inlay hints in a range of a file: the IDs the Thrift compiler assigns to
+    /// fields without one, the numbers of enum values without one, and the types on
+    /// the wire of fields using typedefs.
+    pub fn inlay_hints(&self, path: &str, range: Range) -> Vec<InlayHint> {
+        let document_node = match self.document_nodes.get(path) {
+            Some(document_node) => document_node,
+            None => return vec![],
+        };
+
+        let mut hints = Vec::new();
+        let mut field_lists: Vec<&[FieldNode]> = Vec::new();
+        for definition in &document_node.definitions {
+            match definition.as_ref() {
+                DefinitionNode::Struct(struct_node) => field_lists.push(&struct_node.fields),
+                DefinitionNode::Union(union_node) => field_lists.push(&union_node.fields),
+                DefinitionNode::Exception(exception_node) => {
+                    field_lists.push(&exception_node.fields)
+                }
+                DefinitionNode::Service(service_node) => {
+                    for function in &service_node.functions {
+                        field_lists.push(&function.fields);
+                        if let Some(throws) = &function.throws {
+                            field_lists.push(throws);
                         }
                     }
-                    DefinitionNode::Exception(exception_node) => {
-                        for field in &exception_node.fields {
-                            result.extend(self.collect_field_type_identifiers(&field.field_type));
+                }
+                DefinitionNode::Enum(enum_node) => {
+                    for (value, number) in enum_node.values.iter().zip(enum_node.numbers()) {
+                        if value.value.is_none() {
+                            hints.push(InlayHint {
+                                position: value.identifier.range.end,
+                                label: format!("= {}", number),
+                                padding_left: true,
+                                padding_right: false,
+                            });
                         }
                     }
-                    DefinitionNode::Service(service_node) => {
-                        if let Some(extends) = &service_node.extends {
-                            result.push(extends);
-                        }
+                }
+                DefinitionNode::Const(_) | DefinitionNode::Typedef(_) => {}
+            }
+        }
 
-                        for function in &service_node.functions {
-                            if let Some(function_type) = &function.function_type {
-                                result.extend(self.collect_field_type_identifiers(function_type));
-                            }
-                            for field in &function.fields {
-                                result
-                                    .extend(self.collect_field_type_identifiers(&field.field_type));
-                            }
-                            if let Some(throws) = &function.throws {
-                                for throw in throws {
-                                    result.extend(
-                                        self.collect_field_type_identifiers(&throw.field_type),
-                                    );
-                                }
-                            }
-                        }
-                    }
+        for fields in field_lists {
+            for (field, id) in fields.iter().zip(FieldNode::ids(fields)) {
+                if field.field_id.is_none() {
+                    hints.push(InlayHint {
+                        position: field.range.start,
+                        label: format!("{}:", id),
+                        padding_left: false,
+                        padding_right: true,
+                    });
+                }
 
-                    _ => {}
+                // the type differs from the written one when typedefs are replaced
+                if let Some(wire_type) = self.wire_type(path, &field.field_type, 0) {
+                    if wire_type != field.field_type.to_string() {
+                        hints.push(InlayHint {
+                            position: field.identifier.range.end,
+                            label: format!(": {}", wire_type),
+                            padding_left: false,
+                            padding_right: false,
+                        });
+                    }
                 }
             }
         }
 
-        result
+        hints.retain(|hint| range.contains(hint.position));
+        hints.sort_by_key(|hint| hint.position);
+        hints
     }
 
-    /// Collect all IdentifierNode instances used as field types in the document nodes.
-    fn collect_field_type_identifiers<'a>(
-        &'a self,
-        field_type: &'a FieldTypeNode,
-    ) -> Vec<&'a IdentifierNode> {
-        match field_type {
-            FieldTypeNode::Identifier(identifier) => vec![identifier],
-            FieldTypeNode::BaseType(_) => vec![],
-            FieldTypeNode::MapType(map_type) => {
-                let mut result = self.collect_field_type_identifiers(&map_type.key_type);
-                result.extend(self.collect_field_type_identifiers(&map_type.value_type));
-                result
-            }
-            FieldTypeNode::SetType(set_type) => {
-                self.collect_field_type_identifiers(&set_type.type_node)
-            }
-            FieldTypeNode::ListType(list_type) => {
-                self.collect_field_type_identifiers(&list_type.type_node)
-            }
-        }
+    /// Get the links of include and cpp_include paths that resolve to a file.
+    pub fn document_links(&self, path: &str) -> Vec<DocumentLink> {
+        let document_node = match self.document_nodes.get(path) {
+            Some(document_node) => document_node,
+            None => return vec![],
+        };
+
+        document_node
+            .headers
+            .iter()
+            .filter_map(|header| {
+                let (literal, range) = match header.as_ref() {
+                    HeaderNode::Include(node) => (&node.literal, &node.literal_range),
+                    HeaderNode::CppInclude(node) => (&node.literal, &node.literal_range),
+                    HeaderNode::Namespace(_) => return None,
+                };
+                let link_path = include_path(path, literal)?;
+                if !self.document_nodes.contains_key(&link_path)
+                    && self.read_file(&link_path).is_err()
+                {
+                    return None;
+                }
+
+                Some(DocumentLink {
+                    range: range.clone(),
+                    path: link_path,
+                })
+            })
+            .collect()
     }
 
-    /// Convert a vector of IdentifierNode references to semantic tokens.
-    fn convert_identifiers_to_semantic_tokens(
-        &self,
-        mut identifiers: Vec<(&IdentifierNode, u32)>,
-    ) -> Vec<u32> {
-        identifiers.sort_by_key(|(identifier, _)| identifier.range());
+    /// Get the service at a specific position, as the root of a type hierarchy.
+    pub fn prepare_type_hierarchy(&self, path: &str, pos: Position) -> Option<TypeHierarchyItem> {
+        let document_node = self.document_nodes.get(path)?.as_ref();
+        let ancestors = self.find_ancestors(document_node, pos);
 
-        let mut tokens = Vec::new();
-        let mut prev_line = 0;
-        let mut prev_char = 0;
-
-        for (identifier, token_type) in identifiers {
-            let range = identifier.range();
-
-            // convert to 0-based line and column
-            let line = range.start.line - 1 as u32;
-            let char = range.start.column - 1 as u32;
-            let length = identifier.name.len() as u32;
-
-            // deltaLine: line number relative to the previous token
-            let delta_line = line - prev_line;
-            // deltaStart: start character relative to the previous token
-            let delta_start = if delta_line == 0 {
-                char - prev_char
-            } else {
-                char
-            };
-            // length: length of the token
-            // tokenType: 0 for type, 1 for function (as defined in SemanticTokensLegend)
-            // tokenModifiers: 0 for no modifiers
-            tokens.extend_from_slice(&[delta_line, delta_start, length, token_type, 0]);
+        // the name of a service definition
+        if let [.., parent, last] = ancestors.as_slice() {
+            if let Some(service) = parent.as_any().downcast_ref::<ServiceNode>() {
+                let is_name = last
+                    .as_any()
+                    .downcast_ref::<IdentifierNode>()
+                    .is_some_and(|identifier| ptr::eq(identifier, &service.identifier));
+                if is_name {
+                    let (_, def) = self.find_service(path, &service.identifier.name)?;
+                    return Some(type_hierarchy_item(path, def.as_ref()));
+                }
+            }
+        }
 
-            prev_line = line;
-            prev_char = char;
+        // a reference to a service
+        let (def_path, def) = self.definition_at(path, pos)?;
+        match def.as_ref() {
+            DefinitionNode::Service(_) => Some(type_hierarchy_item(&def_path, def.as_ref())),
+            _ => None,
         }
+    }
 
-        tokens
+    /// Get the service extended by a service.
+    pub fn supertypes(&self, path: &str, name: &str) -> Vec<TypeHierarchyItem> {
+        let mut result = Vec::new();
+        if let Some((symbol_table, def)) = self.find_service(path, name) {
+            if let DefinitionNode::Service(service) = def.as_ref() {
+                if let Some((super_path, super_def, _)) = service
+                    .extends
+                    .as_ref()
+                    .and_then(|extends| symbol_table.find_definition_of_identifier_type(extends))
+                {
+                    result.push(type_hierarchy_item(&super_path, super_def.as_ref()));
+                }
+            }
+        }
+
+        result
     }
 
-    /// Find all function identifiers in the document nodes.
-    fn find_function_identifiers(&self, path: &str) -> Vec<&IdentifierNode> {
+    /// Get the services extending a service, across the workspace.
+    pub fn subtypes(&self, path: &str, name: &str) -> Vec<TypeHierarchyItem> {
         let mut result = Vec::new();
 
-        if let Some(document_node) = self.document_nodes.get(path) {
+        for (sub_path, document_node) in self.index.documents() {
+            let mut symbol_table = None;
             for definition in &document_node.definitions {
-                match definition.as_ref() {
-                    DefinitionNode::Service(service_node) => {
-                        for function in &service_node.functions {
-                            result.push(&function.identifier);
-                        }
+                let extends = match definition.as_ref() {
+                    DefinitionNode::Service(ServiceNode {
+                        extends: Some(extends),
+                        ..
+                    }) => extends,
+                    _ => continue,
+                };
+
+                if symbol_table.is_none() {
+                    symbol_table = self.workspace_symbol_table(sub_path);
+                }
+                let resolved = symbol_table
+                    .as_ref()
+                    .and_then(|table| table.find_definition_of_identifier_type(extends));
+                if let Some((super_path, super_def, _)) = resolved {
+                    if super_path == path && super_def.name() == name {
+                        result.push(type_hierarchy_item(sub_path, definition.as_ref()));
                     }
-                    _ => {}
                 }
             }
         }
 
+        result.sort_by(|a, b| (&a.path, &a.range).cmp(&(&b.path, &b.range)));
         result
     }
-}
-
-/// Definition
-impl Analyzer {
-    /// Find an identifier at a specific position.
-    fn find_identifier<'a>(&self, node: &'a dyn Node, pos: Position) -> Option<&'a IdentifierNode> {
-        if !node.range().contains(pos) {
-            return None;
-        }
 
-        if let Some(identifier) = node.as_any().downcast_ref::<IdentifierNode>() {
-            return Some(identifier);
-        }
+    /// Get the type or function at a specific position, as the root of a type usage hierarchy.
+    pub fn prepare_call_hierarchy(&self, path: &str, pos: Position) -> Option<CallHierarchyItem> {
+        let document_node = self.document_nodes.get(path)?;
+        let identifier = self.find_identifier(document_node.as_ref(), pos)?;
 
-        for child in node.children() {
-            if let Some(identifier) = self.find_identifier(child, pos) {
-                return Some(identifier);
+        // the name of a definition or a function
+        for definition in &document_node.definitions {
+            if ptr::eq(definition.identifier(), identifier) {
+                return call_hierarchy_item(path, definition.as_ref());
+            }
+            if let DefinitionNode::Service(service) = definition.as_ref() {
+                for function in &service.functions {
+                    if ptr::eq(&function.identifier, identifier) {
+                        return Some(function_call_hierarchy_item(path, service, function));
+                    }
+                }
             }
         }
 
-        None
+        // a reference to a type
+        let (def_path, def) = self.definition_at(path, pos)?;
+        call_hierarchy_item(&def_path, def.as_ref())
     }
-}
 
-/// Completion
-impl Analyzer {
-    /// Get the offset at a specific position.
-    fn offset_at_position(&self, path: &str, pos: Position) -> Option<usize> {
-        let document = self.documents.get(path)?;
-        let mut offset = 0;
-        let mut cur_pos = Position { line: 1, column: 1 };
+    /// Get the types and functions using the type whose name starts at a position, across
+    /// the workspace. Expanding them in turn reaches every function using the type,
+    /// through fields, containers and typedefs.
+    pub fn incoming_calls(&self, path: &str, pos: Position) -> Vec<CallHierarchyIncomingCall> {
+        let target = match self.find_definition_starting_at(path, pos) {
+            Some(target) => target,
+            None => return vec![],
+        };
 
-        while offset < document.len() {
-            if cur_pos >= pos {
-                break;
-            }
+        let mut result = Vec::new();
+        for (from_path, document_node) in self.index.documents() {
+            let symbol_table = match self.workspace_symbol_table(from_path) {
+                Some(symbol_table) => symbol_table,
+                None => continue,
+            };
+            let usages = |identifiers: Vec<&IdentifierNode>| -> Vec<Range> {
+                identifiers
+                    .into_iter()
+                    .filter(|identifier| {
+                        symbol_table
+                            .find_definition_of_identifier_type(identifier)
+                            .is_some_and(|(def_path, def, _)| {
+                                def_path == path && def.name() == target.name()
+                            })
+                    })
+                    .map(|identifier| identifier.range())
+                    .collect()
+            };
 
-            if document[offset] == '\n' {
-                offset += 1;
-                cur_pos.line += 1;
-                cur_pos.column = 1;
-            } else if document[offset] == '\r' {
-                offset += 1;
-                cur_pos.line += 1;
-                cur_pos.column = 1;
-                if offset < document.len() && document[offset] == '\n' {
-                    offset += 1;
+            for definition in &document_node.definitions {
+                if let DefinitionNode::Service(service) = definition.as_ref() {
+                    for function in &service.functions {
+                        let from_ranges = usages(self.function_type_references(function));
+                        if !from_ranges.is_empty() {
+                            result.push(CallHierarchyIncomingCall {
+                                from: function_call_hierarchy_item(from_path, service, function),
+                                from_ranges,
+                            });
+                        }
+                    }
+                } else if let Some(from) = call_hierarchy_item(from_path, definition.as_ref()) {
+                    let from_ranges = usages(self.definition_type_references(definition));
+                    if !from_ranges.is_empty() {
+                        result.push(CallHierarchyIncomingCall { from, from_ranges });
+                    }
                 }
-            } else {
-                offset += 1;
-                cur_pos.column += 1;
             }
         }
 
-        if cur_pos == pos {
-            Some(offset)
-        } else {
-            None
-        }
+        result.sort_by(|a, b| (&a.from.path, &a.from.range).cmp(&(&b.from.path, &b.from.range)));
+        result
     }
 
-    /// Get the identifier at the previous offset. no consider the '.'.
-    fn idet_prev_offset(&self, path: &str, offset: usize) -> Option<String> {
+    /// Get the types used by the type or function whose name starts at a position.
+    pub fn outgoing_calls(&self, path: &str, pos: Position) -> Vec<CallHierarchyOutgoingCall> {
+        let document_node = match self.index.document(path) {
+            Some(document_node) => document_node,
+            None => return vec![],
+        };
+        let symbol_table = match self.workspace_symbol_table(path) {
+            Some(symbol_table) => symbol_table,
+            None => return vec![],
+        };
+
+        let mut identifiers = Vec::new();
+        for definition in &document_node.definitions {
+            if definition.identifier().range.start == pos {
+                identifiers = self.definition_type_references(definition);
+            }
+            if let DefinitionNode::Service(service) = definition.as_ref() {
+                for function in &service.functions {
+                    if function.identifier.range.start == pos {
+                        identifiers = self.function_type_references(function);
+                    }
+                }
+            }
+        }
+
+        let mut result: Vec<CallHierarchyOutgoingCall> = Vec::new();
+        for identifier in identifiers {
+            let to = match symbol_table
+                .find_definition_of_identifier_type(identifier)
+                .and_then(|(def_path, def, _)| call_hierarchy_item(&def_path, def.as_ref()))
+            {
+                Some(to) => to,
+                None => continue,
+            };
+
+            match result
+                .iter_mut()
+                .find(|call| call.to.path == to.path && call.to.name == to.name)
+            {
+                Some(call) => call.from_ranges.push(identifier.range()),
+                None => result.push(CallHierarchyOutgoingCall {
+                    to,
+                    from_ranges: vec![identifier.range()],
+                }),
+            }
+        }
+
+        result
+    }
+
+    /// Get the hover information at a specific position.
+    pub fn hover(&self, path: &str, pos: Position) -> Option<Hover> {
+        let document_node = self.document_nodes.get(path)?.as_ref();
+        let (identifier, is_value) = self.find_reference_identifier(document_node, pos)?;
+        let symbol_table = self.symbol_tables.get(path)?;
+
+        if is_value {
+            let (value, header) = symbol_table.find_definition_of_const_value(identifier)?;
+            if identifier.position_in_namespace(pos) {
+                let (namespace, _) = identifier.split_by_first_dot();
+                let contents = match header {
+                    Some(include) => self.hover_include(path, include.as_ref())?,
+                    None => self.hover_definition(&value.path, value.definition.as_ref())?,
+                };
+                return Some(Hover {
+                    contents,
+                    range: namespace?.range(),
+                });
+            }
+
+            return Some(Hover {
+                contents: self.hover_value(&value)?,
+                range: identifier.range(),
+            });
+        }
+
+        let (def_path, def, header) =
+            symbol_table.find_definition_of_identifier_type(identifier)?;
+
+        if identifier.position_in_namespace(pos) {
+            let (namespace, _) = identifier.split_by_first_dot();
+            return Some(Hover {
+                contents: self.hover_include(path, header?.as_ref())?,
+                range: namespace?.range(),
+            });
+        }
+
+        Some(Hover {
+            contents: self.hover_definition(&def_path, def.as_ref())?,
+            range: identifier.range(),
+        })
+    }
+
+    /// Get the types for completion.
+    pub fn types_for_completion(&self, path: &str, pos: Position) -> Vec<String> {
+        let offset = match self.offset_at_position(path, pos) {
+            Some(offset) => offset,
+            None => return vec![],
+        };
+        let document = match self.documents.get(path) {
+            Some(document) => document,
+            None => return vec![],
+        };
+        let mut symbol_table = match self.symbol_tables.get(path) {
+            Some(symbol_table) => symbol_table.clone(),
+            None => return vec![],
+        };
+
+        if offset > 0 && document[offset - 1] == '.' {
+            let word = match self.idet_prev_offset(path, offset - 1) {
+                Some(word) => word,
+                None => return vec!["".to_string()],
+            };
+            let table = match symbol_table.includes().get(&word) {
+                Some(table) => table.clone(),
+                None => return vec!["".to_string()],
+            };
+            symbol_table = table;
+        }
+
+        symbol_table.types().keys().cloned().collect()
+    }
+
+    /// Get the includes for completion.
+    pub fn includes_for_completion(&self, path: &str, _pos: Position) -> Vec<String> {
+        let symbol_table = match self.symbol_tables.get(path) {
+            Some(symbol_table) => symbol_table,
+            None => return vec![],
+        };
+
+        symbol_table.includes().keys().cloned().collect()
+    }
+
+    /// Get the keywords for completion.
+    pub fn keywords_for_completion(&self) -> Vec<String> {
+        KEYWORDS.iter().map(|s| s.to_string()).collect()
+    }
+}
+
+impl Analyzer {
+    /// Analyze a document.
+    fn analyze(&mut self, path: &str) {
+        // clear previous state
+        self.sources.remove(path);
+        self.document_nodes.remove(path);
+        self.symbol_tables.remove(path);
+        self.errors.remove(path);
+        self.semantic_tokens.remove(path);
+
+        let mut visited = HashSet::new();
+        self.parse_document(path, &mut visited, None);
+        self.static_check(path);
+        self.generate_semantic_tokens(path);
+    }
+
+    /// Recursively parse AST and build symbol tables for a file.
+    fn parse_document(
+        &mut self,
+        path: &str,
+        visited: &mut HashSet<String>,
+        source: Option<(&str, &Rc<HeaderNode>)>,
+    ) -> bool {
+        // check for circular dependencies
+        if visited.contains(path) {
+            if let Some((source_path, node)) = source {
+                let error = Error {
+                    range: node.range(),
+                    message: format!("Circular dependency detected: {}", path),
+                    severity: Severity::Error,
+                    tags: vec![],
+                };
+
+                self.errors
+                    .entry(source_path.to_string())
+                    .or_default()
+                    .push(error);
+            }
+            return false;
+        }
+
+        // mark file as being processed
+        visited.insert(path.to_string());
+
+        // if file is already parsed, return
+        if self.document_nodes.contains_key(path) {
+            return true;
+        }
+
+        // read the file
+        let content = if let Some(content) = self.documents.get(path) {
+            content
+        } else {
+            // try to read from local file system
+            match self.read_file(path) {
+                Ok(content) => &content.chars().collect(),
+                Err(e) => {
+                    if let Some((source_path, node)) = source {
+                        let error = Error {
+                            range: node.range(),
+                            message: format!("Failed to read file {}: {}", path, e),
+                            severity: Severity::Error,
+                            tags: vec![],
+                        };
+
+                        self.errors
+                            .entry(source_path.to_string())
+                            .or_default()
+                            .push(error);
+                    }
+                    return false;
+                }
+            }
+        };
+
+        // parse the file
+        let source = content.clone();
+        let (document_node, errors) = Parser::new(&source).parse();
+
+        // store parser errors
+        self.errors
+            .entry(path.to_string())
+            .or_default()
+            .extend(errors);
+
+        // track file dependencies
+        let mut dependencies = Vec::new();
+        for header in &document_node.headers {
+            if let HeaderNode::Include(include) = header.as_ref() {
+                if let Some(dep_path) = include_path(path, &include.literal) {
+                    dependencies.push((dep_path, header.clone()));
+                }
+            }
+        }
+
+        // build symbol table
+        let mut symbol_table = SymbolTable::new_from_ast(path, &document_node);
+
+        // recursively parse dependencies
+        for (dep_path, header) in dependencies.iter() {
+            let res = self.parse_document(dep_path, visited, Some((path, header)));
+            visited.remove(dep_path.as_str());
+            if !res {
+                continue;
+            }
+
+            // add dependency to current symbol table
+            if let Some(dep_table) = self.symbol_tables.get(dep_path) {
+                symbol_table.add_dependency(dep_path, header.clone(), dep_table.clone());
+            }
+        }
+
+        // store document
+        let document_node = Rc::new(document_node);
+        self.index.update(path, document_node.clone());
+        self.sources.insert(path.to_string(), source);
+        self.symbol_tables
+            .insert(path.to_string(), Rc::new(symbol_table));
+        self.document_nodes.insert(path.to_string(), document_node);
+
+        true
+    }
+}
+
+/// Static check
+impl Analyzer {
+    fn static_check(&mut self, path: &str) {
+        let document_node = match self.document_nodes.get(path) {
+            Some(document_node) => document_node.clone(),
+            None => return,
+        };
+        let symbol_table = match self.symbol_tables.get_mut(path) {
+            Some(symbol_table) => symbol_table.clone(),
+            None => return,
+        };
+
+        // type check
+        symbol_table.check_document_types(document_node.as_ref());
+        self.errors
+            .entry(path.to_string())
+            .or_default()
+            .extend(symbol_table.errors());
+
+        // field check
+        self.document_check(path, document_node.as_ref());
+
+        // usages of deprecated symbols
+        let warnings = self.deprecation_check(path, document_node.as_ref());
+        self.errors
+            .entry(path.to_string())
+            .or_default()
+            .extend(warnings);
+    }
+
+    fn document_check(&mut self, path: &str, document_node: &DocumentNode) {
+        for definition in &document_node.definitions {
+            match definition.as_ref() {
+                DefinitionNode::Struct(struct_node) => {
+                    self.fields_check(path, &struct_node.fields);
+                }
+                DefinitionNode::Union(union_node) => {
+                    self.fields_check(path, &union_node.fields);
+                }
+                DefinitionNode::Exception(exception_node) => {
+                    self.fields_check(path, &exception_node.fields);
+                }
+                DefinitionNode::Service(service_node) => {
+                    self.functions_check(path, &service_node.functions);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn fields_check(&mut self, path: &str, fields: &[FieldNode]) {
+        let mut field_ids = HashSet::new();
+        let mut field_identifiers = HashSet::new();
+
+        for field in fields {
+            if let Some(field_id) = &field.field_id {
+                if field_ids.contains(&field_id.id) {
+                    let error = Error {
+                        range: field_id.range.clone(),
+                        message: format!("Duplicate field ID: {}", field_id.id),
+                        severity: Severity::Error,
+                        tags: vec![],
+                    };
+                    self.errors.entry(path.to_string()).or_default().push(error);
+                } else {
+                    field_ids.insert(field_id.id);
+                }
+            }
+
+            let identifier_name = &field.identifier.name;
+            if field_identifiers.contains(identifier_name) {
+                let error = Error {
+                    range: field.identifier.range.clone(),
+                    message: format!("Duplicate field identifier: {}", identifier_name),
+                    severity: Severity::Error,
+                    tags: vec![],
+                };
+                self.errors.entry(path.to_string()).or_default().push(error);
+            } else {
+                field_identifiers.insert(identifier_name.clone());
+            }
+        }
+    }
+
+    fn functions_check(&mut self, path: &str, functions: &[FunctionNode]) {
+        let mut function_identifiers = HashSet::new();
+
+        for function in functions {
+            self.fields_check(path, &function.fields);
+
+            let identifier_name = &function.identifier.name;
+            if function_identifiers.contains(identifier_name) {
+                let error = Error {
+                    range: function.identifier.range.clone(),
+                    message: format!("Duplicate function identifier: {}", identifier_name),
+                    severity: Severity::Error,
+                    tags: vec![],
+                };
+                self.errors.entry(path.to_string()).or_default().push(error);
+            } else {
+                function_identifiers.insert(identifier_name.clone());
+            }
+        }
+    }
+}
+
+/// Deprecation
+impl Analyzer {
+    /// Get the deprecation message of a symbol with the given annotations, if it
+    /// is deprecated. The message may be empty.
+    fn deprecation<'a>(&self, ext: Option<&'a ExtNode>) -> Option<&'a str> {
+        ext?.get(&self.deprecation_annotation)
+    }
+
+    /// Get the deprecation message of a constant value. An enum member is
+    /// deprecated if it or its enum is.
+    fn value_deprecation<'a>(&self, value: &'a ValueDefinition) -> Option<&'a str> {
+        let member = value.enum_value().and_then(|v| v.ext.as_ref());
+        self.deprecation(member)
+            .or_else(|| self.deprecation(value.definition.ext()))
+    }
+
+    /// Find the usages of deprecated types and enum members in a document.
+    fn deprecation_check(&self, path: &str, document_node: &DocumentNode) -> Vec<Error> {
+        let symbol_table = match self.symbol_tables.get(path) {
+            Some(symbol_table) => symbol_table,
+            None => return vec![],
+        };
+
+        let mut warnings = Vec::new();
+        for identifier in self.find_type_references(document_node) {
+            let (_, def, _) = match symbol_table.find_definition_of_identifier_type(identifier) {
+                Some(resolved) => resolved,
+                None => continue,
+            };
+            if let Some(message) = self.deprecation(def.ext()) {
+                warnings.push(deprecation_warning(identifier, def.name(), message));
+            }
+        }
+
+        for identifier in self.find_value_references(document_node) {
+            let (value, _) = match symbol_table.find_definition_of_const_value(identifier) {
+                Some(resolved) => resolved,
+                None => continue,
+            };
+            if let Some(message) = self.value_deprecation(&value) {
+                let name = match value.enum_value() {
+                    Some(member) => {
+                        format!("{}.{}", value.definition.name(), member.identifier.name)
+                    }
+                    None => value.definition.name().to_string(),
+                };
+                warnings.push(deprecation_warning(identifier, &name, message));
+            }
+        }
+
+        warnings
+    }
+}
+
+/// Semantic tokens
+impl Analyzer {
+    /// Generate semantic tokens for a document.
+    fn generate_semantic_tokens(&mut self, path: &str) {
+        let mut tokens = self.lexical_semantic_tokens(path);
+        tokens.extend(self.identifier_semantic_tokens(path));
+
+        self.semantic_tokens_result_id += 1;
+        let new_tokens = SemanticTokens {
+            result_id: self.semantic_tokens_result_id.to_string(),
+            data: encode_semantic_tokens(tokens),
+        };
+        self.semantic_tokens.insert(path.to_string(), new_tokens);
+    }
+
+    /// Find the semantic tokens that only depend on the text: keywords, strings,
+    /// numbers, comments and the keys of annotations.
+    fn lexical_semantic_tokens(&self, path: &str) -> Vec<SemanticToken> {
+        let (document_node, source) = match (self.document_nodes.get(path), self.sources.get(path))
+        {
+            (Some(document_node), Some(source)) => (document_node, source),
+            _ => return vec![],
+        };
+
+        let mut ext_nodes: Vec<&ExtNode> = Vec::new();
+        collect_nodes(document_node.as_ref(), &mut ext_nodes);
+
+        let mut tokens = Vec::new();
+        let mut scanner = Scanner::new(source);
+        let mut prev_kind = TokenKind::Eof;
+        loop {
+            let (token, _) = scanner.scan();
+            if token.is_eof() {
+                break;
+            }
+
+            let token_type = match &token.kind {
+                TokenKind::Include
+                | TokenKind::CppInclude
+                | TokenKind::Namespace
+                | TokenKind::Const
+                | TokenKind::Typedef
+                | TokenKind::Enum
+                | TokenKind::Struct
+                | TokenKind::Union
+                | TokenKind::Exception
+                | TokenKind::Service
+                | TokenKind::Required
+                | TokenKind::Optional
+                | TokenKind::Oneway
+                | TokenKind::Void
+                | TokenKind::Throws
+                | TokenKind::Extends
+                | TokenKind::Map
+                | TokenKind::Set
+                | TokenKind::List
+                | TokenKind::CppType
+                | TokenKind::NamespaceScope(_) => Some(SemanticTokenType::Keyword),
+                TokenKind::BaseType(_) => Some(SemanticTokenType::Type),
+                TokenKind::Literal(_) => Some(SemanticTokenType::String),
+                TokenKind::IntConstant(_) | TokenKind::DoubleConstant(_) => {
+                    Some(SemanticTokenType::Number)
+                }
+                TokenKind::Comment(_) | TokenKind::BlockComment(_) | TokenKind::PoundComment(_) => {
+                    Some(SemanticTokenType::Comment)
+                }
+                // keys of annotations, e.g. `(cpp.type = "...")`
+                TokenKind::Identifier(_)
+                    if prev_kind != TokenKind::Assign
+                        && ext_nodes
+                            .iter()
+                            .any(|ext| ext.range.contains(token.position)) =>
+                {
+                    Some(SemanticTokenType::Decorator)
+                }
+                _ => None,
+            };
+
+            if let Some(token_type) = token_type {
+                let length = match &token.kind {
+                    TokenKind::Literal(value) => value.chars().count() + 2, // with quotes
+                    kind => kind.to_string().chars().count(),
+                };
+                push_source_tokens(&mut tokens, source, token.position, length, token_type);
+            }
+            prev_kind = token.kind;
+        }
+
+        tokens
+    }
+
+    /// Find the semantic tokens of identifiers. Their type and modifiers depend on
+    /// what they resolve to.
+    fn identifier_semantic_tokens(&self, path: &str) -> Vec<SemanticToken> {
+        let document_node = match self.document_nodes.get(path) {
+            Some(document_node) => document_node,
+            None => return vec![],
+        };
+        let symbol_table = self.symbol_tables.get(path);
+
+        let mut tokens = Vec::new();
+        for header in &document_node.headers {
+            if let HeaderNode::Namespace(namespace) = header.as_ref() {
+                push_identifier_token(
+                    &mut tokens,
+                    &namespace.identifier,
+                    SemanticTokenType::Namespace,
+                    0,
+                );
+            }
+        }
+
+        let deprecated = |ext: Option<&ExtNode>| match self.deprecation(ext) {
+            Some(_) => DEPRECATED,
+            None => 0,
+        };
+
+        // declarations
+        for definition in &document_node.definitions {
+            let (token_type, modifiers) = definition_semantic_token(definition);
+            push_identifier_token(
+                &mut tokens,
+                definition.identifier(),
+                token_type,
+                modifiers | DECLARATION | DEFINITION | deprecated(definition.ext()),
+            );
+
+            let mut push_fields = |fields: &[FieldNode], token_type| {
+                for field in fields {
+                    push_identifier_token(
+                        &mut tokens,
+                        &field.identifier,
+                        token_type,
+                        DECLARATION | deprecated(field.ext.as_ref()),
+                    );
+                }
+            };
+            match definition.as_ref() {
+                DefinitionNode::Struct(StructNode { fields, .. })
+                | DefinitionNode::Union(UnionNode { fields, .. })
+                | DefinitionNode::Exception(ExceptionNode { fields, .. }) => {
+                    push_fields(fields, SemanticTokenType::Property);
+                }
+                DefinitionNode::Service(service_node) => {
+                    for function in &service_node.functions {
+                        push_fields(&function.fields, SemanticTokenType::Parameter);
+                        push_fields(
+                            function.throws.as_deref().unwrap_or_default(),
+                            SemanticTokenType::Parameter,
+                        );
+                    }
+                    for function in &service_node.functions {
+                        push_identifier_token(
+                            &mut tokens,
+                            &function.identifier,
+                            SemanticTokenType::Function,
+                            DECLARATION | DEFINITION | deprecated(function.ext.as_ref()),
+                        );
+                    }
+                }
+                DefinitionNode::Enum(enum_node) => {
+                    for value in &enum_node.values {
+                        push_identifier_token(
+                            &mut tokens,
+                            &value.identifier,
+                            SemanticTokenType::EnumMember,
+                            DECLARATION | DEFINITION | READONLY | deprecated(value.ext.as_ref()),
+                        );
+                    }
+                }
+                DefinitionNode::Const(_) | DefinitionNode::Typedef(_) => {}
+            }
+        }
+
+        // types, e.g. `shared.SharedStruct`
+        for identifier in self.find_type_references(document_node) {
+            let resolved =
+                symbol_table.and_then(|table| table.find_definition_of_identifier_type(identifier));
+            let (def, header) = match resolved {
+                Some((_, def, header)) => (def, header),
+                None => {
+                    push_identifier_token(&mut tokens, identifier, SemanticTokenType::Type, 0);
+                    continue;
+                }
+            };
+
+            let mut name = identifier.clone();
+            if header.is_some() {
+                if let (Some(namespace), rest) = identifier.split_by_first_dot() {
+                    push_identifier_token(&mut tokens, &namespace, SemanticTokenType::Namespace, 0);
+                    name = rest;
+                }
+            }
+            let (token_type, modifiers) = definition_semantic_token(&def);
+            push_identifier_token(
+                &mut tokens,
+                &name,
+                token_type,
+                modifiers | deprecated(def.ext()),
+            );
+        }
+
+        // values, e.g. `shared.Numberz.FIVE`
+        for identifier in self.find_value_references(document_node) {
+            let resolved =
+                symbol_table.and_then(|table| table.find_definition_of_const_value(identifier));
+            let (value, header) = match resolved {
+                Some(x) => x,
+                None => continue,
+            };
+
+            let mut name = identifier.clone();
+            if header.is_some() {
+                if let (Some(namespace), rest) = identifier.split_by_first_dot() {
+                    push_identifier_token(&mut tokens, &namespace, SemanticTokenType::Namespace, 0);
+                    name = rest;
+                }
+            }
+            let modifiers = match self.value_deprecation(&value) {
+                Some(_) => READONLY | DEPRECATED,
+                None => READONLY,
+            };
+            match value.member {
+                Some(_) => {
+                    let (enum_name, member) = name.split_by_first_dot();
+                    if let Some(enum_name) = enum_name {
+                        push_identifier_token(
+                            &mut tokens,
+                            &enum_name,
+                            SemanticTokenType::Enum,
+                            deprecated(value.definition.ext()),
+                        );
+                    }
+                    push_identifier_token(
+                        &mut tokens,
+                        &member,
+                        SemanticTokenType::EnumMember,
+                        modifiers,
+                    );
+                }
+                None => push_identifier_token(
+                    &mut tokens,
+                    &name,
+                    SemanticTokenType::Variable,
+                    modifiers,
+                ),
+            }
+        }
+
+        // `true` and `false`
+        let mut values: Vec<&ConstValueNode> = Vec::new();
+        collect_nodes(document_node.as_ref(), &mut values);
+        for value in values {
+            if matches!(value.kind, ConstValueKind::Bool) {
+                tokens.push(SemanticToken {
+                    start: value.range.start,
+                    length: value.value.chars().count() as u32,
+                    token_type: SemanticTokenType::Keyword,
+                    modifiers: 0,
+                });
+            }
+        }
+
+        tokens
+    }
+
+    /// Collect all IdentifierNode instances used as field types in the document nodes.
+    fn collect_field_type_identifiers<'a>(
+        &'a self,
+        field_type: &'a FieldTypeNode,
+    ) -> Vec<&'a IdentifierNode> {
+        match field_type {
+            FieldTypeNode::Identifier(identifier) => vec![identifier],
+            FieldTypeNode::BaseType(_) => vec![],
+            FieldTypeNode::MapType(map_type) => {
+                let mut result = self.collect_field_type_identifiers(&map_type.key_type);
+                result.extend(self.collect_field_type_identifiers(&map_type.value_type));
+                result
+            }
+            FieldTypeNode::SetType(set_type) => {
+                self.collect_field_type_identifiers(&set_type.type_node)
+            }
+            FieldTypeNode::ListType(list_type) => {
+                self.collect_field_type_identifiers(&list_type.type_node)
+            }
+        }
+    }
+}
+
+/// References
+impl Analyzer {
+    /// Find the definition referenced by the identifier at a specific position.
+    fn definition_at(&self, path: &str, pos: Position) -> Option<(String, Rc<DefinitionNode>)> {
+        let document_node = self.document_nodes.get(path)?.as_ref();
+        let (identifier, is_value) = self.find_reference_identifier(document_node, pos)?;
+        let symbol_table = self.symbol_tables.get(path)?;
+
+        if is_value {
+            let (value, _) = symbol_table.find_definition_of_const_value(identifier)?;
+            // enum members are not definitions on their own
+            if value.member.is_some() {
+                return None;
+            }
+            return Some((value.path, value.definition));
+        }
+
+        let (def_path, def, _) = symbol_table.find_definition_of_identifier_type(identifier)?;
+        Some((def_path, def))
+    }
+
+    /// Find the definition whose name is at a specific position.
+    fn declaration_at(&self, path: &str, pos: Position) -> Option<(String, Rc<DefinitionNode>)> {
+        let document_node = self.document_nodes.get(path)?;
+        let identifier = self.find_identifier(document_node.as_ref(), pos)?;
+        let def = document_node
+            .definitions
+            .iter()
+            .find(|definition| ptr::eq(definition.identifier(), identifier))?;
+        Some((path.to_string(), def.clone()))
+    }
+
+    /// Find all identifiers used as constant values in a document: const values
+    /// and default values of fields.
+    fn find_value_references<'a>(
+        &'a self,
+        document_node: &'a DocumentNode,
+    ) -> Vec<&'a IdentifierNode> {
+        document_node
+            .definitions
+            .iter()
+            .flat_map(|definition| self.definition_value_references(definition))
+            .collect()
+    }
+
+    /// Find all identifiers used as constant values in a definition.
+    fn definition_value_references<'a>(
+        &'a self,
+        definition: &'a DefinitionNode,
+    ) -> Vec<&'a IdentifierNode> {
+        let mut result = Vec::new();
+        let mut fields: Vec<&FieldNode> = Vec::new();
+
+        match definition {
+            DefinitionNode::Const(const_node) => {
+                result.extend(const_node.value.identifiers());
+            }
+            DefinitionNode::Struct(struct_node) => fields.extend(&struct_node.fields),
+            DefinitionNode::Union(union_node) => fields.extend(&union_node.fields),
+            DefinitionNode::Exception(exception_node) => fields.extend(&exception_node.fields),
+            DefinitionNode::Service(service_node) => {
+                for function in &service_node.functions {
+                    fields.extend(&function.fields);
+                }
+            }
+            _ => {}
+        }
+
+        for field in fields {
+            if let Some(default_value) = &field.default_value {
+                result.extend(default_value.identifiers());
+            }
+        }
+
+        result
+    }
+
+    /// Find all identifiers used as types in a document: field types, typedef targets,
+    /// function return types, `extends` and `throws`.
+    fn find_type_references<'a>(
+        &'a self,
+        document_node: &'a DocumentNode,
+    ) -> Vec<&'a IdentifierNode> {
+        document_node
+            .definitions
+            .iter()
+            .flat_map(|definition| self.definition_type_references(definition))
+            .collect()
+    }
+
+    /// Find all identifiers used as types in a definition, including the functions of a service.
+    fn definition_type_references<'a>(
+        &'a self,
+        definition: &'a DefinitionNode,
+    ) -> Vec<&'a IdentifierNode> {
+        let mut result = Vec::new();
+
+        match definition {
+            DefinitionNode::Const(const_node) => {
+                result.extend(self.collect_field_type_identifiers(&const_node.field_type));
+            }
+            DefinitionNode::Typedef(typedef_node) => {
+                result.extend(self.collect_field_type_identifiers(&typedef_node.definition_type));
+            }
+            DefinitionNode::Struct(struct_node) => {
+                for field in &struct_node.fields {
+                    result.extend(self.collect_field_type_identifiers(&field.field_type));
+                }
+            }
+            DefinitionNode::Union(union_node) => {
+                for field in &union_node.fields {
+                    result.extend(self.collect_field_type_identifiers(&field.field_type));
+                }
+            }
+            DefinitionNode::Exception(exception_node) => {
+                for field in &exception_node.fields {
+                    result.extend(self.collect_field_type_identifiers(&field.field_type));
+                }
+            }
+            DefinitionNode::Service(service_node) => {
+                if let Some(extends) = &service_node.extends {
+                    result.push(extends);
+                }
+
+                for function in &service_node.functions {
+                    result.extend(self.function_type_references(function));
+                }
+            }
+            DefinitionNode::Enum(_) => {}
+        }
+
+        result
+    }
+
+    /// Find all identifiers used as types in a function: return type, arguments and `throws`.
+    fn function_type_references<'a>(
+        &'a self,
+        function: &'a FunctionNode,
+    ) -> Vec<&'a IdentifierNode> {
+        let mut result = Vec::new();
+
+        if let Some(function_type) = &function.function_type {
+            result.extend(self.collect_field_type_identifiers(function_type));
+        }
+        for field in &function.fields {
+            result.extend(self.collect_field_type_identifiers(&field.field_type));
+        }
+        if let Some(throws) = &function.throws {
+            for throw in throws {
+                result.extend(self.collect_field_type_identifiers(&throw.field_type));
+            }
+        }
+
+        result
+    }
+
+    /// Find all usages of a definition across every indexed document. A usage of an
+    /// enum member is a usage of its enum, located at the enum name.
+    fn find_references(&self, def_path: &str, def: &DefinitionNode) -> Vec<Location> {
+        self.find_usages(def_path, def)
+            .into_iter()
+            .map(|(mut location, member)| {
+                if let (DefinitionNode::Enum(enum_node), Some(member)) = (def, member) {
+                    if let Some(value) = enum_node.values.get(member) {
+                        location.range =
+                            enum_segment_range(&location.range, def.name(), &value.identifier.name);
+                    }
+                }
+                location
+            })
+            .collect()
+    }
+
+    /// Find all usages of a definition across every indexed document, including the
+    /// usages of its members if it is an enum, with the index of the member used.
+    fn find_usages(&self, def_path: &str, def: &DefinitionNode) -> Vec<(Location, Option<usize>)> {
+        let mut usages = Vec::new();
+
+        // indexed files include the parsed ones and the files that are not open
+        for (path, document_node) in self.index.documents() {
+            let symbol_table = match self.workspace_symbol_table(path) {
+                Some(symbol_table) => symbol_table,
+                None => continue,
+            };
+
+            for identifier in self.find_type_references(document_node) {
+                let (ref_path, ref_def, _) =
+                    match symbol_table.find_definition_of_identifier_type(identifier) {
+                        Some(x) => x,
+                        None => continue,
+                    };
+                if ref_path == def_path && ref_def.name() == def.name() {
+                    let location = Location {
+                        path: path.clone(),
+                        range: identifier.range(),
+                    };
+                    usages.push((location, None));
+                }
+            }
+
+            for identifier in self.find_value_references(document_node) {
+                let (value, _) = match symbol_table.find_definition_of_const_value(identifier) {
+                    Some(x) => x,
+                    None => continue,
+                };
+                if value.path == def_path && value.definition.name() == def.name() {
+                    let location = Location {
+                        path: path.clone(),
+                        range: identifier.range(),
+                    };
+                    usages.push((location, value.member));
+                }
+            }
+        }
+
+        usages.sort_by(|(a, _), (b, _)| (&a.path, &a.range).cmp(&(&b.path, &b.range)));
+        usages
+    }
+}
+
+/// Definition
+impl Analyzer {
+    /// Find the location of a type, following typedefs.
+    fn type_location(
+        &self,
+        path: &str,
+        field_type: &FieldTypeNode,
+        depth: usize,
+    ) -> Option<Location> {
+        match field_type {
+            FieldTypeNode::Identifier(identifier) => {
+                let symbol_table = self.workspace_symbol_table(path)?;
+                let (def_path, def, _) =
+                    symbol_table.find_definition_of_identifier_type(identifier)?;
+                self.definition_type_location(&def_path, def.as_ref(), depth)
+            }
+            _ => Some(Location {
+                path: path.to_string(),
+                range: field_type.range(),
+            }),
+        }
+    }
+
+    /// Find the location of the type of a definition, following typedefs. Other types
+    /// are their own type.
+    fn definition_type_location(
+        &self,
+        path: &str,
+        def: &DefinitionNode,
+        depth: usize,
+    ) -> Option<Location> {
+        match def {
+            DefinitionNode::Typedef(_) | DefinitionNode::Const(_) if depth >= MAX_TYPEDEF_DEPTH => {
+                None
+            }
+            DefinitionNode::Typedef(typedef_node) => {
+                self.type_location(path, &typedef_node.definition_type, depth + 1)
+            }
+            DefinitionNode::Const(const_node) => {
+                self.type_location(path, &const_node.field_type, depth + 1)
+            }
+            _ => Some(Location {
+                path: path.to_string(),
+                range: def.identifier().range(),
+            }),
+        }
+    }
+
+    /// Get the type on the wire of a type, replacing typedefs with the types they stand for.
+    fn wire_type(&self, path: &str, field_type: &FieldTypeNode, depth: usize) -> Option<String> {
+        if depth >= MAX_TYPEDEF_DEPTH {
+            return None;
+        }
+
+        match field_type {
+            FieldTypeNode::Identifier(identifier) => {
+                let symbol_table = self.workspace_symbol_table(path)?;
+                let (def_path, def, _) =
+                    symbol_table.find_definition_of_identifier_type(identifier)?;
+                match def.as_ref() {
+                    DefinitionNode::Typedef(typedef_node) => {
+                        self.wire_type(&def_path, &typedef_node.definition_type, depth + 1)
+                    }
+                    _ => Some(identifier.name.clone()),
+                }
+            }
+            FieldTypeNode::BaseType(base_type) => Some(base_type.name.clone()),
+            FieldTypeNode::MapType(map_type) => Some(format!(
+                "map<{}, {}>",
+                self.wire_type(path, &map_type.key_type, depth + 1)?,
+                self.wire_type(path, &map_type.value_type, depth + 1)?
+            )),
+            FieldTypeNode::SetType(set_type) => Some(format!(
+                "set<{}>",
+                self.wire_type(path, &set_type.type_node, depth + 1)?
+            )),
+            FieldTypeNode::ListType(list_type) => Some(format!(
+                "list<{}>",
+                self.wire_type(path, &list_type.type_node, depth + 1)?
+            )),
+        }
+    }
+
+    /// Find an identifier at a specific position.
+    fn find_identifier<'a>(&self, node: &'a dyn Node, pos: Position) -> Option<&'a IdentifierNode> {
+        if !node.range().contains(pos) {
+            return None;
+        }
+
+        if let Some(identifier) = node.as_any().downcast_ref::<IdentifierNode>() {
+            return Some(identifier);
+        }
+
+        for child in node.children() {
+            if let Some(identifier) = self.find_identifier(child, pos) {
+                return Some(identifier);
+            }
+        }
+
+        None
+    }
+
+    /// Find the identifier at a specific position, and whether it is used as a
+    /// constant value rather than as a type.
+    fn find_reference_identifier<'a>(
+        &self,
+        node: &'a dyn Node,
+        pos: Position,
+    ) -> Option<(&'a IdentifierNode, bool)> {
+        let identifier = self.find_identifier(node, pos)?;
+        let ancestors = self.find_ancestors(node, identifier.range.start);
+        let is_value = match ancestors.as_slice() {
+            [.., parent, last] => {
+                parent.as_any().is::<ConstValueNode>()
+                    && last
+                        .as_any()
+                        .downcast_ref::<IdentifierNode>()
+                        .is_some_and(|last| ptr::eq(last, identifier))
+            }
+            _ => false,
+        };
+        Some((identifier, is_value))
+    }
+
+    /// Find the chain of nodes containing a position, from the root to the innermost node.
+    fn find_ancestors<'a>(&self, node: &'a dyn Node, pos: Position) -> Vec<&'a dyn Node> {
+        let mut result = Vec::new();
+        if !node.range().contains(pos) {
+            return result;
+        }
+
+        let mut current = node;
+        loop {
+            result.push(current);
+            match current
+                .children()
+                .into_iter()
+                .find(|child| child.range().contains(pos))
+            {
+                Some(child) => current = child,
+                None => break,
+            }
+        }
+
+        result
+    }
+}
+
+/// Type hierarchy
+impl Analyzer {
+    /// Get the symbol table of a file. Files that are only indexed get a table built
+    /// from the index, resolving their direct includes, and cached until the index changes.
+    fn workspace_symbol_table(&self, path: &str) -> Option<Rc<SymbolTable>> {
+        if let Some(symbol_table) = self.symbol_tables.get(path) {
+            return Some(symbol_table.clone());
+        }
+
+        self.index.symbol_table(path, |document_node| {
+            let mut symbol_table = SymbolTable::new_from_ast(path, document_node);
+            for header in &document_node.headers {
+                let dep_path = match header.as_ref() {
+                    HeaderNode::Include(include) => match include_path(path, &include.literal) {
+                        Some(dep_path) => dep_path,
+                        None => continue,
+                    },
+                    _ => continue,
+                };
+
+                let dep_table = match self.symbol_tables.get(&dep_path) {
+                    Some(dep_table) => dep_table.clone(),
+                    None => match self.index.document(&dep_path) {
+                        Some(dep_node) => Rc::new(SymbolTable::new_from_ast(&dep_path, dep_node)),
+                        None => continue,
+                    },
+                };
+                symbol_table.add_dependency(&dep_path, header.clone(), dep_table);
+            }
+            symbol_table
+        })
+    }
+
+    /// Find a service by name in a file, along with the symbol table of the file.
+    fn find_service(
+        &self,
+        path: &str,
+        name: &str,
+    ) -> Option<(Rc<SymbolTable>, Rc<DefinitionNode>)> {
+        let symbol_table = self.workspace_symbol_table(path)?;
+        let def = symbol_table.types().get(name)?.clone();
+        match def.as_ref() {
+            DefinitionNode::Service(_) => Some((symbol_table, def)),
+            _ => None,
+        }
+    }
+}
+
+/// Call hierarchy
+impl Analyzer {
+    /// Find the definition whose name starts at a position in an indexed file.
+    fn find_definition_starting_at(&self, path: &str, pos: Position) -> Option<Rc<DefinitionNode>> {
+        self.index
+            .document(path)?
+            .definitions
+            .iter()
+            .find(|definition| definition.identifier().range.start == pos)
+            .cloned()
+    }
+}
+
+/// Rename
+impl Analyzer {
+    /// Find the symbol to rename at a specific position, along with the identifier under it.
+    fn find_rename_target(
+        &self,
+        path: &str,
+        pos: Position,
+    ) -> Option<(&IdentifierNode, RenameTarget<'_>)> {
+        let document_node = self.document_nodes.get(path)?.as_ref();
+        let ancestors = self.find_ancestors(document_node, pos);
+        let (identifier, parents) = ancestors.split_last()?;
+        let identifier = identifier.as_any().downcast_ref::<IdentifierNode>()?;
+        let parent = parents.last()?.as_any();
+
+        if let Some(field) = parent.downcast_ref::<FieldNode>() {
+            if ptr::eq(&field.identifier, identifier) {
+                let container = parents.get(parents.len().checked_sub(2)?)?.as_any();
+                let fields = field_siblings(container, field)?;
+                return Some((identifier, RenameTarget::Field(identifier, fields)));
+            }
+        }
+
+        if let Some(function) = parent.downcast_ref::<FunctionNode>() {
+            if ptr::eq(&function.identifier, identifier) {
+                let service = parents
+                    .get(parents.len().checked_sub(2)?)?
+                    .as_any()
+                    .downcast_ref::<ServiceNode>()?;
+                return Some((
+                    identifier,
+                    RenameTarget::Function(identifier, &service.functions),
+                ));
+            }
+        }
+
+        let (def_path, def) = self.definition_at(path, pos)?;
+        Some((identifier, RenameTarget::Definition(def_path, def)))
+    }
+}
+
+/// Code actions
+impl Analyzer {
+    /// Quick fixes for an undefined type: one for each other file defining a type with
+    /// its name, which includes the file if needed and qualifies the type with it.
+    fn missing_include_fixes(&self, path: &str, error: &Error, reference: &str) -> Vec<CodeAction> {
+        let (document_node, symbol_table) =
+            match (self.document_nodes.get(path), self.symbol_tables.get(path)) {
+                (Some(document_node), Some(symbol_table)) => (document_node, symbol_table),
+                _ => return vec![],
+            };
+        let name = reference.rsplit('.').next().unwrap_or(reference);
+
+        let mut candidates: Vec<&String> = self
+            .index
+            .documents()
+            .iter()
+            .filter(|(candidate, document)| {
+                *candidate != path
+                    && document.definitions.iter().any(|d| {
+                        !matches!(d.as_ref(), DefinitionNode::Const(_)) && d.name() == name
+                    })
+            })
+            .map(|(candidate, _)| candidate)
+            .collect();
+        candidates.sort();
+
+        candidates
+            .into_iter()
+            .filter_map(|candidate| {
+                let included = included_namespace(document_node, path, candidate);
+
+                let qualified_edit = |namespace: &str| TextEdit {
+                    range: error.range.clone(),
+                    new_text: format!("{}.{}", namespace, name),
+                };
+                let (title, edits) = match included {
+                    Some(namespace) => (
+                        format!("Use `{}.{}`", namespace, name),
+                        vec![qualified_edit(&namespace)],
+                    ),
+                    None => {
+                        let literal = relative_path(path, candidate);
+                        let namespace = include_namespace(&literal);
+                        // another file is already included under this name
+                        if symbol_table.includes().contains_key(&namespace) {
+                            return None;
+                        }
+                        (
+                            format!(
+                                "Add `include \"{}\"` and use `{}.{}`",
+                                literal, namespace, name
+                            ),
+                            vec![
+                                include_edit(document_node, &[&literal]),
+                                qualified_edit(&namespace),
+                            ],
+                        )
+                    }
+                };
+
+                Some(CodeAction {
+                    title,
+                    kind: CodeActionKind::QuickFix,
+                    diagnostics: vec![error.clone()],
+                    edit: Some(WorkspaceEdit {
+                        changes: HashMap::from([(path.to_string(), edits)]),
+                    }),
+                    command: None,
+                })
+            })
+            .collect()
+    }
+
+    /// Quick fixes for an undefined type without namespace: one for each kind of
+    /// definition the type can be, which adds an empty definition after the definition
+    /// using the type. Only exceptions can be thrown, and only services extended.
+    fn create_definition_fixes(
+        &self,
+        path: &str,
+        error: &Error,
+        reference: &str,
+    ) -> Vec<CodeAction> {
+        let (document_node, source) = match (self.document_nodes.get(path), self.sources.get(path))
+        {
+            (Some(document_node), Some(source)) => (document_node, source),
+            _ => return vec![],
+        };
+        if !is_valid_identifier(reference) {
+            return vec![];
+        }
+        let definition = match document_node
+            .definitions
+            .iter()
+            .find(|d| d.range().contains(error.range.start))
+        {
+            Some(definition) => definition,
+            None => return vec![],
+        };
+
+        let kinds: &[&str] = match definition.as_ref() {
+            DefinitionNode::Service(service)
+                if service
+                    .extends
+                    .as_ref()
+                    .is_some_and(|extends| extends.range == error.range) =>
+            {
+                &["service"]
+            }
+            DefinitionNode::Service(service)
+                if service
+                    .functions
+                    .iter()
+                    .flat_map(|f| f.throws.iter().flatten())
+                    .any(|field| field.field_type.range() == error.range) =>
+            {
+                &["exception"]
+            }
+            _ => &["struct", "enum", "exception"],
+        };
+
+        // the definition goes on the line after the one using the type
+        let line = definition.range().end.line + 1;
+        let pos = line_start_or_end(source, line);
+        let separator = if pos.line == line { "\n" } else { "\n\n" };
+
+        kinds
+            .iter()
+            .map(|kind| CodeAction {
+                title: format!("Create {} `{}`", kind, reference),
+                kind: CodeActionKind::QuickFix,
+                diagnostics: vec![error.clone()],
+                edit: Some(WorkspaceEdit {
+                    changes: HashMap::from([(
+                        path.to_string(),
+                        vec![TextEdit {
+                            range: Range {
+                                start: pos,
+                                end: pos,
+                            },
+                            new_text: format!("{}{} {} {{}}\n", separator, kind, reference),
+                        }],
+                    )]),
+                }),
+                command: None,
+            })
+            .collect()
+    }
+
+    /// Quick fix for a duplicate field ID: change it to the next free ID.
+    fn duplicate_field_id_fix(&self, path: &str, error: &Error) -> Option<CodeAction> {
+        let document_node = self.document_nodes.get(path)?;
+        let (fields, _, _) = field_lists(document_node)
+            .into_iter()
+            .find(|(fields, _, _)| {
+                fields.iter().any(|f| {
+                    f.field_id
+                        .as_ref()
+                        .is_some_and(|id| id.range == error.range)
+                })
+            })?;
+        let id = next_field_id(fields);
+
+        Some(CodeAction {
+            title: format!("Change field ID to {}", id),
+            kind: CodeActionKind::QuickFix,
+            diagnostics: vec![error.clone()],
+            edit: Some(WorkspaceEdit {
+                changes: HashMap::from([(
+                    path.to_string(),
+                    vec![TextEdit {
+                        range: error.range.clone(),
+                        new_text: id.to_string(),
+                    }],
+                )]),
+            }),
+            command: None,
+        })
+    }
+
+    /// Refactorings giving the fields without an ID in the field lists touching a range
+    /// the next free IDs, in order.
+    fn missing_field_ids_actions(&self, path: &str, range: &Range) -> Vec<CodeAction> {
+        let document_node = match self.document_nodes.get(path) {
+            Some(document_node) => document_node,
+            None => return vec![],
+        };
+
+        field_lists(document_node)
+            .into_iter()
+            .filter(|(fields, owner, _)| {
+                owner.start <= range.end
+                    && range.start <= owner.end
+                    && fields.iter().any(|f| f.field_id.is_none())
+            })
+            .map(|(fields, _, description)| {
+                let edits = (next_field_id(fields)..)
+                    .zip(fields.iter().filter(|f| f.field_id.is_none()))
+                    .map(|(id, field)| TextEdit {
+                        range: Range {
+                            start: field.range.start,
+                            end: field.range.start,
+                        },
+                        new_text: format!("{}: ", id),
+                    })
+                    .collect();
+
+                CodeAction {
+                    title: format!("Assign explicit IDs to all {}", description),
+                    kind: CodeActionKind::RefactorRewrite,
+                    diagnostics: vec![],
+                    edit: Some(WorkspaceEdit {
+                        changes: HashMap::from([(path.to_string(), edits)]),
+                    }),
+                    command: None,
+                }
+            })
+            .collect()
+    }
+
+    /// Refactoring extracting the container type in a range into a typedef. Its name is
+    /// asked to the user, so the edit is computed by a command.
+    fn extract_typedef_action(&self, path: &str, range: &Range) -> Option<CodeAction> {
+        let document_node = self.document_nodes.get(path)?;
+        let target = extract_target(document_node, range)?;
+
+        Some(CodeAction {
+            title: format!("Extract `{}` into a typedef", target),
+            kind: CodeActionKind::RefactorExtract,
+            diagnostics: vec![],
+            edit: None,
+            command: Some(CodeActionCommand::ExtractTypedef {
+                path: path.to_string(),
+                range: target.range(),
+            }),
+        })
+    }
+
+    /// Refactoring replacing the usages of the typedef in a range (its definition or a
+    /// usage) with its type, and removing the typedef once it is unused.
+    fn inline_typedef_action(&self, path: &str, range: &Range) -> Option<CodeAction> {
+        let document_node = self.document_nodes.get(path)?;
+        let (def_path, def) = match self.definition_at(path, range.start) {
+            Some((def_path, def)) => (def_path, def),
+            None => (
+                path.to_string(),
+                document_node
+                    .definitions
+                    .iter()
+                    .find(|d| d.range().contains(range.start))?
+                    .clone(),
+            ),
+        };
+        let typedef = match def.as_ref() {
+            DefinitionNode::Typedef(typedef) => typedef,
+            _ => return None,
+        };
+        let (def_document, source) = match (
+            self.document_nodes.get(&def_path),
+            self.sources.get(&def_path),
+        ) {
+            (Some(document_node), Some(source)) => (document_node, source),
+            _ => return None,
+        };
+        let type_text = text_in_range(source, &typedef.definition_type.range())?;
+
+        // types named in the typedef may need another name in other files
+        let mut identifiers: Vec<&IdentifierNode> = Vec::new();
+        collect_nodes(&*typedef.definition_type, &mut identifiers);
+        let portable = identifiers.is_empty();
+        // usages in the current file would be left alone
+        if def_path != path && !portable {
+            return None;
+        }
+
+        let mut edit = WorkspaceEdit::default();
+        let mut unused = true;
+        for location in self.find_references(&def_path, def.as_ref()) {
+            if location.path != def_path && !portable {
+                unused = false;
+                continue;
+            }
+            edit.changes
+                .entry(location.path)
+                .or_default()
+                .push(TextEdit {
+                    range: location.range,
+                    new_text: type_text.clone(),
+                });
+        }
+        if unused {
+            edit.changes
+                .entry(def_path)
+                .or_default()
+                .push(definition_removal(def_document, source, def.as_ref()));
+        }
+
+        Some(CodeAction {
+            title: format!("Inline typedef `{}`", typedef.identifier.name),
+            kind: CodeActionKind::RefactorInline,
+            diagnostics: vec![],
+            edit: Some(edit),
+            command: None,
+        })
+    }
+
+    /// Refactoring moving the definition whose name is in a range to another file. The
+    /// file is chosen by the user, so the edit is computed by a command.
+    fn move_definition_action(&self, path: &str, range: &Range) -> Option<CodeAction> {
+        let document_node = self.document_nodes.get(path)?;
+        let def = document_node
+            .definitions
+            .iter()
+            .find(|d| d.identifier().range().contains(range.start))?;
+
+        Some(CodeAction {
+            title: format!("Move `{}` to another file", def.name()),
+            kind: CodeActionKind::RefactorMove,
+            diagnostics: vec![],
+            edit: None,
+            command: Some(CodeActionCommand::MoveDefinition {
+                path: path.to_string(),
+                position: def.identifier().range().start,
+            }),
+        })
+    }
+}
+
+/// Hover
+impl Analyzer {
+    /// Render a definition as Markdown: its source, doc comment and file.
+    fn hover_definition(&self, path: &str, def: &DefinitionNode) -> Option<String> {
+        let source = self.sources.get(path)?;
+        let range = def.range();
+        let code = text_in_range(source, &range)?;
+
+        Some(hover_markdown(
+            path,
+            source,
+            &code,
+            range.start.line,
+            self.deprecation(def.ext()),
+        ))
+    }
+
+    /// Render a constant value as Markdown. Enum members are shown qualified by their enum.
+    fn hover_value(&self, value: &ValueDefinition) -> Option<String> {
+        let enum_value = match value.enum_value() {
+            Some(enum_value) => enum_value,
+            None => return self.hover_definition(&value.path, value.definition.as_ref()),
+        };
+
+        let source = self.sources.get(&value.path)?;
+        let mut code = format!("{}.{}", value.definition.name(), enum_value.identifier.name);
+        let number = match (value.definition.as_ref(), value.member) {
+            (DefinitionNode::Enum(enum_node), Some(member)) => {
+                enum_node.numbers().get(member).copied()
+            }
+            _ => None,
+        };
+        if let Some(number) = number {
+            code.push_str(&format!(" = {}", number));
+        }
+
+        Some(hover_markdown(
+            &value.path,
+            source,
+            &code,
+            enum_value.range.start.line,
+            self.value_deprecation(value),
+        ))
+    }
+
+    /// Render an include header as Markdown.
+    fn hover_include(&self, path: &str, header: &HeaderNode) -> Option<String> {
+        let source = self.sources.get(path)?;
+        let text = text_in_range(source, &header.range())?;
+
+        let mut contents = format!("```thrift\n{}\n```", text);
+        if let HeaderNode::Include(include) = header {
+            if let Some(dep_path) = include_path(path, &include.literal) {
+                contents.push_str(&format!("\n\n---\n\n`{}`", dep_path));
+            }
+        }
+
+        Some(contents)
+    }
+}
+
+/// Completion
+impl Analyzer {
+    /// Get the offset at a specific position.
+    fn offset_at_position(&self, path: &str, pos: Position) -> Option<usize> {
+        offset_at(self.documents.get(path)?, pos)
+    }
+
+    /// Get the identifier at the previous offset. no consider the '.'.
+    fn idet_prev_offset(&self, path: &str, offset: usize) -> Option<String> {
         let document = self.documents.get(path)?;
 
-        Some(
-            document[..offset]
-                .iter()
-                .rev()
-                .take_while(|&&c| c.is_ascii_alphanumeric() || c == '_')
-                .collect::<Vec<_>>()
-                .into_iter()
-                .rev()
-                .collect(),
-        )
+        Some(
+            document[..offset]
+                .iter()
+                .rev()
+                .take_while(|&&c| c.is_ascii_alphanumeric() || c == '_')
+                .collect::<Vec<_>>()
+                .into_iter()
+                .rev()
+                .collect(),
+        )
+    }
+}
+
+impl Analyzer {
+    fn read_file(&self, path: &str) -> io::Result<String> {
+        if let Some(read_file) = &self.wasm_read_file {
+            read_file(path.to_string())
+        } else {
+            fs::read_to_string(path)
+        }
+    }
+}
+
+/// Recursively collects the Thrift files under a directory, skipping hidden directories,
+/// dependency and build output directories, and symlinked directories.
+fn find_thrift_files(dir: &Path, paths: &mut Vec<String>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let file_type = match entry.file_type() {
+            Ok(file_type) => file_type,
+            Err(_) => continue,
+        };
+
+        // symlinked directories are not followed, they may form a loop
+        if file_type.is_dir() {
+            let skipped = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(is_skipped_directory);
+            if !skipped {
+                find_thrift_files(&path, paths);
+            }
+        } else if path.extension().is_some_and(|ext| ext == "thrift") && path.is_file() {
+            if let Some(path) = path.to_str() {
+                paths.push(path.to_string());
+            }
+        }
+    }
+}
+
+/// Check if a directory is skipped when indexing a workspace: hidden directories,
+/// dependencies and build outputs.
+fn is_skipped_directory(name: &str) -> bool {
+    name.starts_with('.') || SKIPPED_DIRECTORIES.contains(&name)
+}
+
+/// Returns the path of an included file, relative to the including file. The path is
+/// normalized, so a file has the same path whichever file includes it.
+fn include_path(path: &str, literal: &str) -> Option<String> {
+    let parent = path_parent(path)?;
+    Some(normalize_path(&parent.join(literal).to_string_lossy()))
+}
+
+/// Returns the name an included file is referred to by, e.g. `shared` for
+/// `include "common/shared.thrift"`.
+fn include_namespace(literal: &str) -> String {
+    let name = file_name(literal);
+    match name.rsplit_once('.') {
+        Some((stem, _)) if !stem.is_empty() => stem.to_string(),
+        _ => name.to_string(),
+    }
+}
+
+/// Returns the name a document refers to an included file by, or `None` if the document
+/// does not include the file.
+fn included_namespace(document: &DocumentNode, path: &str, file: &str) -> Option<String> {
+    document.headers.iter().find_map(|h| match h.as_ref() {
+        HeaderNode::Include(include) => include_path(path, &include.literal)
+            .filter(|p| p == file)
+            .map(|_| include_namespace(&include.literal)),
+        _ => None,
+    })
+}
+
+/// Returns true if a document includes a file under a name.
+fn has_include_namespace(document: &DocumentNode, namespace: &str) -> bool {
+    document.headers.iter().any(|h| match h.as_ref() {
+        HeaderNode::Include(include) => include_namespace(&include.literal) == namespace,
+        _ => false,
+    })
+}
+
+/// Returns the edit adding includes to a document: after the last include, before
+/// the other headers, or before the first definition and the comments above it.
+fn include_edit(document: &DocumentNode, literals: &[&str]) -> TextEdit {
+    let include = literals
+        .iter()
+        .map(|literal| format!("include \"{}\"", literal))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let last_include = document
+        .headers
+        .iter()
+        .rfind(|h| matches!(h.as_ref(), HeaderNode::Include(_)));
+    if let Some(last_include) = last_include {
+        let end = last_include.range().end;
+        return TextEdit {
+            range: Range { start: end, end },
+            new_text: format!("\n{}", include),
+        };
+    }
+
+    let mut start = match (document.headers.first(), document.definitions.first()) {
+        (Some(header), _) => header.range().start,
+        (None, Some(definition)) => definition.range().start,
+        (None, None) => Position { line: 1, column: 1 },
+    };
+    if document.headers.is_empty() {
+        start = leading_comments_start(document, start);
+    }
+    let start = Position {
+        line: start.line,
+        column: 1,
+    };
+
+    let new_text = if document.headers.is_empty() && document.definitions.is_empty() {
+        format!("{}\n", include)
+    } else {
+        format!("{}\n\n", include)
+    };
+    TextEdit {
+        range: Range { start, end: start },
+        new_text,
+    }
+}
+
+/// Returns the start of the comments right above a position, or the position if there
+/// are none.
+fn leading_comments_start(document: &DocumentNode, pos: Position) -> Position {
+    let mut start = pos;
+    while let Some(comment) = document.comments.iter().find(|c| {
+        c.range.start < start && c.range.end <= start && c.range.end.line + 1 >= start.line
+    }) {
+        start = comment.range.start;
+    }
+
+    start
+}
+
+/// Returns the edit removing the lines of a definition and the comments above it,
+/// with a blank line around it if there is one on each side. The last definition of a
+/// document is removed with the blank lines above it.
+fn definition_removal(
+    document: &DocumentNode,
+    content: &[char],
+    definition: &DefinitionNode,
+) -> TextEdit {
+    let mut start_line = leading_comments_start(document, definition.range().start).line;
+    let mut end_line = definition.range().end.line + 1;
+    let rest = offset_at(
+        content,
+        Position {
+            line: end_line,
+            column: 1,
+        },
+    )
+    .map_or(&[][..], |offset| &content[offset..]);
+    if rest.iter().all(|c| c.is_whitespace()) {
+        while start_line > 1 && is_blank_line(content, start_line - 1) {
+            start_line -= 1;
+        }
+        end_line = u32::MAX;
+    } else if is_blank_line(content, end_line)
+        && (start_line == 1 || is_blank_line(content, start_line - 1))
+    {
+        end_line += 1;
+    }
+
+    TextEdit {
+        range: Range {
+            start: Position {
+                line: start_line,
+                column: 1,
+            },
+            end: line_start_or_end(content, end_line),
+        },
+        new_text: String::new(),
+    }
+}
+
+/// Returns the edit adding a definition at the end of a document, after a blank line.
+fn append_edit(content: &[char], text: &str) -> TextEdit {
+    let end = line_start_or_end(content, u32::MAX);
+    let separator = if content.iter().all(|c| c.is_whitespace()) {
+        ""
+    } else {
+        let newlines = content
+            .iter()
+            .rev()
+            .take_while(|c| c.is_whitespace())
+            .filter(|c| **c == '\n')
+            .count();
+        &"\n\n"[newlines.min(2)..]
+    };
+
+    TextEdit {
+        range: Range { start: end, end },
+        new_text: format!("{}{}\n", separator, text),
+    }
+}
+
+/// Returns true if a line (one-based) exists and only has whitespace.
+fn is_blank_line(content: &[char], line: u32) -> bool {
+    match offset_at(content, Position { line, column: 1 }) {
+        Some(start) => content[start..]
+            .iter()
+            .take_while(|c| **c != '\n')
+            .all(|c| c.is_whitespace()),
+        None => false,
+    }
+}
+
+/// Returns the start of a line (one-based), or the end of the content if there is no
+/// such line.
+fn line_start_or_end(content: &[char], line: u32) -> Position {
+    let pos = Position { line, column: 1 };
+    if offset_at(content, pos).is_some() {
+        return pos;
+    }
+
+    let text: String = content.iter().collect();
+    let last = text.split('\n').next_back().unwrap_or_default();
+    Position {
+        line: text.split('\n').count() as u32,
+        column: last.chars().count() as u32 + 1,
+    }
+}
+
+/// Returns the innermost container type enclosing a range.
+fn extract_target<'a>(document: &'a DocumentNode, range: &Range) -> Option<&'a FieldTypeNode> {
+    document
+        .definitions
+        .iter()
+        .flat_map(|definition| definition_field_types(definition))
+        .filter(|field_type| {
+            matches!(
+                field_type,
+                FieldTypeNode::MapType(_) | FieldTypeNode::SetType(_) | FieldTypeNode::ListType(_)
+            ) && field_type.range().contains(range.start)
+                && field_type.range().contains(range.end)
+        })
+        .min_by_key(|field_type| field_type.range().end)
+}
+
+/// Returns the types used in a definition, including the types inside containers.
+fn definition_field_types(definition: &DefinitionNode) -> Vec<&FieldTypeNode> {
+    let mut field_types = Vec::new();
+    let mut fields: Vec<&FieldNode> = Vec::new();
+    match definition {
+        DefinitionNode::Const(node) => nested_field_types(&node.field_type, &mut field_types),
+        DefinitionNode::Typedef(node) => {
+            nested_field_types(&node.definition_type, &mut field_types)
+        }
+        DefinitionNode::Struct(node) => fields.extend(&node.fields),
+        DefinitionNode::Union(node) => fields.extend(&node.fields),
+        DefinitionNode::Exception(node) => fields.extend(&node.fields),
+        DefinitionNode::Service(node) => {
+            for function in &node.functions {
+                if let Some(function_type) = &function.function_type {
+                    nested_field_types(function_type, &mut field_types);
+                }
+                fields.extend(&function.fields);
+                fields.extend(function.throws.iter().flatten());
+            }
+        }
+        DefinitionNode::Enum(_) => {}
+    }
+    for field in fields {
+        nested_field_types(&field.field_type, &mut field_types);
+    }
+
+    field_types
+}
+
+/// Collects a type and the types inside it.
+fn nested_field_types<'a>(field_type: &'a FieldTypeNode, result: &mut Vec<&'a FieldTypeNode>) {
+    result.push(field_type);
+    match field_type {
+        FieldTypeNode::MapType(node) => {
+            nested_field_types(&node.key_type, result);
+            nested_field_types(&node.value_type, result);
+        }
+        FieldTypeNode::SetType(node) => nested_field_types(&node.type_node, result),
+        FieldTypeNode::ListType(node) => nested_field_types(&node.type_node, result),
+        FieldTypeNode::Identifier(_) | FieldTypeNode::BaseType(_) => {}
+    }
+}
+
+/// Returns the name of a definition, or of one of its members if it is an enum, as
+/// written in a file referring to it by a namespace, e.g. `shared.Color.RED`.
+fn qualified_name(namespace: Option<&str>, def: &DefinitionNode, member: Option<usize>) -> String {
+    let mut name = match namespace {
+        Some(namespace) => format!("{}.{}", namespace, def.name()),
+        None => def.name().to_string(),
+    };
+    if let (DefinitionNode::Enum(enum_node), Some(member)) = (def, member) {
+        if let Some(value) = enum_node.values.get(member) {
+            name.push('.');
+            name.push_str(&value.identifier.name);
+        }
+    }
+
+    name
+}
+
+/// Returns a path without `.` and `..` segments. Its separator is `\\` if the path has
+/// one, as on Windows, and `/` otherwise.
+fn normalize_path(path: &str) -> String {
+    let separator = if path.contains('\\') { "\\" } else { "/" };
+    let mut parts: Vec<&str> = Vec::new();
+    for part in path.split(['/', '\\']) {
+        match part {
+            "." => {}
+            ".." if parts.last().is_some_and(|p| !p.is_empty() && *p != "..") => {
+                parts.pop();
+            }
+            _ => parts.push(part),
+        }
+    }
+
+    parts.join(separator)
+}
+
+/// Returns the path of a file relative to the directory of another file, as written
+/// in an include, e.g. `../common/shared.thrift`. Both paths must be normalized.
+fn relative_path(from: &str, to: &str) -> String {
+    let from: Vec<&str> = from.split(['/', '\\']).collect();
+    let to: Vec<&str> = to.split(['/', '\\']).collect();
+    let from_dir = &from[..from.len() - 1];
+    let common = from_dir
+        .iter()
+        .zip(&to[..to.len() - 1])
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut parts = vec![".."; from_dir.len() - common];
+    parts.extend(&to[common..]);
+    parts.join("/")
+}
+
+/// Returns the file name of a given path.
+fn file_name(path: &str) -> &str {
+    path.rsplit(['/', '\\']).next().unwrap_or(path)
+}
+
+/// Returns the offset of a position in the content.
+fn offset_at(content: &[char], pos: Position) -> Option<usize> {
+    let mut offset = 0;
+    let mut cur_pos = Position { line: 1, column: 1 };
+
+    while offset < content.len() {
+        if cur_pos >= pos {
+            break;
+        }
+
+        if content[offset] == '\n' {
+            offset += 1;
+            cur_pos.line += 1;
+            cur_pos.column = 1;
+        } else if content[offset] == '\r' {
+            offset += 1;
+            cur_pos.line += 1;
+            cur_pos.column = 1;
+            if offset < content.len() && content[offset] == '\n' {
+                offset += 1;
+            }
+        } else {
+            offset += 1;
+            cur_pos.column += 1;
+        }
+    }
+
+    if cur_pos == pos {
+        Some(offset)
+    } else {
+        None
+    }
+}
+
+/// Returns the text of a range in the content.
+fn text_in_range(content: &[char], range: &Range) -> Option<String> {
+    let start = offset_at(content, range.start)?;
+    let end = offset_at(content, range.end)?;
+    Some(content.get(start..end)?.iter().collect())
+}
+
+/// Returns the text of a range in the content with the edits inside the range applied.
+/// The edits must be sorted.
+fn text_with_edits(content: &[char], range: &Range, edits: &[TextEdit]) -> Option<String> {
+    let mut text = String::new();
+    let mut offset = offset_at(content, range.start)?;
+    for edit in edits {
+        let start = offset_at(content, edit.range.start)?;
+        text.extend(content.get(offset..start)?);
+        text.push_str(&edit.new_text);
+        offset = offset_at(content, edit.range.end)?;
+    }
+    text.extend(content.get(offset..offset_at(content, range.end)?)?);
+
+    Some(text)
+}
+
+/// Returns the edits turning a text into another one, one for each run of changed
+/// lines. Returns no edits if the texts are equal.
+fn line_edits(old: &[char], new: &str) -> Vec<TextEdit> {
+    let old: String = old.iter().collect();
+    if old == new {
+        return vec![];
+    }
+    // lines keep their line breaks, so that a missing last line break is a change
+    let old_lines: Vec<&str> = old.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new.split_inclusive('\n').collect();
+
+    let mut edits = Vec::new();
+    let (mut old_next, mut new_next) = (0, 0);
+    let common = common_lines(&old_lines, &new_lines);
+    for (i, j) in common
+        .into_iter()
+        .chain([(old_lines.len(), new_lines.len())])
+    {
+        if i > old_next || j > new_next {
+            edits.push(TextEdit {
+                range: Range {
+                    start: line_start(&old_lines, old_next),
+                    end: line_start(&old_lines, i),
+                },
+                new_text: new_lines[new_next..j].concat(),
+            });
+        }
+        (old_next, new_next) = (i + 1, j + 1);
+    }
+
+    edits
+}
+
+/// Splits an edit of whole lines, as returned by `line_edits`, into edits of the lines
+/// it pairs up with lines of its new text, so that only a part of it can be applied.
+/// Lines are paired if they are the same without whitespace and list separators.
+fn split_line_edit(lines: &[&str], edit: &TextEdit) -> Vec<TextEdit> {
+    // the inverse of `line_start`
+    let index = |pos: Position| match pos.column {
+        1 => pos.line as usize - 1,
+        // the end of a text without a last line break
+        _ => pos.line as usize,
+    };
+    let (start, end) = (index(edit.range.start), index(edit.range.end));
+    let (old, new): (&[&str], Vec<&str>) = (
+        &lines[start..end],
+        edit.new_text.split_inclusive('\n').collect(),
+    );
+
+    let key = |line: &&str| -> String {
+        line.chars()
+            .filter(|c| !c.is_whitespace() && *c != ',' && *c != ';')
+            .collect()
+    };
+    let old_keys: Vec<String> = old.iter().map(key).collect();
+    let new_keys: Vec<String> = new.iter().map(key).collect();
+    let common = common_lines(
+        &old_keys.iter().map(String::as_str).collect::<Vec<_>>(),
+        &new_keys.iter().map(String::as_str).collect::<Vec<_>>(),
+    );
+
+    let mut edits = Vec::new();
+    let mut push = |old_range: (usize, usize), new_range: (usize, usize)| {
+        edits.push(TextEdit {
+            range: Range {
+                start: line_start(lines, start + old_range.0),
+                end: line_start(lines, start + old_range.1),
+            },
+            new_text: new[new_range.0..new_range.1].concat(),
+        });
+    };
+    let (mut old_next, mut new_next) = (0, 0);
+    for (i, j) in common.into_iter().chain([(old.len(), new.len())]) {
+        if i > old_next || j > new_next {
+            push((old_next, i), (new_next, j));
+        }
+        if i < old.len() && old[i] != new[j] {
+            push((i, i + 1), (j, j + 1));
+        }
+        (old_next, new_next) = (i + 1, j + 1);
+    }
+
+    edits
+}
+
+/// Returns the indexes of the lines two texts have in common, in order: their longest
+/// common subsequence of lines.
+fn common_lines(old: &[&str], new: &[&str]) -> Vec<(usize, usize)> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (old_middle, new_middle) = (
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+    );
+
+    let mut common: Vec<(usize, usize)> = (0..prefix).map(|i| (i, i)).collect();
+    let (n, m) = (old_middle.len(), new_middle.len());
+    if n * m <= MAX_LINE_PAIRS {
+        // lengths[i * (m + 1) + j]: length of the common subsequence of old_middle[i..]
+        // and new_middle[j..]
+        let mut lengths = vec![0u32; (n + 1) * (m + 1)];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lengths[i * (m + 1) + j] = if old_middle[i] == new_middle[j] {
+                    lengths[(i + 1) * (m + 1) + j + 1] + 1
+                } else {
+                    lengths[(i + 1) * (m + 1) + j].max(lengths[i * (m + 1) + j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < n && j < m {
+            if old_middle[i] == new_middle[j] {
+                common.push((prefix + i, prefix + j));
+                (i, j) = (i + 1, j + 1);
+            } else if lengths[(i + 1) * (m + 1) + j] >= lengths[i * (m + 1) + j + 1] {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+    }
+    common.extend((0..suffix).map(|k| (old.len() - suffix + k, new.len() - suffix + k)));
+
+    common
+}
+
+/// Returns the position of the start of a line (zero-based index) in lines split with
+/// their line breaks, or the end of the text for the index after the last line.
+fn line_start(lines: &[&str], index: usize) -> Position {
+    match lines.last() {
+        Some(last) if index == lines.len() && !last.ends_with('\n') => Position {
+            line: lines.len() as u32,
+            column: last.chars().count() as u32 + 1,
+        },
+        _ => Position {
+            line: index as u32 + 1,
+            column: 1,
+        },
+    }
+}
+
+/// Returns the last line (one-based) touched by a range, not counting the line it ends
+/// at the start of.
+fn last_line(range: &Range) -> u32 {
+    if range.end.column == 1 && range.end.line > range.start.line {
+        range.end.line - 1
+    } else {
+        range.end.line
+    }
+}
+
+/// Returns the edit starting a field on an empty line right after a field, or after
+/// the opening of a field list: the indentation and the next free field ID.
+fn next_field_edit(
+    document: &DocumentNode,
+    content: &[char],
+    pos: Position,
+    options: &FormattingOptions,
+) -> Option<TextEdit> {
+    let line_start = Position {
+        line: pos.line,
+        column: 1,
+    };
+    let start = offset_at(content, line_start)?;
+    let line_len = content[start..]
+        .iter()
+        .take_while(|c| **c != '\n' && **c != '\r')
+        .count();
+    if line_indent(content, pos.line).chars().count() != line_len {
+        return None;
+    }
+
+    let tokens: Vec<Token> = scan_tokens(content)
+        .into_iter()
+        .filter(|t| !t.is_comment() && !t.is_line_separator())
+        .collect();
+    let (fields, open) = field_list_at(document, content, &tokens, line_start)?;
+    let prev = tokens.iter().rfind(|t| t.position < line_start)?.position;
+    let after_field = fields
+        .iter()
+        .any(|f| f.range.contains(prev) && f.range.end <= line_start);
+    if prev != open && !after_field {
+        return None;
+    }
+
+    let next_id = next_field_id(fields);
+
+    Some(TextEdit {
+        range: Range {
+            start: line_start,
+            end: Position {
+                line: pos.line,
+                column: line_len as u32 + 1,
+            },
+        },
+        new_text: format!(
+            "{}{}{}: ",
+            line_indent(content, open.line),
+            format::indent_unit(options),
+            next_id
+        ),
+    })
+}
+
+/// Returns the field list enclosing a position, with the position of its opening brace
+/// or parenthesis: the body of a struct, union or exception, or the arguments or
+/// exceptions of a function.
+fn field_list_at<'a>(
+    document: &'a DocumentNode,
+    content: &[char],
+    tokens: &[Token],
+    pos: Position,
+) -> Option<(&'a [FieldNode], Position)> {
+    let definition = document
+        .definitions
+        .iter()
+        .find(|d| d.range().contains(pos))?;
+    let (node, fields): (&dyn Node, &[FieldNode]) = match definition.as_ref() {
+        DefinitionNode::Struct(node) => (node, &node.fields),
+        DefinitionNode::Union(node) => (node, &node.fields),
+        DefinitionNode::Exception(node) => (node, &node.fields),
+        DefinitionNode::Service(node) => {
+            let function = node.functions.iter().find(|f| f.range.contains(pos))?;
+            return function_field_list_at(function, tokens, pos);
+        }
+        _ => return None,
+    };
+
+    let block = block_range(content, node)?;
+    (block.start < pos && pos < block.end).then_some((fields, block.start))
+}
+
+/// Returns the arguments or the exceptions of a function if they enclose a position,
+/// with the position of their opening parenthesis.
+fn function_field_list_at<'a>(
+    function: &'a FunctionNode,
+    tokens: &[Token],
+    pos: Position,
+) -> Option<(&'a [FieldNode], Position)> {
+    let mut depth = 0;
+    let mut prev_kind = &TokenKind::Eof;
+    let mut open = None;
+    let mut lists = 0;
+    for token in tokens
+        .iter()
+        .filter(|t| function.range.contains(t.position))
+    {
+        match token.kind {
+            TokenKind::Lparen => {
+                if depth == 0 {
+                    // the first list holds the arguments, the one after `throws` the
+                    // exceptions, and any other is an annotation
+                    open = match (lists, prev_kind) {
+                        (0, _) => Some((function.fields.as_slice(), token.position)),
+                        (_, TokenKind::Throws) => function
+                            .throws
+                            .as_deref()
+                            .map(|throws| (throws, token.position)),
+                        _ => None,
+                    };
+                }
+                depth += 1;
+            }
+            TokenKind::Rparen if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    lists += 1;
+                    if let Some((fields, start)) = open.take() {
+                        if start < pos && pos <= token.position {
+                            return Some((fields, start));
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+        prev_kind = &token.kind;
+    }
+
+    None
+}
+
+/// Returns the edit giving a closing brace typed at the start of a line the indentation
+/// of the line of its opening brace.
+fn closing_brace_edit(content: &[char], pos: Position) -> Option<TextEdit> {
+    let brace = Position {
+        line: pos.line,
+        column: pos.column.checked_sub(1)?,
+    };
+    if !starts_line(content, brace) {
+        return None;
+    }
+
+    let mut opens = Vec::new();
+    let mut closing = None;
+    for token in scan_tokens(content) {
+        if token.position >= brace {
+            closing = Some(token);
+            break;
+        }
+        match token.kind {
+            TokenKind::Lbrace => opens.push(token.position),
+            TokenKind::Rbrace => {
+                opens.pop();
+            }
+            _ => {}
+        }
+    }
+    if closing?.kind != TokenKind::Rbrace {
+        return None;
+    }
+
+    let indent = line_indent(content, opens.pop()?.line);
+    let line_start = Position {
+        line: pos.line,
+        column: 1,
+    };
+    if text_in_range(
+        content,
+        &Range {
+            start: line_start,
+            end: brace,
+        },
+    )? == indent
+    {
+        return None;
+    }
+
+    Some(TextEdit {
+        range: Range {
+            start: line_start,
+            end: brace,
+        },
+        new_text: indent,
+    })
+}
+
+/// Returns the whitespace at the start of a line (one-based).
+fn line_indent(content: &[char], line: u32) -> String {
+    let start = match offset_at(content, Position { line, column: 1 }) {
+        Some(start) => start,
+        None => return String::new(),
+    };
+    content[start..]
+        .iter()
+        .take_while(|c| c.is_whitespace() && **c != '\n' && **c != '\r')
+        .collect()
+}
+
+/// Returns the tokens of a content, without the end of file.
+fn scan_tokens(content: &[char]) -> Vec<Token> {
+    let mut scanner = Scanner::new(content);
+    let mut tokens = Vec::new();
+    loop {
+        let (token, _) = scanner.scan();
+        if token.is_eof() {
+            break;
+        }
+        tokens.push(token);
+    }
+
+    tokens
+}
+
+/// Returns the doc comment right above a line (one-based), without comment markers.
+fn doc_comment(content: &[char], line: u32) -> Option<String> {
+    let text: String = content.iter().collect();
+    let lines: Vec<&str> = text.lines().map(|l| l.trim()).collect();
+    let mut index = (line as usize).checked_sub(1)?.min(lines.len());
+
+    let mut comment = Vec::new();
+    let is_line_comment = |l: &str| l.starts_with("//") || l.starts_with('#');
+
+    if index > 0 && lines[index - 1].ends_with("*/") {
+        // block comment: /** ... */
+        loop {
+            index = index.checked_sub(1)?;
+            comment.push(lines[index]);
+            if lines[index].starts_with("/*") {
+                break;
+            }
+        }
+        comment.reverse();
+
+        let last = comment.len() - 1;
+        for (i, l) in comment.iter_mut().enumerate() {
+            if i == 0 {
+                *l = l.trim_start_matches('/').trim_start_matches('*');
+            }
+            if i == last {
+                *l = l.trim_end_matches('/').trim_end_matches('*');
+            }
+            if i != 0 {
+                *l = l.strip_prefix('*').unwrap_or(l);
+            }
+            *l = l.trim();
+        }
+    } else {
+        // line comments: // ... or # ...
+        while index > 0 && is_line_comment(lines[index - 1]) {
+            index -= 1;
+            let l = lines[index].trim_start_matches('/').trim_start_matches('#');
+            comment.push(l.trim());
+        }
+        comment.reverse();
+    }
+
+    let start = comment.iter().position(|l| !l.is_empty())?;
+    let end = comment.iter().rposition(|l| !l.is_empty())?;
+    Some(comment[start..=end].join("\n"))
+}
+
+/// Returns the call hierarchy item of a type definition, or `None` for consts and services,
+/// which can't be used as types.
+fn call_hierarchy_item(path: &str, def: &DefinitionNode) -> Option<CallHierarchyItem> {
+    if matches!(def, DefinitionNode::Const(_) | DefinitionNode::Service(_)) {
+        return None;
+    }
+
+    Some(CallHierarchyItem {
+        name: def.name().to_string(),
+        kind: symbol_kind(def),
+        detail: Some(file_name(path).to_string()),
+        path: path.to_string(),
+        range: def.range(),
+        selection_range: def.identifier().range(),
+    })
+}
+
+/// Returns the call hierarchy item of a function, with its service as detail.
+fn function_call_hierarchy_item(
+    path: &str,
+    service: &ServiceNode,
+    function: &FunctionNode,
+) -> CallHierarchyItem {
+    CallHierarchyItem {
+        name: function.identifier.name.clone(),
+        kind: SymbolKind::Method,
+        detail: Some(service.identifier.name.clone()),
+        path: path.to_string(),
+        range: function.range.clone(),
+        selection_range: function.identifier.range(),
+    }
+}
+
+/// Returns the type hierarchy item of a definition.
+fn type_hierarchy_item(path: &str, def: &DefinitionNode) -> TypeHierarchyItem {
+    TypeHierarchyItem {
+        name: def.name().to_string(),
+        kind: SymbolKind::Interface,
+        detail: Some(file_name(path).to_string()),
+        path: path.to_string(),
+        range: def.range(),
+        selection_range: def.identifier().range(),
+    }
+}
+
+/// Merges ranges on consecutive lines into runs. The ranges must be sorted.
+fn line_runs(ranges: &[Range]) -> Vec<Range> {
+    let mut runs: Vec<Range> = Vec::new();
+    for range in ranges {
+        match runs.last_mut() {
+            Some(run) if range.start.line <= run.end.line + 1 => run.end = range.end,
+            _ => runs.push(range.clone()),
+        }
+    }
+    runs
+}
+
+/// Adds a folding range if the range spans several lines.
+fn push_folding_range(
+    ranges: &mut Vec<FoldingRange>,
+    range: &Range,
+    kind: Option<FoldingRangeKind>,
+) {
+    if range.end.line > range.start.line {
+        ranges.push(FoldingRange {
+            start_line: range.start.line,
+            end_line: range.end.line,
+            kind,
+        });
+    }
+}
+
+/// Collects all nodes of a given type in a tree.
+fn collect_nodes<'a, T: 'static>(node: &'a dyn Node, result: &mut Vec<&'a T>) {
+    if let Some(node) = node.as_any().downcast_ref::<T>() {
+        result.push(node);
+    }
+    for child in node.children() {
+        collect_nodes(child, result);
+    }
+}
+
+/// Returns the range of the braces enclosing the body of a struct, union,
+/// exception, service or enum node.
+fn block_range(content: &[char], node: &dyn Node) -> Option<Range> {
+    let node = node.as_any();
+    let (identifier, end) = if let Some(node) = node.downcast_ref::<StructNode>() {
+        (&node.identifier, node.range.end)
+    } else if let Some(node) = node.downcast_ref::<UnionNode>() {
+        (&node.identifier, node.range.end)
+    } else if let Some(node) = node.downcast_ref::<ExceptionNode>() {
+        (&node.identifier, node.range.end)
+    } else if let Some(node) = node.downcast_ref::<ServiceNode>() {
+        (&node.identifier, node.range.end)
+    } else if let Some(node) = node.downcast_ref::<EnumNode>() {
+        (&node.identifier, node.range.end)
+    } else {
+        return None;
+    };
+
+    // the body opens with the first '{' token after the name and closes with the
+    // matching '}', so braces in strings, comments and annotations are skipped
+    let base = identifier.range.end;
+    let absolute = |pos: Position| {
+        if pos.line == 1 {
+            Position {
+                line: base.line,
+                column: base.column + pos.column - 1,
+            }
+        } else {
+            Position {
+                line: base.line + pos.line - 1,
+                column: pos.column,
+            }
+        }
+    };
+
+    let mut open = None;
+    let mut depth = 0;
+    for token in scan_tokens(&content[offset_at(content, base)?..offset_at(content, end)?]) {
+        match token.kind {
+            TokenKind::Lbrace => {
+                if open.is_none() {
+                    open = Some(absolute(token.range().start));
+                }
+                depth += 1;
+            }
+            TokenKind::Rbrace if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(Range {
+                        start: open?,
+                        end: absolute(token.range().end),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Returns the semantic token type and modifiers of the name of a definition.
+fn definition_semantic_token(def: &DefinitionNode) -> (SemanticTokenType, u32) {
+    match def {
+        DefinitionNode::Const(_) => (SemanticTokenType::Variable, READONLY),
+        DefinitionNode::Typedef(_) => (SemanticTokenType::Type, 0),
+        DefinitionNode::Enum(_) => (SemanticTokenType::Enum, 0),
+        DefinitionNode::Struct(_) | DefinitionNode::Union(_) | DefinitionNode::Exception(_) => {
+            (SemanticTokenType::Struct, 0)
+        }
+        DefinitionNode::Service(_) => (SemanticTokenType::Interface, 0),
+    }
+}
+
+/// Adds the semantic token of an identifier.
+fn push_identifier_token(
+    tokens: &mut Vec<SemanticToken>,
+    identifier: &IdentifierNode,
+    token_type: SemanticTokenType,
+    modifiers: u32,
+) {
+    tokens.push(SemanticToken {
+        start: identifier.range.start,
+        length: identifier.name.chars().count() as u32,
+        token_type,
+        modifiers,
+    });
+}
+
+/// Adds the semantic tokens of a text in the source, one for each line it spans.
+fn push_source_tokens(
+    tokens: &mut Vec<SemanticToken>,
+    source: &[char],
+    start: Position,
+    length: usize,
+    token_type: SemanticTokenType,
+) {
+    let offset = match offset_at(source, start) {
+        Some(offset) => offset,
+        None => return,
+    };
+
+    let mut token = SemanticToken {
+        start,
+        length: 0,
+        token_type,
+        modifiers: 0,
+    };
+    let mut prev = None;
+    for &c in source[offset..].iter().take(length) {
+        if c == '\n' || c == '\r' {
+            // `\r\n` ends a single line
+            if !(c == '\n' && prev == Some('\r')) {
+                let next_line = Position {
+                    line: token.start.line + 1,
+                    column: 1,
+                };
+                let line_token = std::mem::replace(
+                    &mut token,
+                    SemanticToken {
+                        start: next_line,
+                        length: 0,
+                        token_type,
+                        modifiers: 0,
+                    },
+                );
+                if line_token.length > 0 {
+                    tokens.push(line_token);
+                }
+            }
+        } else {
+            token.length += 1;
+        }
+        prev = Some(c);
+    }
+    if token.length > 0 {
+        tokens.push(token);
+    }
+}
+
+/// Encodes semantic tokens with positions relative to the previous token. Overlapping
+/// tokens are dropped.
+fn encode_semantic_tokens(mut tokens: Vec<SemanticToken>) -> Vec<u32> {
+    tokens.sort_by_key(|token| token.start);
+
+    let mut data = Vec::new();
+    let mut prev_line = 0;
+    let mut prev_char = 0;
+    let mut prev_end: Option<Position> = None;
+
+    for token in tokens {
+        if prev_end.is_some_and(|end| token.start < end) {
+            continue;
+        }
+        prev_end = Some(Position {
+            line: token.start.line,
+            column: token.start.column + token.length,
+        });
+
+        // convert to 0-based line and column
+        let line = token.start.line - 1;
+        let char = token.start.column - 1;
+
+        // deltaLine: line number relative to the previous token
+        let delta_line = line - prev_line;
+        // deltaStart: start character relative to the previous token
+        let delta_start = if delta_line == 0 {
+            char - prev_char
+        } else {
+            char
+        };
+        data.extend_from_slice(&[
+            delta_line,
+            delta_start,
+            token.length,
+            token.token_type as u32,
+            token.modifiers,
+        ]);
+
+        prev_line = line;
+        prev_char = char;
+    }
+
+    data
+}
+
+/// Returns the encoded semantic tokens starting in a range, re-encoded relative to
+/// each other.
+fn semantic_tokens_in_range(data: &[u32], range: &Range) -> Vec<u32> {
+    let mut result = Vec::new();
+    let (mut line, mut char) = (0, 0);
+    let (mut prev_line, mut prev_char) = (0, 0);
+
+    for token in data.chunks_exact(5) {
+        if token[0] > 0 {
+            line += token[0];
+            char = token[1];
+        } else {
+            char += token[1];
+        }
+
+        // ranges are one-based
+        let start = Position {
+            line: line + 1,
+            column: char + 1,
+        };
+        if start < range.start || start >= range.end {
+            continue;
+        }
+
+        let delta_line = line - prev_line;
+        let delta_start = if delta_line == 0 {
+            char - prev_char
+        } else {
+            char
+        };
+        result.extend_from_slice(&[delta_line, delta_start, token[2], token[3], token[4]]);
+        prev_line = line;
+        prev_char = char;
+    }
+
+    result
+}
+
+/// Returns the edits turning encoded semantic tokens into others: a single edit
+/// replacing what is between their common prefix and suffix.
+fn semantic_tokens_edits(old: &[u32], new: &[u32]) -> Vec<SemanticTokensEdit> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    if prefix == old.len() && prefix == new.len() {
+        return vec![];
+    }
+
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    vec![SemanticTokensEdit {
+        start: prefix as u32,
+        delete_count: (old.len() - prefix - suffix) as u32,
+        data: new[prefix..new.len() - suffix].to_vec(),
+    }]
+}
+
+/// Returns true if only whitespace precedes a position on its line.
+fn starts_line(content: &[char], pos: Position) -> bool {
+    let offset = match offset_at(content, pos) {
+        Some(offset) => offset,
+        None => return false,
+    };
+    content[..offset]
+        .iter()
+        .rev()
+        .take_while(|c| **c != '\n')
+        .all(|c| c.is_whitespace())
+}
+
+/// Returns the warning for a usage of a deprecated symbol.
+fn deprecation_warning(identifier: &IdentifierNode, name: &str, message: &str) -> Error {
+    Error {
+        range: identifier.range(),
+        message: deprecation_message(name, message),
+        severity: Severity::Warning,
+        tags: vec![DiagnosticTag::Deprecated],
+    }
+}
+
+/// Returns the message shown for a deprecated symbol, e.g. "`Work` is deprecated: use Job".
+fn deprecation_message(name: &str, message: &str) -> String {
+    if message.is_empty() {
+        format!("`{}` is deprecated", name)
+    } else {
+        format!("`{}` is deprecated: {}", name, message)
+    }
+}
+
+/// Returns the Markdown of a hover: the code, the deprecation message, the doc comment
+/// above a line, and the file.
+fn hover_markdown(
+    path: &str,
+    source: &[char],
+    code: &str,
+    line: u32,
+    deprecation: Option<&str>,
+) -> String {
+    let mut contents = format!("```thrift\n{}\n```", code);
+    match deprecation {
+        Some("") => contents.push_str("\n\n**Deprecated**"),
+        Some(message) => contents.push_str(&format!("\n\n**Deprecated**: {}", message)),
+        None => {}
+    }
+    if let Some(doc) = doc_comment(source, line) {
+        contents.push_str("\n\n");
+        contents.push_str(&doc);
+    }
+    contents.push_str(&format!("\n\n---\n\nDefined in `{}`", file_name(path)));
+
+    contents
+}
+
+/// Returns the document symbol of a header.
+fn header_symbol(header: &HeaderNode) -> DocumentSymbol {
+    let (name, detail, kind) = match header {
+        HeaderNode::Include(node) => (node.literal.clone(), None, SymbolKind::Module),
+        HeaderNode::CppInclude(node) => (node.literal.clone(), None, SymbolKind::File),
+        HeaderNode::Namespace(node) => (
+            node.identifier.name.clone(),
+            Some(node.scope.clone()),
+            SymbolKind::Namespace,
+        ),
+    };
+    let selection_range = match header {
+        HeaderNode::Namespace(node) => node.identifier.range(),
+        _ => header.range(),
+    };
+
+    DocumentSymbol {
+        name,
+        detail,
+        kind,
+        range: header.range(),
+        selection_range,
+        children: vec![],
+    }
+}
+
+/// Returns the document symbol of a definition, including its members.
+fn definition_symbol(definition: &DefinitionNode) -> DocumentSymbol {
+    let (detail, children) = match definition {
+        DefinitionNode::Const(node) => (Some(node.field_type.to_string()), vec![]),
+        DefinitionNode::Typedef(node) => (Some(node.definition_type.to_string()), vec![]),
+        DefinitionNode::Enum(node) => (None, node.values.iter().map(enum_value_symbol).collect()),
+        DefinitionNode::Struct(node) => (None, node.fields.iter().map(field_symbol).collect()),
+        DefinitionNode::Union(node) => (
+            Some("union".to_string()),
+            node.fields.iter().map(field_symbol).collect(),
+        ),
+        DefinitionNode::Exception(node) => (
+            Some("exception".to_string()),
+            node.fields.iter().map(field_symbol).collect(),
+        ),
+        DefinitionNode::Service(node) => (
+            node.extends
+                .as_ref()
+                .map(|extends| format!("extends {}", extends.name)),
+            node.functions.iter().map(function_symbol).collect(),
+        ),
+    };
+
+    DocumentSymbol {
+        name: definition.name().to_string(),
+        detail,
+        kind: symbol_kind(definition),
+        range: definition.range(),
+        selection_range: definition.identifier().range(),
+        children,
+    }
+}
+
+/// Returns the symbol kind of a definition.
+fn symbol_kind(definition: &DefinitionNode) -> SymbolKind {
+    match definition {
+        DefinitionNode::Const(_) => SymbolKind::Constant,
+        DefinitionNode::Typedef(_) => SymbolKind::TypeParameter,
+        DefinitionNode::Enum(_) => SymbolKind::Enum,
+        DefinitionNode::Struct(_) | DefinitionNode::Union(_) => SymbolKind::Struct,
+        DefinitionNode::Exception(_) => SymbolKind::Class,
+        DefinitionNode::Service(_) => SymbolKind::Interface,
+    }
+}
+
+/// Returns the document symbol of a field, with its ID and type as detail.
+fn field_symbol(field: &FieldNode) -> DocumentSymbol {
+    let detail = match &field.field_id {
+        Some(field_id) => format!("{}: {}", field_id.id, field.field_type),
+        None => field.field_type.to_string(),
+    };
+
+    DocumentSymbol {
+        name: field.identifier.name.clone(),
+        detail: Some(detail),
+        kind: SymbolKind::Field,
+        range: field.range(),
+        selection_range: field.identifier.range(),
+        children: vec![],
+    }
+}
+
+/// Returns the document symbol of an enum value.
+fn enum_value_symbol(value: &EnumValueNode) -> DocumentSymbol {
+    DocumentSymbol {
+        name: value.identifier.name.clone(),
+        detail: value.value.map(|v| format!("= {}", v)),
+        kind: SymbolKind::EnumMember,
+        range: value.range(),
+        selection_range: value.identifier.range(),
+        children: vec![],
+    }
+}
+
+/// Returns the document symbol of a function, with its arguments and exceptions as children.
+fn function_symbol(function: &FunctionNode) -> DocumentSymbol {
+    let detail = match &function.function_type {
+        Some(function_type) => function_type.to_string(),
+        None => "void".to_string(),
+    };
+
+    let mut children: Vec<DocumentSymbol> = function.fields.iter().map(field_symbol).collect();
+    if let Some(throws) = &function.throws {
+        children.extend(throws.iter().map(field_symbol));
+    }
+
+    DocumentSymbol {
+        name: function.identifier.name.clone(),
+        detail: Some(detail),
+        kind: SymbolKind::Method,
+        range: function.range(),
+        selection_range: function.identifier.range(),
+        children,
+    }
+}
+
+/// Returns the fields list a field belongs to, given its parent node.
+fn field_siblings<'a>(container: &'a dyn Any, field: &FieldNode) -> Option<&'a [FieldNode]> {
+    let contains = |fields: &[FieldNode]| fields.iter().any(|f| ptr::eq(f, field));
+
+    if let Some(node) = container.downcast_ref::<StructNode>() {
+        return Some(&node.fields);
+    }
+    if let Some(node) = container.downcast_ref::<UnionNode>() {
+        return Some(&node.fields);
+    }
+    if let Some(node) = container.downcast_ref::<ExceptionNode>() {
+        return Some(&node.fields);
+    }
+    if let Some(node) = container.downcast_ref::<FunctionNode>() {
+        if contains(&node.fields) {
+            return Some(&node.fields);
+        }
+        return node.throws.as_deref().filter(|throws| contains(throws));
+    }
+
+    None
+}
+
+/// Returns the field lists of a document whose fields need IDs, with the range of
+/// their struct, union, exception or function and a description, e.g.
+/// "fields of `Job`" or "arguments of `get`".
+fn field_lists(document: &DocumentNode) -> Vec<(&[FieldNode], Range, String)> {
+    let mut lists = Vec::new();
+    for definition in &document.definitions {
+        let fields = match definition.as_ref() {
+            DefinitionNode::Struct(node) => &node.fields,
+            DefinitionNode::Union(node) => &node.fields,
+            DefinitionNode::Exception(node) => &node.fields,
+            DefinitionNode::Service(node) => {
+                for function in &node.functions {
+                    lists.push((
+                        function.fields.as_slice(),
+                        function.range.clone(),
+                        format!("arguments of `{}`", function.identifier.name),
+                    ));
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        lists.push((
+            fields.as_slice(),
+            definition.range(),
+            format!("fields of `{}`", definition.name()),
+        ));
+    }
+
+    lists
+}
+
+/// Returns the next free field ID of a field list: one more than the highest ID, and
+/// at least 1.
+fn next_field_id(fields: &[FieldNode]) -> i32 {
+    fields
+        .iter()
+        .filter_map(|f| f.field_id.as_ref())
+        .map(|id| id.id)
+        .max()
+        .map_or(1, |id| id.saturating_add(1).max(1))
+}
+
+/// Returns the range of the last dot-separated segment of an identifier,
+/// e.g. `Type` in `shared.Type`.
+fn last_segment_range(range: &Range, name: &str) -> Range {
+    let segment = name.rsplit('.').next().unwrap_or(name);
+    Range {
+        start: Position {
+            line: range.end.line,
+            column: range.end.column - segment.chars().count() as u32,
+        },
+        end: range.end,
     }
 }
 
-impl Analyzer {
-    fn read_file(&self, path: &str) -> io::Result<String> {
-        if let Some(read_file) = &self.wasm_read_file {
-            read_file(path.to_string())
-        } else {
-            fs::read_to_string(path)
-        }
+/// Returns the range of the enum name in a usage of one of its members, e.g. `Op` in
+/// `shared.Op.ADD`. The usage must be on a single line.
+fn enum_segment_range(range: &Range, enum_name: &str, member: &str) -> Range {
+    let end = range.end.column - member.chars().count() as u32 - 1;
+    Range {
+        start: Position {
+            line: range.end.line,
+            column: end - enum_name.chars().count() as u32,
+        },
+        end: Position {
+            line: range.end.line,
+            column: end,
+        },
+    }
+}
+
+/// Returns true if the name is a valid Thrift identifier. Keywords, base types and
+/// namespace scopes are scanned as other tokens, so they are not identifiers.
+fn is_valid_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+        _ => return false,
     }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_') && TokenKind::from_string(name).is_none()
 }
 
 /// Returns the parent path of a given path.
@@ -649,7 +4375,7 @@ impl Analyzer {
 fn path_parent(path: &str) -> Option<PathBuf> {
     let parent = Path::new(path).parent();
     if let Some(p) = parent {
-        if p.to_string_lossy().len() > 0 {
+        if !p.to_string_lossy().is_empty() {
             return Some(p.to_path_buf());
         }
     }
@@ -660,3 +4386,1500 @@ fn path_parent(path: &str) -> Option<PathBuf> {
 
     parent.map(|p| p.to_path_buf())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, path::Path};
+
+    use super::*;
+
+    fn test_file_path(name: &str) -> String {
+        let work_path = env::current_dir().unwrap();
+        let file_path = work_path.join(Path::new("lib/analyzer/test_file").join(name));
+        file_path.to_string_lossy().to_string()
+    }
+
+    fn open(analyzer: &mut Analyzer, name: &str) -> String {
+        let path = test_file_path(name);
+        let content = fs::read_to_string(&path).unwrap();
+        analyzer.sync_document(&path, &content);
+        path
+    }
+
+    /// Returns the empty range at a position.
+    fn at(line: u32, column: u32) -> Range {
+        Range {
+            start: Position { line, column },
+            end: Position { line, column },
+        }
+    }
+
+    /// Applies edits, sorted by position, to a text.
+    fn apply_edits(source: &str, edits: &[TextEdit]) -> String {
+        let mut content: Vec<char> = source.chars().collect();
+        for edit in edits.iter().rev() {
+            let start = offset_at(&content, edit.range.start).unwrap();
+            let end = offset_at(&content, edit.range.end).unwrap();
+            content.splice(start..end, edit.new_text.chars());
+        }
+        content.into_iter().collect()
+    }
+
+    /// Decodes semantic tokens to (line, column, length, type, modifiers), one-based.
+    fn decode_semantic_tokens(
+        analyzer: &mut Analyzer,
+        path: &str,
+    ) -> Vec<(u32, u32, u32, String, String)> {
+        let types = analyzer.semantic_token_types();
+        let modifiers = analyzer.semantic_token_modifiers();
+        let mut tokens = Vec::new();
+        let (mut line, mut column) = (1, 1);
+        for token in analyzer.semantic_tokens(path).unwrap().data.chunks(5) {
+            if token[0] > 0 {
+                line += token[0];
+                column = 1;
+            }
+            column += token[1];
+            let token_modifiers: Vec<&str> = (0..modifiers.len())
+                .filter(|i| token[4] & (1 << i) != 0)
+                .map(|i| modifiers[i].as_str())
+                .collect();
+            tokens.push((
+                line,
+                column,
+                token[2],
+                types[token[3] as usize].clone(),
+                token_modifiers.join(","),
+            ));
+        }
+        tokens
+    }
+
+    #[test]
+    fn hover_included_definition() {
+        let mut analyzer = Analyzer::new();
+        let path = open(&mut analyzer, "tutorial.thrift");
+
+        // shared.SharedStruct lookup(1: MyInteger key),
+        let hover = analyzer
+            .hover(
+                &path,
+                Position {
+                    line: 33,
+                    column: 12,
+                },
+            )
+            .unwrap();
+        assert!(hover.contents.contains("struct SharedStruct {"));
+        assert!(hover.contents.contains("A struct shared between services."));
+        assert!(hover.contents.contains("Defined in `shared.thrift`"));
+
+        // hover on the namespace shows the include
+        let hover = analyzer
+            .hover(
+                &path,
+                Position {
+                    line: 33,
+                    column: 4,
+                },
+            )
+            .unwrap();
+        assert!(hover.contents.contains("include \"shared.thrift\""));
+    }
+
+    #[test]
+    fn references_across_includes() {
+        let mut analyzer = Analyzer::new();
+        open(&mut analyzer, "tutorial.thrift");
+        let shared_path = test_file_path("shared.thrift");
+
+        // struct SharedStruct {
+        let pos = Position {
+            line: 7,
+            column: 10,
+        };
+        let locations = analyzer.references(&shared_path, pos, false);
+        assert_eq!(locations.len(), 2);
+        assert!(locations.iter().any(|l| l.path.ends_with("tutorial.thrift")
+            && l.range.start
+                == Position {
+                    line: 33,
+                    column: 3
+                }));
+
+        let locations = analyzer.references(&shared_path, pos, true);
+        assert_eq!(locations.len(), 3);
+    }
+
+    #[test]
+    fn references_through_parent_includes() {
+        let mut analyzer = Analyzer::new();
+        let base_path = test_file_path("base.thrift");
+        let user_path = test_file_path("nested/user.thrift");
+        analyzer.sync_document(&base_path, "struct Base {}\n");
+        analyzer.sync_document(
+            &user_path,
+            "include \"../base.thrift\"\n\nstruct User {\n  1: base.Base base\n}\n",
+        );
+
+        // `nested/../base.thrift` is the same file as `base.thrift`
+        let locations = analyzer.references(&base_path, Position { line: 1, column: 8 }, false);
+        assert_eq!(locations.len(), 1);
+        assert_eq!(locations[0].path, user_path);
+
+        let edit = analyzer
+            .rename(
+                &user_path,
+                Position {
+                    line: 4,
+                    column: 11,
+                },
+                "Root",
+            )
+            .unwrap();
+        let mut paths: Vec<&String> = edit.changes.keys().collect();
+        paths.sort();
+        assert_eq!(paths, vec![&base_path, &user_path]);
+    }
+
+    #[test]
+    fn rename_enum_with_member_usages() {
+        let mut analyzer = Analyzer::new();
+        let ops_path = test_file_path("ops.thrift");
+        let calc_path = test_file_path("calc.thrift");
+        let ops = "enum Op {\n  ADD = 1,\n}\n\nconst Op DEFAULT = Op.ADD\n";
+        let calc = "include \"ops.thrift\"\n\nconst ops.Op FIRST = ops.Op.ADD\n";
+        analyzer.sync_document(&ops_path, ops);
+        analyzer.sync_document(&calc_path, calc);
+
+        let pos = Position { line: 1, column: 6 };
+        let locations = analyzer.references(&ops_path, pos, false);
+        assert_eq!(locations.len(), 4);
+        assert!(locations.iter().any(|l| l.path == calc_path
+            && l.range
+                == Range {
+                    start: Position {
+                        line: 3,
+                        column: 26
+                    },
+                    end: Position {
+                        line: 3,
+                        column: 28
+                    },
+                }));
+
+        let edit = analyzer.rename(&ops_path, pos, "Operation").unwrap();
+        let mut ops_edits = edit.changes[&ops_path].clone();
+        ops_edits.sort_by(|a, b| a.range.cmp(&b.range));
+        assert_eq!(
+            apply_edits(ops, &ops_edits),
+            "enum Operation {\n  ADD = 1,\n}\n\nconst Operation DEFAULT = Operation.ADD\n"
+        );
+        assert_eq!(
+            apply_edits(calc, &edit.changes[&calc_path]),
+            "include \"ops.thrift\"\n\nconst ops.Operation FIRST = ops.Operation.ADD\n"
+        );
+    }
+
+    #[test]
+    fn rename_definition_and_usages() {
+        let mut analyzer = Analyzer::new();
+        let path = open(&mut analyzer, "tutorial.thrift");
+        let shared_path = test_file_path("shared.thrift");
+
+        // shared.SharedStruct lookup(1: MyInteger key),
+        let pos = Position {
+            line: 33,
+            column: 12,
+        };
+        let range = analyzer.prepare_rename(&path, pos).unwrap();
+        assert_eq!(
+            range.start,
+            Position {
+                line: 33,
+                column: 10
+            }
+        );
+
+        let edit = analyzer.rename(&path, pos, "Shared").unwrap();
+        assert_eq!(edit.changes[&shared_path].len(), 2);
+        assert_eq!(
+            edit.changes[&path],
+            vec![TextEdit {
+                range: Range {
+                    start: Position {
+                        line: 33,
+                        column: 10
+                    },
+                    end: Position {
+                        line: 33,
+                        column: 22
+                    },
+                },
+                new_text: "Shared".to_string(),
+            }]
+        );
+
+        // struct Work { ... } collides with enum Operation
+        let pos = Position {
+            line: 17,
+            column: 9,
+        };
+        assert!(analyzer.rename(&path, pos, "Operation").is_err());
+
+        // keywords and base types are not identifiers
+        for name in ["struct", "i32", "service", "list"] {
+            assert_eq!(
+                analyzer.rename(&path, pos, name).unwrap_err(),
+                format!("Invalid identifier: {}", name)
+            );
+        }
+
+        // 2: i32 num2, collides with num1
+        let pos = Position {
+            line: 19,
+            column: 11,
+        };
+        assert!(analyzer.rename(&path, pos, "num1").is_err());
+        assert_eq!(
+            analyzer.rename(&path, pos, "num3").unwrap().changes.len(),
+            1
+        );
+    }
+
+    #[test]
+    fn document_symbols_tree() {
+        let mut analyzer = Analyzer::new();
+        let path = open(&mut analyzer, "tutorial.thrift");
+
+        let symbols = analyzer.document_symbols(&path);
+        let names: Vec<&str> = symbols.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "shared.thrift",
+                "tutorial",
+                "MyInteger",
+                "INT32CONSTANT",
+                "Operation",
+                "Work",
+                "InvalidOperation",
+                "Calculator",
+            ]
+        );
+
+        let work = &symbols[5];
+        assert_eq!(work.kind, SymbolKind::Struct);
+        assert_eq!(work.children.len(), 4);
+        assert_eq!(work.children[3].detail.as_deref(), Some("4: string"));
+
+        let calculator = &symbols[7];
+        assert_eq!(calculator.kind, SymbolKind::Interface);
+        assert_eq!(calculator.children[2].children.len(), 3);
+    }
+
+    #[test]
+    fn split_identifier_by_first_dot() {
+        let identifier = IdentifierNode {
+            range: Range {
+                start: Position { line: 3, column: 5 },
+                end: Position {
+                    line: 3,
+                    column: 24,
+                },
+            },
+            name: "shared.SharedStruct".to_string(),
+        };
+
+        let (namespace, name) = identifier.split_by_first_dot();
+        let namespace = namespace.unwrap();
+        assert_eq!(namespace.name, "shared");
+        assert_eq!(
+            namespace.range,
+            Range {
+                start: Position { line: 3, column: 5 },
+                end: Position {
+                    line: 3,
+                    column: 11,
+                },
+            }
+        );
+        // the dot belongs to neither segment
+        assert_eq!(name.name, "SharedStruct");
+        assert_eq!(
+            name.range,
+            Range {
+                start: Position {
+                    line: 3,
+                    column: 12,
+                },
+                end: Position {
+                    line: 3,
+                    column: 24,
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn resolve_const_values() {
+        let mut analyzer = Analyzer::new();
+        let path = open(&mut analyzer, "constants.thrift");
+        let tutorial_path = test_file_path("tutorial.thrift");
+
+        // 2: tutorial.Operation op = tutorial.Operation.MULTIPLY,
+        let pos = Position {
+            line: 8,
+            column: 52,
+        };
+        let location = analyzer.definition(&path, pos).unwrap();
+        assert_eq!(location.path, tutorial_path);
+        assert_eq!(
+            location.range.start,
+            Position {
+                line: 12,
+                column: 3
+            }
+        );
+        let hover = analyzer.hover(&path, pos).unwrap();
+        assert!(hover
+            .contents
+            .starts_with("```thrift\nOperation.MULTIPLY = 3\n```"));
+
+        // the namespace part goes to the include
+        let pos = Position {
+            line: 8,
+            column: 31,
+        };
+        let location = analyzer.definition(&path, pos).unwrap();
+        assert_eq!(location.path, path);
+        assert_eq!(location.range.start.line, 1);
+
+        // 1: i32 num = DEFAULT_NUM,
+        let pos = Position {
+            line: 7,
+            column: 17,
+        };
+        let location = analyzer.definition(&path, pos).unwrap();
+        assert_eq!(
+            location.range.start,
+            Position {
+                line: 3,
+                column: 11
+            }
+        );
+        assert_eq!(analyzer.references(&path, pos, true).len(), 3);
+
+        let errors: Vec<&str> = analyzer.errors()[&path]
+            .iter()
+            .map(|error| error.message.as_str())
+            .collect();
+        assert_eq!(errors, vec!["Undefined constant: UNKNOWN"]);
+    }
+
+    #[test]
+    fn document_links_resolved_includes() {
+        let mut analyzer = Analyzer::new();
+        let path = open(&mut analyzer, "tutorial.thrift");
+
+        let links = analyzer.document_links(&path);
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].path, test_file_path("shared.thrift"));
+        assert_eq!(links[0].range.start, Position { line: 1, column: 9 });
+
+        // include "ThriftTes.thrift" does not exist
+        let path = open(&mut analyzer, "InvalidThriftTest.thrift");
+        assert!(analyzer.document_links(&path).is_empty());
+    }
+
+    #[test]
+    fn service_type_hierarchy() {
+        let mut analyzer = Analyzer::new();
+        analyzer.index_workspace(&test_file_path(""));
+        let path = open(&mut analyzer, "tutorial.thrift");
+        let shared_path = test_file_path("shared.thrift");
+
+        // service Calculator extends shared.SharedService {
+        let item = analyzer
+            .prepare_type_hierarchy(
+                &path,
+                Position {
+                    line: 29,
+                    column: 9,
+                },
+            )
+            .unwrap();
+        assert_eq!(item.name, "Calculator");
+
+        let supertypes = analyzer.supertypes(&path, "Calculator");
+        assert_eq!(supertypes.len(), 1);
+        assert_eq!(supertypes[0].name, "SharedService");
+        assert_eq!(supertypes[0].path, shared_path);
+
+        // scientific.thrift is not open
+        let subtypes = analyzer.subtypes(&path, "Calculator");
+        assert_eq!(subtypes.len(), 1);
+        assert_eq!(subtypes[0].name, "ScientificCalculator");
+        assert_eq!(subtypes[0].path, test_file_path("scientific.thrift"));
+
+        let item = analyzer
+            .prepare_type_hierarchy(
+                &path,
+                Position {
+                    line: 29,
+                    column: 40,
+                },
+            )
+            .unwrap();
+        assert_eq!(item.name, "SharedService");
+        let subtypes = analyzer.subtypes(&shared_path, "SharedService");
+        assert_eq!(subtypes.len(), 1);
+        assert_eq!(subtypes[0].name, "Calculator");
+    }
+
+    #[test]
+    fn type_usage_hierarchy() {
+        let mut analyzer = Analyzer::new();
+        let usages_path = test_file_path("usages.thrift");
+        analyzer.index_file(&usages_path);
+        let path = open(&mut analyzer, "tutorial.thrift");
+
+        // struct Work {
+        let item = analyzer
+            .prepare_call_hierarchy(
+                &path,
+                Position {
+                    line: 17,
+                    column: 8,
+                },
+            )
+            .unwrap();
+        assert_eq!(item.name, "Work");
+
+        let calls = analyzer.incoming_calls(&path, item.selection_range.start);
+        let names: Vec<&str> = calls.iter().map(|call| call.from.name.as_str()).collect();
+        assert_eq!(names, vec!["calculate", "WorkList"]);
+        assert_eq!(calls[0].from.detail.as_deref(), Some("Calculator"));
+
+        // WorkList -> Batch -> BatchCalculator.run
+        let calls = analyzer.incoming_calls(&usages_path, calls[1].from.selection_range.start);
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].from.name, "Batch");
+        let calls = analyzer.incoming_calls(&usages_path, calls[0].from.selection_range.start);
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].from.name, "run");
+        assert_eq!(calls[0].from_ranges.len(), 2);
+
+        let calls = analyzer.outgoing_calls(&usages_path, calls[0].from.selection_range.start);
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].to.name, "Batch");
+    }
+
+    #[test]
+    fn document_highlights_read_and_write() {
+        let mut analyzer = Analyzer::new();
+        let path = open(&mut analyzer, "tutorial.thrift");
+
+        // struct Work {
+        let highlights = analyzer.document_highlights(
+            &path,
+            Position {
+                line: 17,
+                column: 9,
+            },
+        );
+        let kinds: Vec<(u32, DocumentHighlightKind)> = highlights
+            .iter()
+            .map(|highlight| (highlight.range.start.line, highlight.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (17, DocumentHighlightKind::Write),
+                (32, DocumentHighlightKind::Read)
+            ]
+        );
+
+        // 3: Operation op,
+        let highlights = analyzer.document_highlights(
+            &path,
+            Position {
+                line: 20,
+                column: 8,
+            },
+        );
+        assert_eq!(highlights.len(), 2);
+        assert_eq!(highlights[0].range.start.line, 9);
+    }
+
+    #[test]
+    fn folding_ranges_of_blocks_and_comments() {
+        let mut analyzer = Analyzer::new();
+        let path = open(&mut analyzer, "ThriftTest.thrift");
+
+        let ranges = analyzer.folding_ranges(&path);
+        let folding_range = |start_line, end_line, kind| FoldingRange {
+            start_line,
+            end_line,
+            kind,
+        };
+        for expected in [
+            // license block comment
+            folding_range(1, 22, Some(FoldingRangeKind::Comment)),
+            // namespaces
+            folding_range(24, 38, Some(FoldingRangeKind::Imports)),
+            // line comments
+            folding_range(40, 43, Some(FoldingRangeKind::Comment)),
+            // doc comment and enum Numberz
+            folding_range(47, 49, Some(FoldingRangeKind::Comment)),
+            folding_range(50, 58, None),
+        ] {
+            assert!(ranges.contains(&expected), "missing {:?}", expected);
+        }
+    }
+
+    #[test]
+    fn selection_ranges_expand_to_definition() {
+        let mut analyzer = Analyzer::new();
+        let path = open(&mut analyzer, "tutorial.thrift");
+
+        // `Operation` in `3: Operation op,`
+        let pos = Position {
+            line: 20,
+            column: 8,
+        };
+        let selection_ranges = analyzer.selection_ranges(&path, &[pos]);
+        assert_eq!(selection_ranges.len(), 1);
+
+        let mut ranges = Vec::new();
+        let mut selection_range = Some(&selection_ranges[0]);
+        while let Some(current) = selection_range {
+            let range = &current.range;
+            ranges.push((
+                (range.start.line, range.start.column),
+                (range.end.line, range.end.column),
+            ));
+            selection_range = current.parent.as_deref();
+        }
+        assert_eq!(
+            ranges,
+            vec![
+                ((20, 6), (20, 15)), // field type
+                ((20, 3), (20, 19)), // field
+                ((17, 13), (22, 2)), // struct body
+                ((17, 1), (22, 2)),  // struct Work
+                ((1, 1), (35, 2)),   // document
+            ]
+        );
+
+        // braces in strings, comments and annotations are not the body
+        let path = test_file_path("braces.thrift");
+        analyzer.sync_document(
+            &path,
+            "struct A /* { */ {\n  1: string s = \"}\",\n} (x = \"}\")\n",
+        );
+        let selection_ranges = analyzer.selection_ranges(&path, &[Position { line: 2, column: 6 }]);
+        let body = selection_ranges[0]
+            .parent
+            .as_ref()
+            .unwrap()
+            .parent
+            .as_ref()
+            .unwrap();
+        assert_eq!(
+            body.range,
+            Range {
+                start: Position {
+                    line: 1,
+                    column: 18
+                },
+                end: Position { line: 3, column: 2 },
+            }
+        );
+    }
+
+    #[test]
+    fn code_lenses_count_references_and_subtypes() {
+        let mut analyzer = Analyzer::new();
+        let path = open(&mut analyzer, "tutorial.thrift");
+        open(&mut analyzer, "scientific.thrift");
+        open(&mut analyzer, "usages.thrift");
+
+        let code_lenses = analyzer.code_lenses(&path);
+        assert_eq!(code_lenses.len(), 4);
+        assert!(code_lenses
+            .iter()
+            .all(|code_lens| code_lens.title.is_none()));
+
+        let titles: Vec<String> = code_lenses
+            .into_iter()
+            .map(|code_lens| analyzer.resolve_code_lens(&path, code_lens))
+            .map(|code_lens| code_lens.title.unwrap_or_default())
+            .collect();
+        assert_eq!(
+            titles,
+            vec![
+                "1 reference",           // enum Operation
+                "2 references",          // struct Work
+                "1 reference",           // exception InvalidOperation
+                "extended by 1 service", // service Calculator
+            ]
+        );
+    }
+
+    #[test]
+    fn inlay_hints_of_implicit_numbers() {
+        let mut analyzer = Analyzer::new();
+        let path = open(&mut analyzer, "implicit.thrift");
+
+        let range = Range {
+            start: Position { line: 1, column: 1 },
+            end: Position {
+                line: 20,
+                column: 1,
+            },
+        };
+        let hints: Vec<(u32, u32, String)> = analyzer
+            .inlay_hints(&path, range)
+            .into_iter()
+            .map(|hint| (hint.position.line, hint.position.column, hint.label))
+            .collect();
+        assert_eq!(
+            hints,
+            vec![
+                (2, 6, "= 0".to_string()),
+                (4, 7, "= 6".to_string()),
+                (8, 3, "-1:".to_string()),
+                (10, 3, "-2:".to_string()),
+                (14, 3, "-1:".to_string()),
+                (18, 13, "-1:".to_string()),
+                (18, 34, "-1:".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn type_definition_through_typedefs() {
+        let mut analyzer = Analyzer::new();
+        let path = open(&mut analyzer, "typedefs.thrift");
+        assert!(analyzer.errors()[&path].is_empty());
+
+        // `CreatedAt` in `1: CreatedAt created,` is an i64
+        let location = analyzer
+            .type_definition(
+                &path,
+                Position {
+                    line: 10,
+                    column: 6,
+                },
+            )
+            .unwrap();
+        assert_eq!(location.path, path);
+        assert_eq!(location.range.start, Position { line: 3, column: 9 });
+
+        // the field `job` is a tutorial.Work
+        let location = analyzer
+            .type_definition(
+                &path,
+                Position {
+                    line: 12,
+                    column: 18,
+                },
+            )
+            .unwrap();
+        assert!(location.path.ends_with("tutorial.thrift"));
+        assert_eq!(location.range.start.line, 17);
+
+        let range = Range {
+            start: Position { line: 1, column: 1 },
+            end: Position {
+                line: 15,
+                column: 1,
+            },
+        };
+        let labels: Vec<String> = analyzer
+            .inlay_hints(&path, range)
+            .into_iter()
+            .map(|hint| hint.label)
+            .collect();
+        assert_eq!(
+            labels,
+            vec![": i64", ": map<string, i64>", ": tutorial.Work"]
+        );
+    }
+
+    #[test]
+    fn semantic_tokens_by_resolved_kind() {
+        let mut analyzer = Analyzer::new();
+        let path = open(&mut analyzer, "constants.thrift");
+
+        let tokens = decode_semantic_tokens(&mut analyzer, &path);
+        for expected in [
+            (1, 9, 17, "string", ""),
+            // const i32 DEFAULT_NUM = tutorial.INT32CONSTANT
+            (3, 7, 3, "type", ""),
+            (3, 11, 11, "variable", "declaration,definition,readonly"),
+            (3, 25, 8, "namespace", ""),
+            (3, 34, 13, "variable", "readonly"),
+            // tutorial.Operation.ADD
+            (4, 48, 9, "enum", ""),
+            (4, 58, 3, "enumMember", "readonly"),
+            (6, 8, 8, "struct", "declaration,definition"),
+            (7, 3, 1, "number", ""),
+            (7, 10, 3, "property", "declaration"),
+            (10, 18, 4, "keyword", ""),
+        ] {
+            assert!(
+                tokens.contains(&(
+                    expected.0,
+                    expected.1,
+                    expected.2,
+                    expected.3.to_string(),
+                    expected.4.to_string()
+                )),
+                "missing {:?}",
+                expected
+            );
+        }
+
+        // `} (python.immutable= "")`
+        let path = open(&mut analyzer, "ThriftTest.thrift");
+        let tokens = decode_semantic_tokens(&mut analyzer, &path);
+        assert!(tokens.contains(&(107, 4, 16, "decorator".to_string(), "".to_string())));
+    }
+
+    #[test]
+    fn semantic_tokens_range_and_delta() {
+        let mut analyzer = Analyzer::new();
+        let path = open(&mut analyzer, "tutorial.thrift");
+        let previous = analyzer.semantic_tokens(&path).unwrap().clone();
+
+        // enum Operation { ... }
+        let range = Range {
+            start: Position { line: 9, column: 1 },
+            end: Position {
+                line: 15,
+                column: 1,
+            },
+        };
+        let data = analyzer.semantic_tokens_range(&path, range).unwrap();
+        assert_eq!(data[0], 8);
+        let lines: u32 = data.chunks(5).map(|token| token[0]).sum();
+        assert_eq!(lines, 12);
+
+        // several changes between two requests
+        let content = fs::read_to_string(&path).unwrap();
+        analyzer.sync_document(&path, &content.replace("MULTIPLY = 3", "MULTIPLY = 4"));
+        analyzer.sync_document(&path, &content.replace("MULTIPLY = 3", "MULTIPLY"));
+
+        let delta = analyzer
+            .semantic_tokens_delta(&path, &previous.result_id)
+            .unwrap();
+        let current = analyzer.semantic_tokens(&path).unwrap().clone();
+        assert_ne!(previous.result_id, current.result_id);
+        assert_eq!(delta.result_id, current.result_id);
+        assert_eq!(delta.edits.len(), 1);
+        let mut data = previous.data.clone();
+        let edit = &delta.edits[0];
+        data.splice(
+            edit.start as usize..(edit.start + edit.delete_count) as usize,
+            edit.data.iter().copied(),
+        );
+        assert_eq!(data, current.data);
+
+        let delta = analyzer
+            .semantic_tokens_delta(&path, &current.result_id)
+            .unwrap();
+        assert!(delta.edits.is_empty());
+        assert!(analyzer
+            .semantic_tokens_delta(&path, &previous.result_id)
+            .is_none());
+        assert!(analyzer.semantic_tokens_delta(&path, "unknown").is_none());
+    }
+
+    #[test]
+    fn deprecated_usages() {
+        let mut analyzer = Analyzer::new();
+        let path = open(&mut analyzer, "deprecated.thrift");
+
+        let warnings: Vec<(u32, u32, &str)> = analyzer.errors()[&path]
+            .iter()
+            .filter(|e| e.tags.contains(&DiagnosticTag::Deprecated))
+            .map(|e| (e.range.start.line, e.range.start.column, e.message.as_str()))
+            .collect();
+        assert_eq!(
+            warnings,
+            vec![
+                (13, 6, "`LegacyUser` is deprecated: use User"),
+                (19, 3, "`LegacyUser` is deprecated: use User"),
+                (12, 22, "`Status.RETIRED` is deprecated: use ACTIVE"),
+            ]
+        );
+        assert!(analyzer.errors()[&path]
+            .iter()
+            .all(|e| e.severity == Severity::Warning));
+
+        let tokens = decode_semantic_tokens(&mut analyzer, &path);
+        for expected in [
+            (
+                3,
+                3,
+                7,
+                "enumMember",
+                "declaration,definition,readonly,deprecated",
+            ),
+            (6, 8, 10, "struct", "declaration,definition,deprecated"),
+            (12, 29, 7, "enumMember", "readonly,deprecated"),
+            (13, 6, 10, "struct", "deprecated"),
+            (14, 10, 3, "property", "declaration,deprecated"),
+            (19, 14, 9, "function", "declaration,definition,deprecated"),
+        ] {
+            assert!(
+                tokens.contains(&(
+                    expected.0,
+                    expected.1,
+                    expected.2,
+                    expected.3.to_string(),
+                    expected.4.to_string()
+                )),
+                "missing {:?}",
+                expected
+            );
+        }
+
+        let hover = analyzer
+            .hover(
+                &path,
+                Position {
+                    line: 13,
+                    column: 8,
+                },
+            )
+            .unwrap();
+        assert!(hover.contents.contains("**Deprecated**: use User"));
+
+        // another annotation key
+        analyzer.set_deprecation_annotation("obsolete");
+        assert!(analyzer.errors()[&path].is_empty());
+    }
+
+    #[test]
+    fn formatting_aligns_fields_and_keeps_comments() {
+        let mut analyzer = Analyzer::new();
+        let path = test_file_path("format.thrift");
+        let source = r#"// Shared definitions
+include "shared.thrift"
+namespace java tutorial
+
+
+const i32 A=1;
+const list<i32> NUMS = [1,2,
+  3]
+/** A job. */
+struct Job{
+  1:i32 id, // the id
+  2: optional string name;
+
+  10: map<string,i32> counts = {"a":1} (cpp.type = "Counts")
+}
+enum State {ON=1,OFF}
+service Jobs extends shared.SharedService {
+  Job get(1:i32 id) throws (1:shared.SharedStruct e)
+}"#;
+        let expected = r#"// Shared definitions
+include "shared.thrift"
+namespace java tutorial
+
+const i32 A = 1
+const list<i32> NUMS = [
+  1,
+  2,
+  3,
+]
+
+/** A job. */
+struct Job {
+  1: i32             id, // the id
+  2: optional string name,
+
+  10: map<string, i32> counts = {"a": 1} (cpp.type = "Counts"),
+}
+
+enum State {
+  ON = 1,
+  OFF,
+}
+
+service Jobs extends shared.SharedService {
+  Job get(1: i32 id) throws (1: shared.SharedStruct e),
+}
+"#;
+        analyzer.sync_document(&path, source);
+
+        let options = FormattingOptions {
+            tab_size: 2,
+            insert_spaces: true,
+        };
+        assert_eq!(
+            apply_edits(source, &analyzer.formatting(&path, &options)),
+            expected
+        );
+
+        let tabs = FormattingOptions {
+            tab_size: 4,
+            insert_spaces: false,
+        };
+        let formatted = apply_edits(source, &analyzer.formatting(&path, &tabs));
+        assert!(formatted.contains("\n\t1: i32             id, // the id\n"));
+
+        // already formatted
+        analyzer.sync_document(&path, expected);
+        assert!(analyzer.formatting(&path, &options).is_empty());
+
+        // the line ending of the document is kept
+        analyzer.sync_document(&path, &expected.replace('\n', "\r\n"));
+        assert!(analyzer.formatting(&path, &options).is_empty());
+        analyzer.sync_document(&path, "enum State {ON=1,OFF}\r\n");
+        let edits = analyzer.formatting(&path, &options);
+        assert_eq!(
+            edits[0].new_text,
+            "enum State {\r\n  ON = 1,\r\n  OFF,\r\n}\r\n"
+        );
+
+        // an enum value not written as the parsed number is kept as written
+        analyzer.sync_document(&path, "enum E { A = 0x10 (x = \"y\"), B }\n");
+        assert!(analyzer.formatting(&path, &options).is_empty());
+
+        // syntax errors
+        analyzer.sync_document(&path, "struct Job {\n  1: i32\n}\n");
+        assert!(analyzer.formatting(&path, &options).is_empty());
+    }
+
+    #[test]
+    fn range_and_on_type_formatting() {
+        let mut analyzer = Analyzer::new();
+        let path = test_file_path("format.thrift");
+        let options = FormattingOptions {
+            tab_size: 2,
+            insert_spaces: true,
+        };
+        let pos = |line, column| Position { line, column };
+
+        // only the selected definition is formatted
+        let source = "struct A {\n1:i32 a\n}\n\nstruct B {\n1:i32 b\n}\n";
+        analyzer.sync_document(&path, source);
+        let range = Range {
+            start: pos(1, 1),
+            end: pos(3, 2),
+        };
+        assert_eq!(
+            apply_edits(source, &analyzer.range_formatting(&path, &range, &options)),
+            "struct A {\n  1: i32 a,\n}\n\nstruct B {\n1:i32 b\n}\n"
+        );
+
+        // and only the selected lines of a block of changed lines
+        let source = "struct A {\n1:i32 a\n2:i32 b\n3:i32 c\n}\n";
+        analyzer.sync_document(&path, source);
+        let range = Range {
+            start: pos(3, 1),
+            end: pos(3, 8),
+        };
+        assert_eq!(
+            apply_edits(source, &analyzer.range_formatting(&path, &range, &options)),
+            "struct A {\n1:i32 a\n  2: i32 b,\n3:i32 c\n}\n"
+        );
+
+        // a line break after a field starts the next field
+        let source = "struct A {\n  1: i32 a,\n  5: i32 b // b\n\n}\n";
+        analyzer.sync_document(&path, source);
+        assert_eq!(
+            analyzer.on_type_formatting(&path, pos(4, 1), "\n", &options),
+            vec![TextEdit {
+                range: Range {
+                    start: pos(4, 1),
+                    end: pos(4, 1),
+                },
+                new_text: "  6: ".to_string(),
+            }]
+        );
+
+        // and so does a line break after the opening of a field list
+        let source = "service S {\n  void f(\n      \n  ) throws (\n    1: E e,\n    \n  )\n}\n";
+        analyzer.sync_document(&path, source);
+        assert_eq!(
+            apply_edits(
+                source,
+                &analyzer.on_type_formatting(&path, pos(3, 7), "\n", &options)
+            ),
+            "service S {\n  void f(\n    1: \n  ) throws (\n    1: E e,\n    \n  )\n}\n"
+        );
+        assert_eq!(
+            apply_edits(
+                source,
+                &analyzer.on_type_formatting(&path, pos(6, 5), "\n", &options)
+            ),
+            "service S {\n  void f(\n      \n  ) throws (\n    1: E e,\n    2: \n  )\n}\n"
+        );
+
+        // but not a line break elsewhere
+        let source = "enum E {\n  A,\n\n}\n";
+        analyzer.sync_document(&path, source);
+        assert!(analyzer
+            .on_type_formatting(&path, pos(3, 1), "\n", &options)
+            .is_empty());
+
+        // a closing brace is indented like the line of its opening brace
+        let source = "struct A {\n  1: map<i32, i32> m = {\n    1: 2\n  },\n    }\n";
+        analyzer.sync_document(&path, source);
+        assert_eq!(
+            apply_edits(
+                source,
+                &analyzer.on_type_formatting(&path, pos(5, 6), "}", &options)
+            ),
+            "struct A {\n  1: map<i32, i32> m = {\n    1: 2\n  },\n}\n"
+        );
+        assert!(analyzer
+            .on_type_formatting(&path, pos(4, 4), "}", &options)
+            .is_empty());
+    }
+
+    #[test]
+    fn missing_include_quick_fixes() {
+        let mut analyzer = Analyzer::new();
+        let user_path = test_file_path("types/user.thrift");
+        let account_path = test_file_path("account.thrift");
+        let path = test_file_path("profile.thrift");
+        analyzer.sync_document(&user_path, "struct User {\n  1: string name\n}\n");
+        analyzer.sync_document(&account_path, "const i32 User = 1\nstruct Account {}\n");
+        analyzer.sync_document(
+            &path,
+            "// Profiles\nnamespace java demo\n\nstruct Profile {\n  1: User user\n}\n",
+        );
+
+        let range = Range {
+            start: Position { line: 5, column: 6 },
+            end: Position { line: 5, column: 6 },
+        };
+        let actions = analyzer.code_actions(&path, &range);
+        // account.thrift only has a const named `User`, and `User` can be created
+        assert_eq!(actions.len(), 4);
+        assert_eq!(
+            actions[0].title,
+            "Add `include \"types/user.thrift\"` and use `user.User`"
+        );
+        assert_eq!(actions[0].kind, CodeActionKind::QuickFix);
+        assert_eq!(actions[0].diagnostics[0].message, "Undefined type: User");
+        assert_eq!(
+            actions[0].edit.as_ref().unwrap().changes[&path],
+            vec![
+                TextEdit {
+                    range: Range {
+                        start: Position { line: 2, column: 1 },
+                        end: Position { line: 2, column: 1 },
+                    },
+                    new_text: "include \"types/user.thrift\"\n\n".to_string(),
+                },
+                TextEdit {
+                    range: Range {
+                        start: Position { line: 5, column: 6 },
+                        end: Position {
+                            line: 5,
+                            column: 10
+                        },
+                    },
+                    new_text: "user.User".to_string(),
+                },
+            ]
+        );
+
+        // no errors in the range
+        let range = Range {
+            start: Position { line: 1, column: 1 },
+            end: Position { line: 2, column: 1 },
+        };
+        assert!(analyzer.code_actions(&path, &range).is_empty());
+
+        // a file that is already included is only used, and a new include goes after
+        // the others
+        analyzer.sync_document(
+            &account_path,
+            "include \"types/user.thrift\"\n\nstruct Account {\n  1: User user\n}\n",
+        );
+        analyzer.sync_document(
+            &path,
+            "include \"account.thrift\"\n\nstruct Profile {\n  1: User user\n  2: Account account\n}\n",
+        );
+        let range = Range {
+            start: Position { line: 4, column: 1 },
+            end: Position { line: 5, column: 1 },
+        };
+        let actions = analyzer.code_actions(&path, &range);
+        assert_eq!(actions.len(), 4);
+        assert_eq!(
+            actions[0].edit.as_ref().unwrap().changes[&path][0],
+            TextEdit {
+                range: Range {
+                    start: Position {
+                        line: 1,
+                        column: 25
+                    },
+                    end: Position {
+                        line: 1,
+                        column: 25
+                    },
+                },
+                new_text: "\ninclude \"types/user.thrift\"".to_string(),
+            }
+        );
+        let range = Range {
+            start: Position { line: 5, column: 6 },
+            end: Position { line: 5, column: 6 },
+        };
+        let actions = analyzer.code_actions(&path, &range);
+        assert_eq!(actions.len(), 4);
+        assert_eq!(actions[0].title, "Use `account.Account`");
+        assert_eq!(actions[0].edit.as_ref().unwrap().changes[&path].len(), 1);
+
+        // relative paths go up to the common directory
+        let user_path = normalize_path(&user_path);
+        let nested = user_path.replace("types/user.thrift", "api/v1/service.thrift");
+        assert_eq!(
+            relative_path(&nested, &user_path),
+            "../../types/user.thrift"
+        );
+    }
+
+    #[test]
+    fn create_definition_quick_fixes() {
+        let mut analyzer = Analyzer::new();
+        let path = test_file_path("stubs.thrift");
+        let titles =
+            |actions: &[CodeAction]| actions.iter().map(|a| a.title.clone()).collect::<Vec<_>>();
+
+        let source = "struct Job {\n  1: Task task\n}\n\nservice Jobs extends Base {\n  void run() throws (1: Failure failure)\n}";
+        analyzer.sync_document(&path, source);
+
+        let actions = analyzer.code_actions(&path, &at(2, 6));
+        assert_eq!(
+            titles(&actions),
+            vec![
+                "Create struct `Task`",
+                "Create enum `Task`",
+                "Create exception `Task`"
+            ]
+        );
+        assert_eq!(actions[0].diagnostics[0].message, "Undefined type: Task");
+        assert_eq!(
+            actions[1].edit.as_ref().unwrap().changes[&path],
+            vec![TextEdit {
+                range: at(4, 1),
+                new_text: "\nenum Task {}\n".to_string(),
+            }]
+        );
+
+        let actions = analyzer.code_actions(&path, &at(5, 22));
+        assert_eq!(titles(&actions), vec!["Create service `Base`"]);
+
+        // the last definition has no line break after it
+        let actions = analyzer.code_actions(&path, &at(6, 26));
+        assert_eq!(titles(&actions), vec!["Create exception `Failure`"]);
+        assert_eq!(
+            actions[0].edit.as_ref().unwrap().changes[&path],
+            vec![TextEdit {
+                range: at(7, 2),
+                new_text: "\n\nexception Failure {}\n".to_string(),
+            }]
+        );
+
+        // only types of the document itself are created
+        analyzer.sync_document(&path, "struct Job {\n  1: shared.Task task\n}\n");
+        assert!(analyzer.code_actions(&path, &at(2, 6)).is_empty());
+    }
+
+    #[test]
+    fn field_id_quick_fixes() {
+        let mut analyzer = Analyzer::new();
+        let path = test_file_path("field_ids.thrift");
+        analyzer.sync_document(
+            &path,
+            "struct Job {\n  1: i32 id,\n  2: string name,\n  1: i64 created,\n  optional bool done,\n}\n\nservice Jobs {\n  void run(1: i32 id, string note)\n}\n",
+        );
+        let insert = |line, column, text: &str| TextEdit {
+            range: at(line, column),
+            new_text: text.to_string(),
+        };
+
+        let actions = analyzer.code_actions(&path, &at(4, 3));
+        let titles: Vec<&str> = actions.iter().map(|a| a.title.as_str()).collect();
+        assert_eq!(
+            titles,
+            vec![
+                "Change field ID to 3",
+                "Assign explicit IDs to all fields of `Job`"
+            ]
+        );
+        assert_eq!(actions[0].kind, CodeActionKind::QuickFix);
+        assert_eq!(actions[0].diagnostics[0].message, "Duplicate field ID: 1");
+        assert_eq!(actions[1].kind, CodeActionKind::RefactorRewrite);
+        assert!(actions[1].diagnostics.is_empty());
+        assert_eq!(
+            actions[0].edit.as_ref().unwrap().changes[&path],
+            vec![TextEdit {
+                range: Range {
+                    start: Position { line: 4, column: 3 },
+                    end: Position { line: 4, column: 4 },
+                },
+                new_text: "3".to_string(),
+            }]
+        );
+        assert_eq!(
+            actions[1].edit.as_ref().unwrap().changes[&path],
+            vec![insert(5, 3, "3: ")]
+        );
+
+        let actions = analyzer.code_actions(&path, &at(9, 5));
+        assert_eq!(actions.len(), 1);
+        assert_eq!(
+            actions[0].title,
+            "Assign explicit IDs to all arguments of `run`"
+        );
+        assert_eq!(
+            actions[0].edit.as_ref().unwrap().changes[&path],
+            vec![insert(9, 23, "2: ")]
+        );
+
+        // every field has an ID
+        analyzer.sync_document(&path, "struct Job {\n  1: i32 id,\n}\n");
+        assert!(analyzer.code_actions(&path, &at(2, 3)).is_empty());
+    }
+
+    #[test]
+    fn extract_and_inline_typedef() {
+        let mut analyzer = Analyzer::new();
+        let path = test_file_path("typedefs.thrift");
+
+        let source = "struct Job {\n  1: map<string, list<i64>> ids,\n  2: list<i64> counts,\n}\n\nservice Jobs {\n  map<string,list<i64>> get(1: list<i64> keys),\n}\n";
+        analyzer.sync_document(&path, source);
+
+        // the innermost container type is extracted
+        let actions = analyzer.code_actions(&path, &at(2, 20));
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].title, "Extract `list<i64>` into a typedef");
+        assert_eq!(actions[0].kind, CodeActionKind::RefactorExtract);
+        let range = match &actions[0].command {
+            Some(CodeActionCommand::ExtractTypedef { range, .. }) => range.clone(),
+            _ => panic!("missing command"),
+        };
+        let actions = analyzer.code_actions(&path, &at(2, 6));
+        assert_eq!(
+            actions[0].title,
+            "Extract `map<string, list<i64>>` into a typedef"
+        );
+
+        let edit = analyzer.extract_typedef(&path, &range, "Ids").unwrap();
+        assert_eq!(
+            apply_edits(source, &edit.changes[&path]),
+            "typedef list<i64> Ids\n\nstruct Job {\n  1: map<string, Ids> ids,\n  2: Ids counts,\n}\n\nservice Jobs {\n  map<string,Ids> get(1: Ids keys),\n}\n"
+        );
+        assert!(analyzer.extract_typedef(&path, &range, "1ds").is_err());
+        assert!(analyzer.extract_typedef(&path, &range, "Job").is_err());
+        assert!(analyzer.extract_typedef(&path, &at(1, 1), "Ids").is_err());
+
+        let source = "/** Ids of a job. */\ntypedef list<i64> Ids\n\nstruct Job {\n  1: Ids ids,\n  2: map<string, Ids> counts,\n}\n";
+        analyzer.sync_document(&path, source);
+        let expected =
+            "struct Job {\n  1: list<i64> ids,\n  2: map<string, list<i64>> counts,\n}\n";
+
+        // on a usage or on the typedef
+        for range in [at(5, 6), at(2, 20)] {
+            let actions = analyzer.code_actions(&path, &range);
+            let action = actions
+                .iter()
+                .find(|a| a.kind == CodeActionKind::RefactorInline)
+                .unwrap();
+            assert_eq!(action.title, "Inline typedef `Ids`");
+            let mut edits = action.edit.as_ref().unwrap().changes[&path].clone();
+            edits.sort_by(|a, b| a.range.cmp(&b.range));
+            assert_eq!(apply_edits(source, &edits), expected);
+        }
+
+        // a typedef naming a type is not inlined from another file
+        let jobs_path = test_file_path("jobs.thrift");
+        analyzer.sync_document(&path, "struct Job {}\n\ntypedef Job Current\n");
+        analyzer.sync_document(
+            &jobs_path,
+            "include \"typedefs.thrift\"\n\nstruct Queue {\n  1: typedefs.Current job,\n}\n",
+        );
+        assert!(analyzer
+            .code_actions(&jobs_path, &at(4, 17))
+            .iter()
+            .all(|a| a.kind != CodeActionKind::RefactorInline));
+        let actions = analyzer.code_actions(&path, &at(3, 14));
+        let action = actions
+            .iter()
+            .find(|a| a.kind == CodeActionKind::RefactorInline)
+            .unwrap();
+        assert!(!action
+            .edit
+            .as_ref()
+            .unwrap()
+            .changes
+            .contains_key(&jobs_path));
+    }
+
+    #[test]
+    fn move_definition() {
+        let mut analyzer = Analyzer::new();
+        let common_path = test_file_path("common.thrift");
+        let api_path = test_file_path("api.thrift");
+        let colors_path = test_file_path("types/colors.thrift");
+
+        // an enum moved to an empty file, with the usages of its members
+        let common =
+            "enum Color {\n  RED = 1,\n}\n\nstruct Job {\n  1: Color color = Color.RED,\n}\n";
+        let api = "include \"common.thrift\"\n\nconst common.Color DEFAULT = common.Color.RED\n";
+        analyzer.sync_document(&colors_path, "");
+        analyzer.sync_document(&common_path, common);
+        analyzer.sync_document(&api_path, api);
+
+        let actions = analyzer.code_actions(
+            &common_path,
+            &Range {
+                start: Position { line: 1, column: 7 },
+                end: Position { line: 1, column: 7 },
+            },
+        );
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].title, "Move `Color` to another file");
+        assert_eq!(actions[0].kind, CodeActionKind::RefactorMove);
+        let position = match &actions[0].command {
+            Some(CodeActionCommand::MoveDefinition { position, .. }) => *position,
+            _ => panic!("missing command"),
+        };
+
+        let edit = analyzer
+            .move_definition(&common_path, position, &colors_path)
+            .unwrap();
+        assert_eq!(
+            apply_edits(common, &edit.changes[&common_path]),
+            "include \"types/colors.thrift\"\n\nstruct Job {\n  1: colors.Color color = colors.Color.RED,\n}\n"
+        );
+        assert_eq!(
+            apply_edits(api, &edit.changes[&api_path]),
+            "include \"common.thrift\"\ninclude \"types/colors.thrift\"\n\nconst colors.Color DEFAULT = colors.Color.RED\n"
+        );
+        assert_eq!(
+            apply_edits("", &edit.changes[&colors_path]),
+            "enum Color {\n  RED = 1,\n}\n"
+        );
+
+        // a struct moved with its doc comment, using types of other files and itself
+        let ids_path = test_file_path("ids.thrift");
+        let jobs_path = test_file_path("jobs.thrift");
+        let common = "include \"ids.thrift\"\n\nenum Color {\n  RED = 1,\n}\n\n/** A job. */\nstruct Job {\n  1: ids.Id id,\n  2: Color color = Color.RED,\n  3: list<Job> children,\n}\n";
+        let jobs = "namespace java jobs\n\nstruct Other {}\n";
+        analyzer.sync_document(&ids_path, "typedef i64 Id\n");
+        analyzer.sync_document(&jobs_path, jobs);
+        analyzer.sync_document(&api_path, "");
+        analyzer.sync_document(&common_path, common);
+
+        let pos = Position { line: 8, column: 8 };
+        let edit = analyzer
+            .move_definition(&common_path, pos, &jobs_path)
+            .unwrap();
+        assert_eq!(
+            apply_edits(common, &edit.changes[&common_path]),
+            "include \"ids.thrift\"\n\nenum Color {\n  RED = 1,\n}\n"
+        );
+        assert_eq!(
+            apply_edits(jobs, &edit.changes[&jobs_path]),
+            "include \"ids.thrift\"\ninclude \"common.thrift\"\n\nnamespace java jobs\n\nstruct Other {}\n\n/** A job. */\nstruct Job {\n  1: ids.Id id,\n  2: common.Color color = common.Color.RED,\n  3: list<Job> children,\n}\n"
+        );
+
+        assert!(analyzer
+            .move_definition(&common_path, pos, &common_path)
+            .is_err());
+        assert!(analyzer
+            .move_definition(&common_path, pos, &test_file_path("missing.thrift"))
+            .is_err());
+        analyzer.sync_document(&jobs_path, "struct Job {}\n");
+        assert!(analyzer
+            .move_definition(&common_path, pos, &jobs_path)
+            .is_err());
+
+        // common.thrift would include jobs.thrift, which includes common.thrift
+        analyzer.sync_document(&jobs_path, jobs);
+        analyzer.sync_document(
+            &common_path,
+            &format!("{}\nstruct Task {{\n  1: Job job,\n}}\n", common),
+        );
+        let error = analyzer
+            .move_definition(&common_path, pos, &jobs_path)
+            .unwrap_err();
+        assert!(error.contains("include each other"));
+    }
+
+    #[test]
+    fn workspace_symbols_unopened_files() {
+        let mut analyzer = Analyzer::new();
+        analyzer.index_workspace(&test_file_path(""));
+
+        let symbols = analyzer.workspace_symbols("SharedStr");
+        assert_eq!(symbols[0].name, "SharedStruct");
+        assert_eq!(symbols[0].location.path, test_file_path("shared.thrift"));
+
+        let symbols = analyzer.workspace_symbols("Calculator.look");
+        assert_eq!(symbols[0].name, "lookup");
+        assert_eq!(symbols[0].container_name.as_deref(), Some("Calculator"));
+
+        // open documents take precedence over the content on disk
+        let path = test_file_path("shared.thrift");
+        analyzer.sync_document(&path, "struct Renamed {}");
+        assert!(analyzer
+            .workspace_symbols("SharedStruct")
+            .iter()
+            .all(|symbol| symbol.name != "SharedStruct"));
+        analyzer.remove_document(&path);
+        assert_eq!(
+            analyzer.workspace_symbols("SharedStruct")[0].name,
+            "SharedStruct"
+        );
+    }
+
+    #[test]
+    fn workspace_symbol_tables_cached_until_index_changes() {
+        let mut analyzer = Analyzer::new();
+        let path = test_file_path("tutorial.thrift");
+        analyzer.index_file(&path);
+
+        let first = analyzer.workspace_symbol_table(&path).unwrap();
+        let second = analyzer.workspace_symbol_table(&path).unwrap();
+        assert!(Rc::ptr_eq(&first, &second));
+
+        // updating any file invalidates the tables, which hold their includes
+        analyzer.index_file(&test_file_path("shared.thrift"));
+        let third = analyzer.workspace_symbol_table(&path).unwrap();
+        assert!(!Rc::ptr_eq(&first, &third));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn index_workspace_skips_symlinked_directories() {
+        let root = env::temp_dir().join(format!("thrift-ls-index-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("a")).unwrap();
+        fs::create_dir_all(root.join("node_modules")).unwrap();
+        fs::write(root.join("a/user.thrift"), "struct User {}\n").unwrap();
+        fs::write(root.join("node_modules/dep.thrift"), "struct Dep {}\n").unwrap();
+        std::os::unix::fs::symlink("..", root.join("a/loop")).unwrap();
+
+        let mut paths = Vec::new();
+        find_thrift_files(&root, &mut paths);
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(
+            paths,
+            vec![root.join("a/user.thrift").to_string_lossy().to_string()]
+        );
+    }
+}
@@ -3,12 +3,13 @@ use std::rc::Rc;
 use crate::{
     analyzer::{
         ast::{
-            BaseTypeNode, ConstNode, ConstValueNode, CppIncludeNode, DefinitionNode, DocumentNode,
-            EnumNode, EnumValueNode, ExceptionNode, ExtNode, FieldIdNode, FieldNode, FieldTypeNode,
-            FunctionNode, HeaderNode, IdentifierNode, IncludeNode, ListTypeNode, MapTypeNode,
-            NamespaceNode, ServiceNode, SetTypeNode, StructNode, TypedefNode, UnionNode,
+            BaseTypeNode, CommentNode, ConstNode, ConstValueKind, ConstValueNode, CppIncludeNode,
+            DefinitionNode, DocumentNode, EnumNode, EnumValueNode, ExceptionNode, ExtNode,
+            FieldIdNode, FieldNode, FieldTypeNode, FunctionNode, HeaderNode, IdentifierNode,
+            IncludeNode, ListTypeNode, MapTypeNode, NamespaceNode, ServiceNode, SetTypeNode,
+            StructNode, TypedefNode, UnionNode,
         },
-        base::{Error, Range},
+        base::{Error, Range, Severity},
         scanner::Scanner,
         token::{Token, TokenKind},
     },
@@ -21,6 +22,7 @@ pub struct Parser<'a> {
     scanner: Scanner<'a>,
     errors: Vec<Error>,
     prev_token: Option<Token>,
+    comments: Vec<CommentNode>,
 }
 
 impl<'a> Parser<'a> {
@@ -30,6 +32,7 @@ impl<'a> Parser<'a> {
             scanner: Scanner::new(input),
             errors: Vec::new(),
             prev_token: None,
+            comments: Vec::new(),
         }
     }
 
@@ -44,6 +47,7 @@ impl<'a> Parser<'a> {
         let node = DocumentNode {
             headers,
             definitions,
+            comments: self.comments,
             range,
         };
 
@@ -89,6 +93,16 @@ impl<'a> Parser<'a> {
                 self.scanner.restore_state(state);
                 break;
             }
+
+            // keep comments, e.g. for folding
+            let comment = comment_node(&next_token);
+            if self
+                .comments
+                .last()
+                .is_none_or(|last| last.range.start < comment.range.start)
+            {
+                self.comments.push(comment);
+            }
         }
     }
 }
@@ -118,11 +132,16 @@ impl<'a> Parser<'a> {
         let start = self.peek_next_token().range().start;
         expect_token!(self, Include, "'include'");
         let token = self.next_token();
+        let literal_range = token.range();
         let literal = extract_token_value!(self, token, Literal, "literal");
         let end = self.prev_token().unwrap_or_default().range().end;
 
         let range = Range { start, end };
-        Some(IncludeNode { range, literal })
+        Some(IncludeNode {
+            range,
+            literal,
+            literal_range,
+        })
     }
 
     fn parse_cpp_include(&mut self) -> Option<CppIncludeNode> {
@@ -131,11 +150,16 @@ impl<'a> Parser<'a> {
         let start = self.peek_next_token().range().start;
         expect_token!(self, CppInclude, "'cpp_include'");
         let token = self.next_token();
+        let literal_range = token.range();
         let literal = extract_token_value!(self, token, Literal, "literal");
         let end = self.prev_token().unwrap_or_default().range().end;
 
         let range = Range { start, end };
-        Some(CppIncludeNode { range, literal })
+        Some(CppIncludeNode {
+            range,
+            literal,
+            literal_range,
+        })
     }
 
     fn parse_namespace(&mut self) -> Option<NamespaceNode> {
@@ -168,7 +192,7 @@ impl<'a> Parser<'a> {
         }
 
         let name = token.kind.to_string();
-        if !TokenKind::from_string(&name).is_some() {
+        if TokenKind::from_string(&name).is_none() {
             self.add_error(format!("Invalid identifier: {}", name), token.range());
             return None;
         }
@@ -229,14 +253,12 @@ impl<'a> Parser<'a> {
         match next_token.kind {
             TokenKind::Identifier(ref identifier) => {
                 self.eat_next_token();
-                return Some(FieldTypeNode::Identifier(IdentifierNode {
+                Some(FieldTypeNode::Identifier(IdentifierNode {
                     range: next_token.range(),
                     name: identifier.clone(),
-                }));
-            }
-            _ => {
-                return self.parse_definition_type();
+                }))
             }
+            _ => self.parse_definition_type(),
         }
     }
 
@@ -247,14 +269,12 @@ impl<'a> Parser<'a> {
         match next_token.kind {
             TokenKind::BaseType(ref base_type) => {
                 self.eat_next_token();
-                return Some(FieldTypeNode::BaseType(BaseTypeNode {
+                Some(FieldTypeNode::BaseType(BaseTypeNode {
                     range: next_token.range(),
                     name: base_type.clone(),
-                }));
-            }
-            _ => {
-                return self.parse_container_type();
+                }))
             }
+            _ => self.parse_container_type(),
         }
     }
 
@@ -263,9 +283,9 @@ impl<'a> Parser<'a> {
 
         let next_token = self.peek_next_token();
         match next_token.kind {
-            TokenKind::Map => self.parse_map_type().map(|x| FieldTypeNode::MapType(x)),
-            TokenKind::Set => self.parse_set_type().map(|x| FieldTypeNode::SetType(x)),
-            TokenKind::List => self.parse_list_type().map(|x| FieldTypeNode::ListType(x)),
+            TokenKind::Map => self.parse_map_type().map(FieldTypeNode::MapType),
+            TokenKind::Set => self.parse_set_type().map(FieldTypeNode::SetType),
+            TokenKind::List => self.parse_list_type().map(FieldTypeNode::ListType),
             _ => {
                 self.add_error(
                     format!("Expected map, set, or list, but got {}", next_token.kind),
@@ -355,28 +375,38 @@ impl<'a> Parser<'a> {
         // ConstValue ::= IntConstant | DoubleConstant | Literal | Identifier | ConstList | ConstMap
 
         let next_token = self.peek_next_token();
-        match &next_token.kind {
-            TokenKind::IntConstant(value)
-            | TokenKind::DoubleConstant(value)
-            | TokenKind::Literal(value)
-            | TokenKind::Identifier(value) => {
-                self.eat_next_token();
-                Some(ConstValueNode {
-                    range: next_token.range(),
-                    value: value.clone(),
-                })
+        let (value, kind) = match &next_token.kind {
+            TokenKind::IntConstant(value) => (value.clone(), ConstValueKind::Int),
+            TokenKind::DoubleConstant(value) => (value.clone(), ConstValueKind::Double),
+            TokenKind::Literal(value) => (value.clone(), ConstValueKind::Literal),
+            TokenKind::Identifier(value) if value == "true" || value == "false" => {
+                (value.clone(), ConstValueKind::Bool)
             }
-            TokenKind::Lbrack => self.parse_const_list(),
-            TokenKind::Lbrace => self.parse_const_map(),
+            TokenKind::Identifier(value) => (
+                value.clone(),
+                ConstValueKind::Identifier(IdentifierNode {
+                    range: next_token.range(),
+                    name: value.clone(),
+                }),
+            ),
+            TokenKind::Lbrack => return self.parse_const_list(),
+            TokenKind::Lbrace => return self.parse_const_map(),
             _ => {
                 self.eat_next_token();
                 self.add_error(
                     format!("Expected constant value, but got {}", next_token.kind),
                     next_token.range(),
                 );
-                None
+                return None;
             }
-        }
+        };
+
+        self.eat_next_token();
+        Some(ConstValueNode {
+            range: next_token.range(),
+            value,
+            kind,
+        })
     }
 
     fn parse_const_list(&mut self) -> Option<ConstValueNode> {
@@ -387,7 +417,7 @@ impl<'a> Parser<'a> {
         let mut values = Vec::new();
         loop {
             break_opt_token_or_eof!(self, Rbrack);
-            values.push(self.parse_const_value()?.value);
+            values.push(self.parse_const_value()?);
             opt_list_separator!(self);
         }
         let end = self.prev_token().unwrap_or_default().range().end;
@@ -395,7 +425,15 @@ impl<'a> Parser<'a> {
         let range = Range { start, end };
         Some(ConstValueNode {
             range,
-            value: format!("[{}]", values.join(", ")),
+            value: format!(
+                "[{}]",
+                values
+                    .iter()
+                    .map(|v| v.value.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            kind: ConstValueKind::List(values),
         })
     }
 
@@ -418,14 +456,15 @@ impl<'a> Parser<'a> {
                 "{{{}}}",
                 pairs
                     .iter()
-                    .map(|(k, v)| format!("{}: {}", k, v))
+                    .map(|(k, v)| format!("{}: {}", k.value, v.value))
                     .collect::<Vec<_>>()
                     .join(", ")
             ),
+            kind: ConstValueKind::Map(pairs),
         })
     }
 
-    fn parse_const_map_value(&mut self) -> Option<(String, String)> {
+    fn parse_const_map_value(&mut self) -> Option<(ConstValueNode, ConstValueNode)> {
         // ConstMapValue ::= ConstValue ':' ConstValue ListSeparator?
 
         let key = self.parse_const_value()?;
@@ -433,15 +472,15 @@ impl<'a> Parser<'a> {
         let value = self.parse_const_value()?;
         opt_list_separator!(self);
 
-        Some((key.value, value.value))
+        Some((key, value))
     }
 
     fn parse_typedef(&mut self) -> Option<TypedefNode> {
-        // Typedef ::= 'typedef' DefinitionType Identifier
+        // Typedef ::= 'typedef' FieldType Identifier
 
         let start = self.peek_next_token().range().start;
         expect_token!(self, Typedef, "'typedef'");
-        let definition_type = self.parse_definition_type()?;
+        let definition_type = self.parse_field_type()?;
         let identifier = self.parse_identifier()?;
         let end = self.prev_token().unwrap_or_default().range().end;
 
@@ -487,7 +526,10 @@ impl<'a> Parser<'a> {
 
         let start = self.peek_next_token().range().start;
         let token = self.next_token();
-        let name = extract_token_value!(self, token, Identifier, "identifier");
+        let identifier = IdentifierNode {
+            range: token.range(),
+            name: extract_token_value!(self, token, Identifier, "identifier"),
+        };
 
         let mut value = None;
         let next_token = self.peek_next_token();
@@ -508,7 +550,7 @@ impl<'a> Parser<'a> {
         let range = Range { start, end };
         Some(EnumValueNode {
             range,
-            name,
+            identifier,
             value,
             ext,
         })
@@ -573,7 +615,7 @@ impl<'a> Parser<'a> {
 
         let next_token = self.peek_next_token();
         if let TokenKind::Required | TokenKind::Optional = next_token.kind {
-            if !field_req.is_none() {
+            if field_req.is_some() {
                 self.add_error(
                     format!("Expected field type, but got {}", next_token.kind),
                     next_token.range(),
@@ -814,7 +856,12 @@ impl<'a> Parser<'a> {
 // error handling
 impl<'a> Parser<'a> {
     fn add_error(&mut self, message: String, range: Range) {
-        self.errors.push(Error { range, message });
+        self.errors.push(Error {
+            range,
+            message,
+            severity: Severity::Error,
+            tags: vec![],
+        });
     }
 
     fn recover_to_next_definition(&mut self) {
@@ -844,6 +891,33 @@ impl<'a> Parser<'a> {
     }
 }
 
+/// Returns the comment node of a comment token, whose range may span several lines.
+fn comment_node(token: &Token) -> CommentNode {
+    // line comments end with the line break
+    let text = token
+        .kind
+        .to_string()
+        .trim_end_matches(['\r', '\n'])
+        .to_string();
+    let mut end = token.position;
+    for c in text.chars() {
+        if c == '\n' {
+            end.line += 1;
+            end.column = 1;
+        } else {
+            end.column += 1;
+        }
+    }
+
+    CommentNode {
+        range: Range {
+            start: token.position,
+            end,
+        },
+        text,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::{fs, path::Path};
@@ -1,5 +1,5 @@
 use crate::analyzer::{
-    base::{Error, Position},
+    base::{Error, Position, Severity},
     token::{Token, TokenKind},
 };
 
@@ -17,11 +17,11 @@ pub struct ScannerState {
     column: usize, // current column offset
 }
 
-impl Into<Position> for ScannerState {
-    fn into(self) -> Position {
+impl From<ScannerState> for Position {
+    fn from(state: ScannerState) -> Self {
         Position {
-            line: self.line as u32,
-            column: self.column as u32,
+            line: state.line as u32,
+            column: state.column as u32,
         }
     }
 }
@@ -115,6 +115,8 @@ impl<'a> Scanner<'a> {
                         err = Some(Error {
                             range: tk.range(),
                             message: format!("Unclosed block comment: {}", value),
+                            severity: Severity::Error,
+                            tags: vec![],
                         });
                         token = Some(tk);
                     }
@@ -184,6 +186,8 @@ impl<'a> Scanner<'a> {
                         err = Some(Error {
                             range: tk.range(),
                             message: format!("Unclosed string: {}", value),
+                            severity: Severity::Error,
+                            tags: vec![],
                         });
                         token = Some(tk);
                     }
@@ -288,7 +292,7 @@ impl<'a> Scanner<'a> {
     /// Skips to the next line.
     pub fn skip_to_next_line(&mut self) {
         while self.state.offset < self.input.len() {
-            let ch = self.input[self.state.offset] as char;
+            let ch = self.input[self.state.offset];
             self.state.offset += 1;
 
             if ch == '\n' {
@@ -296,9 +300,7 @@ impl<'a> Scanner<'a> {
                 self.state.column = 1;
                 break;
             } else if ch == '\r' {
-                if self.state.offset < self.input.len()
-                    && self.input[self.state.offset] as char == '\n'
-                {
+                if self.state.offset < self.input.len() && self.input[self.state.offset] == '\n' {
                     self.state.offset += 1;
                 }
                 self.state.line += 1;
@@ -367,7 +369,7 @@ impl<'a> Scanner<'a> {
                 column_offset = 1;
             } else if ch == '\r' {
                 if self.state.offset + offset < self.input.len()
-                    && self.input[self.state.offset + offset] as char == '\n'
+                    && self.input[self.state.offset + offset] == '\n'
                 {
                     offset += 1;
                 }
@@ -485,7 +487,7 @@ impl<'a> Scanner<'a> {
         let mut has_digit = false;
         for i in 0..offset {
             let ch = self.input[self.state.offset + i];
-            if ch >= '0' && ch <= '9' {
+            if ch.is_ascii_digit() {
                 has_digit = true;
                 break;
             }
@@ -538,7 +540,7 @@ impl<'a> Scanner<'a> {
                 column_offset = 1;
             } else if ch == '\r' {
                 if self.state.offset + offset < self.input.len()
-                    && self.input[self.state.offset + offset] as char == '\n'
+                    && self.input[self.state.offset + offset] == '\n'
                 {
                     offset += 1;
                 }
@@ -588,7 +590,7 @@ impl<'a> Scanner<'a> {
                 break;
             } else if ch == '\r' {
                 if self.state.offset + offset < self.input.len()
-                    && self.input[self.state.offset + offset] as char == '\n'
+                    && self.input[self.state.offset + offset] == '\n'
                 {
                     offset += 1;
                 }
@@ -1,10 +1,42 @@
 use std::{cell::RefCell, collections::HashMap, path::Path, rc::Rc};
 
 use crate::analyzer::{
-    ast::{DefinitionNode, DocumentNode, FieldTypeNode, HeaderNode, IdentifierNode, Node},
-    base::Error,
+    ast::{
+        ConstValueNode, DefinitionNode, DocumentNode, EnumValueNode, FieldNode, FieldTypeNode,
+        HeaderNode, IdentifierNode, Node,
+    },
+    base::{Error, Severity},
 };
 
+/// Definition of a constant value: a const, or a member of an enum.
+#[derive(Debug, Clone)]
+pub struct ValueDefinition {
+    /// Path of the file defining the value.
+    pub path: String,
+    /// The const definition, or the enum the member belongs to.
+    pub definition: Rc<DefinitionNode>,
+    /// Index of the enum member, if the value is an enum member.
+    pub member: Option<usize>,
+}
+
+impl ValueDefinition {
+    /// Get the enum member, if the value is an enum member.
+    pub fn enum_value(&self) -> Option<&EnumValueNode> {
+        match self.definition.as_ref() {
+            DefinitionNode::Enum(enum_node) => enum_node.values.get(self.member?),
+            _ => None,
+        }
+    }
+
+    /// Get the identifier of the const or the enum member.
+    pub fn identifier(&self) -> &IdentifierNode {
+        match self.enum_value() {
+            Some(enum_value) => &enum_value.identifier,
+            None => self.definition.identifier(),
+        }
+    }
+}
+
 /// Symbol table for a single file.
 #[derive(Debug)]
 pub struct SymbolTable {
@@ -16,6 +48,12 @@ pub struct SymbolTable {
     errors: RefCell<Vec<Error>>,
 }
 
+impl Default for SymbolTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl SymbolTable {
     /// Create a new empty symbol table.
     pub fn new() -> Self {
@@ -81,20 +119,24 @@ impl SymbolTable {
             match definition.as_ref() {
                 DefinitionNode::Const(const_def) => {
                     self.check_field_type(&const_def.field_type);
+                    self.check_const_value(&const_def.value);
+                }
+                DefinitionNode::Typedef(typedef_def) => {
+                    self.check_field_type(&typedef_def.definition_type);
                 }
                 DefinitionNode::Struct(struct_def) => {
                     for field in &struct_def.fields {
-                        self.check_field_type(&field.field_type);
+                        self.check_field(field);
                     }
                 }
                 DefinitionNode::Union(union_def) => {
                     for field in &union_def.fields {
-                        self.check_field_type(&field.field_type);
+                        self.check_field(field);
                     }
                 }
                 DefinitionNode::Exception(exception_def) => {
                     for field in &exception_def.fields {
-                        self.check_field_type(&field.field_type);
+                        self.check_field(field);
                     }
                 }
                 DefinitionNode::Service(service_def) => {
@@ -108,7 +150,7 @@ impl SymbolTable {
                         }
 
                         for field in &function.fields {
-                            self.check_field_type(&field.field_type);
+                            self.check_field(field);
                         }
 
                         if let Some(throws) = &function.throws {
@@ -146,6 +188,58 @@ impl SymbolTable {
             None,
         ))
     }
+
+    /// Find a definition of an identifier used as a constant value,
+    /// e.g. `MY_CONST`, `Numberz.FIVE` or `shared.Numberz.FIVE`.
+    pub fn find_definition_of_const_value(
+        &self,
+        identifier: &IdentifierNode,
+    ) -> Option<(ValueDefinition, Option<Rc<HeaderNode>>)> {
+        let (namespace, name) = identifier.split_by_first_dot();
+        let namespace = match namespace {
+            Some(namespace) => namespace,
+            None => {
+                // a const in current symbol table
+                let definition = self.types.get(&identifier.name)?;
+                if !matches!(definition.as_ref(), DefinitionNode::Const(_)) {
+                    return None;
+                }
+
+                let value = ValueDefinition {
+                    path: self.path.clone(),
+                    definition: definition.clone(),
+                    member: None,
+                };
+                return Some((value, None));
+            }
+        };
+
+        // look up in included files
+        if let Some(included_table) = self.includes.get(&namespace.name) {
+            if let Some((value, _)) = included_table.find_definition_of_const_value(&name) {
+                let header = self.include_nodes.get(&namespace.name)?.clone();
+                return Some((value, Some(header)));
+            }
+        }
+
+        // an enum member in current symbol table
+        let definition = self.types.get(&namespace.name)?;
+        let enum_node = match definition.as_ref() {
+            DefinitionNode::Enum(enum_node) => enum_node,
+            _ => return None,
+        };
+        let member = enum_node
+            .values
+            .iter()
+            .position(|value| value.identifier.name == name.name)?;
+
+        let value = ValueDefinition {
+            path: self.path.clone(),
+            definition: definition.clone(),
+            member: Some(member),
+        };
+        Some((value, None))
+    }
 }
 
 impl SymbolTable {
@@ -154,6 +248,8 @@ impl SymbolTable {
             self.errors.borrow_mut().push(Error {
                 range: definition.range(),
                 message: format!("Duplicate definition: {}", definition.name()),
+                severity: Severity::Error,
+                tags: vec![],
             });
             return;
         }
@@ -171,14 +267,34 @@ impl SymbolTable {
                 // base types are always valid
             }
             FieldTypeNode::MapType(map_type) => {
-                self.check_field_type(&*map_type.key_type);
-                self.check_field_type(&*map_type.value_type);
+                self.check_field_type(&map_type.key_type);
+                self.check_field_type(&map_type.value_type);
             }
             FieldTypeNode::SetType(set_type) => {
-                self.check_field_type(&*set_type.type_node);
+                self.check_field_type(&set_type.type_node);
             }
             FieldTypeNode::ListType(list_type) => {
-                self.check_field_type(&*list_type.type_node);
+                self.check_field_type(&list_type.type_node);
+            }
+        }
+    }
+
+    fn check_field(&self, field: &FieldNode) {
+        self.check_field_type(&field.field_type);
+        if let Some(default_value) = &field.default_value {
+            self.check_const_value(default_value);
+        }
+    }
+
+    fn check_const_value(&self, value: &ConstValueNode) {
+        for identifier in value.identifiers() {
+            if self.find_definition_of_const_value(identifier).is_none() {
+                self.errors.borrow_mut().push(Error {
+                    range: identifier.range(),
+                    message: format!("Undefined constant: {}", identifier.name),
+                    severity: Severity::Error,
+                    tags: vec![],
+                });
             }
         }
     }
@@ -189,6 +305,8 @@ impl SymbolTable {
             self.errors.borrow_mut().push(Error {
                 range: identifier.range(),
                 message: format!("Undefined type: {}", identifier.name),
+                severity: Severity::Error,
+                tags: vec![],
             });
         }
     }
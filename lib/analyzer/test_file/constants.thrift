@@ -0,0 +1,11 @@
+include "tutorial.thrift"
+
+const i32 DEFAULT_NUM = tutorial.INT32CONSTANT
+const tutorial.Operation DEFAULT_OP = tutorial.Operation.ADD
+
+struct Defaults {
+  1: i32 num = DEFAULT_NUM,
+  2: tutorial.Operation op = tutorial.Operation.MULTIPLY,
+  3: list<i32> nums = [DEFAULT_NUM, UNKNOWN],
+  4: bool flag = true,
+}
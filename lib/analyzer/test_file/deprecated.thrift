@@ -0,0 +1,20 @@
+enum Status {
+  ACTIVE = 1,
+  RETIRED = 2 (deprecated = "use ACTIVE"),
+}
+
+struct LegacyUser {
+  1: string name,
+} (deprecated = "use User")
+
+struct User {
+  1: string name,
+  2: Status status = Status.RETIRED,
+  3: LegacyUser legacy,
+  4: i32 age (deprecated = ""),
+}
+
+service UserService {
+  User get(1: string name),
+  LegacyUser getLegacy(1: string name) (deprecated = "use get"),
+}
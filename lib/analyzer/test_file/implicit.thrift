@@ -0,0 +1,19 @@
+enum Color {
+  RED,
+  GREEN = 5,
+  BLUE
+}
+
+struct Point {
+  i32 x,
+  2: i32 y,
+  i32 z
+}
+
+exception PlotError {
+  string message
+}
+
+service Plotter {
+  void plot(Point point) throws (PlotError error)
+}
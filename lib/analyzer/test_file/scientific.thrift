@@ -0,0 +1,5 @@
+include "tutorial.thrift"
+
+service ScientificCalculator extends tutorial.Calculator {
+  double sqrt(1: double value)
+}
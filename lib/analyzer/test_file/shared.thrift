@@ -0,0 +1,14 @@
+namespace cpp shared
+namespace java shared
+
+/**
+ * A struct shared between services.
+ */
+struct SharedStruct {
+  1: i32 key
+  2: string value
+}
+
+service SharedService {
+  SharedStruct getStruct(1: i32 key)
+}
@@ -0,0 +1,35 @@
+include "shared.thrift"
+
+namespace java tutorial
+
+typedef i32 MyInteger
+
+const i32 INT32CONSTANT = 9853
+
+enum Operation {
+  ADD = 1,
+  SUBTRACT = 2,
+  MULTIPLY = 3,
+  DIVIDE = 4
+}
+
+// Structs are the basic complex data structures.
+struct Work {
+  1: i32 num1 = 0,
+  2: i32 num2,
+  3: Operation op,
+  4: optional string comment,
+}
+
+exception InvalidOperation {
+  1: i32 whatOp,
+  2: string why
+}
+
+service Calculator extends shared.SharedService {
+  void ping(),
+  i32 add(1: i32 num1, 2: i32 num2),
+  i32 calculate(1: i32 logid, 2: Work w) throws (1: InvalidOperation ouch),
+  shared.SharedStruct lookup(1: MyInteger key),
+  oneway void zip()
+}
@@ -0,0 +1,14 @@
+include "tutorial.thrift"
+
+typedef i64 Timestamp
+typedef Timestamp CreatedAt
+typedef map<string, CreatedAt> History
+typedef tutorial.Work Job
+typedef Job CurrentJob
+
+struct Task {
+  1: CreatedAt created,
+  2: History history,
+  3: CurrentJob job,
+  4: i32 retries,
+}
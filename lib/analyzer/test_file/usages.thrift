@@ -0,0 +1,12 @@
+include "tutorial.thrift"
+
+typedef list<tutorial.Work> WorkList
+
+struct Batch {
+  1: map<string, WorkList> works,
+}
+
+service BatchCalculator {
+  Batch run(1: Batch batch),
+  void ping(),
+}
@@ -17,29 +17,23 @@ impl Token {
 
     /// Returns true if the token is an invalid token.
     pub fn is_invalid(&self) -> bool {
-        match self.kind {
-            TokenKind::Invalid(_) => true,
-            TokenKind::InvalidString(_) => true,
-            _ => false,
-        }
+        matches!(
+            self.kind,
+            TokenKind::Invalid(_) | TokenKind::InvalidString(_)
+        )
     }
 
     /// Returns true if the token is a comment.
     pub fn is_comment(&self) -> bool {
-        match self.kind {
-            TokenKind::Comment(_) => true,
-            TokenKind::BlockComment(_) => true,
-            TokenKind::PoundComment(_) => true,
-            _ => false,
-        }
+        matches!(
+            self.kind,
+            TokenKind::Comment(_) | TokenKind::BlockComment(_) | TokenKind::PoundComment(_)
+        )
     }
 
     /// Returns true if the token is a separator.
     pub fn is_line_separator(&self) -> bool {
-        match self.kind {
-            TokenKind::ListSeparator(_) => true,
-            _ => false,
-        }
+        matches!(self.kind, TokenKind::ListSeparator(_))
     }
 
     /// Returns the range of the token.
@@ -54,7 +48,7 @@ impl Token {
 }
 
 /// Represents the kind of a Thrift token.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Default)]
 pub enum TokenKind {
     // keywords
     Include,    // include
@@ -113,15 +107,10 @@ pub enum TokenKind {
     InvalidString(String),
 
     // end of file
+    #[default]
     Eof,
 }
 
-impl Default for TokenKind {
-    fn default() -> Self {
-        TokenKind::Eof
-    }
-}
-
 impl Display for TokenKind {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
         match self {
@@ -173,6 +162,11 @@ impl Display for TokenKind {
 }
 
 impl TokenKind {
+    /// Returns true if the token has no length.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     /// Returns the length of the token.
     pub fn len(&self) -> usize {
         match self {
@@ -5,7 +5,7 @@
 use std::io;
 
 use js_sys::Function;
-use serde_wasm_bindgen::to_value;
+use serde_wasm_bindgen::{from_value, to_value};
 use wasm_bindgen::prelude::*;
 
 use crate::analyzer;
@@ -15,6 +15,12 @@ pub struct Analyzer {
     analyzer: analyzer::Analyzer,
 }
 
+impl Default for Analyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[wasm_bindgen]
 impl Analyzer {
     pub fn new() -> Analyzer {
@@ -31,13 +37,59 @@ impl Analyzer {
         self.analyzer.remove_document(path);
     }
 
+    pub fn index_file(&mut self, path: &str) {
+        self.analyzer.index_file(path);
+    }
+
+    pub fn is_skipped_directory(&self, name: &str) -> bool {
+        self.analyzer.is_skipped_directory(name)
+    }
+
+    pub fn set_deprecation_annotation(&mut self, key: &str) {
+        self.analyzer.set_deprecation_annotation(key);
+    }
+
     pub fn errors(&self) -> JsValue {
         let errors = self.analyzer.errors();
         to_value(errors).unwrap_or_default()
     }
 
-    pub fn semantic_tokens(&self, path: &str) -> Option<Vec<u32>> {
-        self.analyzer.semantic_tokens(path).cloned()
+    pub fn semantic_tokens(&mut self, path: &str) -> JsValue {
+        match self.analyzer.semantic_tokens(path) {
+            Some(tokens) => to_value(tokens).unwrap_or_default(),
+            None => JsValue::null(),
+        }
+    }
+
+    pub fn semantic_tokens_range(
+        &self,
+        path: &str,
+        start_line: u32,
+        start_column: u32,
+        end_line: u32,
+        end_column: u32,
+    ) -> Option<Vec<u32>> {
+        let range = analyzer::base::Range {
+            start: analyzer::base::Position {
+                line: start_line,
+                column: start_column,
+            },
+            end: analyzer::base::Position {
+                line: end_line,
+                column: end_column,
+            },
+        };
+        self.analyzer.semantic_tokens_range(path, range)
+    }
+
+    pub fn semantic_tokens_delta(&mut self, path: &str, previous_result_id: &str) -> JsValue {
+        match self
+            .analyzer
+            .semantic_tokens_delta(path, previous_result_id)
+        {
+            Some(delta) => to_value(&delta).unwrap_or_default(),
+            None => JsValue::null(),
+        }
     }
 
     pub fn semantic_token_types(&self) -> Vec<String> {
@@ -57,6 +109,291 @@ impl Analyzer {
         }
     }
 
+    pub fn type_definition(&self, path: &str, line: u32, column: u32) -> JsValue {
+        let pos = analyzer::base::Position { line, column };
+
+        match self.analyzer.type_definition(path, pos) {
+            Some(loc) => to_value(&loc).unwrap_or_default(),
+            None => JsValue::null(),
+        }
+    }
+
+    pub fn references(
+        &self,
+        path: &str,
+        line: u32,
+        column: u32,
+        include_declaration: bool,
+    ) -> JsValue {
+        let pos = analyzer::base::Position { line, column };
+        let locations = self.analyzer.references(path, pos, include_declaration);
+        to_value(&locations).unwrap_or_default()
+    }
+
+    pub fn document_highlights(&self, path: &str, line: u32, column: u32) -> JsValue {
+        let pos = analyzer::base::Position { line, column };
+        let highlights = self.analyzer.document_highlights(path, pos);
+        to_value(&highlights).unwrap_or_default()
+    }
+
+    pub fn document_symbols(&self, path: &str) -> JsValue {
+        let symbols = self.analyzer.document_symbols(path);
+        to_value(&symbols).unwrap_or_default()
+    }
+
+    pub fn folding_ranges(&self, path: &str) -> JsValue {
+        let ranges = self.analyzer.folding_ranges(path);
+        to_value(&ranges).unwrap_or_default()
+    }
+
+    pub fn formatting(&self, path: &str, tab_size: u32, insert_spaces: bool) -> JsValue {
+        let options = analyzer::base::FormattingOptions {
+            tab_size,
+            insert_spaces,
+        };
+        let edits = self.analyzer.formatting(path, &options);
+        to_value(&edits).unwrap_or_default()
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn range_formatting(
+        &self,
+        path: &str,
+        start_line: u32,
+        start_column: u32,
+        end_line: u32,
+        end_column: u32,
+        tab_size: u32,
+        insert_spaces: bool,
+    ) -> JsValue {
+        let range = analyzer::base::Range {
+            start: analyzer::base::Position {
+                line: start_line,
+                column: start_column,
+            },
+            end: analyzer::base::Position {
+                line: end_line,
+                column: end_column,
+            },
+        };
+        let options = analyzer::base::FormattingOptions {
+            tab_size,
+            insert_spaces,
+        };
+        let edits = self.analyzer.range_formatting(path, &range, &options);
+        to_value(&edits).unwrap_or_default()
+    }
+
+    pub fn on_type_formatting(
+        &self,
+        path: &str,
+        line: u32,
+        column: u32,
+        ch: &str,
+        tab_size: u32,
+        insert_spaces: bool,
+    ) -> JsValue {
+        let pos = analyzer::base::Position { line, column };
+        let options = analyzer::base::FormattingOptions {
+            tab_size,
+            insert_spaces,
+        };
+        let edits = self.analyzer.on_type_formatting(path, pos, ch, &options);
+        to_value(&edits).unwrap_or_default()
+    }
+
+    pub fn selection_ranges(&self, path: &str, lines: Vec<u32>, columns: Vec<u32>) -> JsValue {
+        let positions: Vec<analyzer::base::Position> = lines
+            .into_iter()
+            .zip(columns)
+            .map(|(line, column)| analyzer::base::Position { line, column })
+            .collect();
+        let selection_ranges = self.analyzer.selection_ranges(path, &positions);
+        to_value(&selection_ranges).unwrap_or_default()
+    }
+
+    pub fn code_lenses(&self, path: &str) -> JsValue {
+        let code_lenses = self.analyzer.code_lenses(path);
+        to_value(&code_lenses).unwrap_or_default()
+    }
+
+    pub fn resolve_code_lens(&self, path: &str, code_lens: JsValue) -> JsValue {
+        match from_value(code_lens) {
+            Ok(code_lens) => {
+                let code_lens = self.analyzer.resolve_code_lens(path, code_lens);
+                to_value(&code_lens).unwrap_or_default()
+            }
+            Err(_) => JsValue::null(),
+        }
+    }
+
+    pub fn inlay_hints(
+        &self,
+        path: &str,
+        start_line: u32,
+        start_column: u32,
+        end_line: u32,
+        end_column: u32,
+    ) -> JsValue {
+        let range = analyzer::base::Range {
+            start: analyzer::base::Position {
+                line: start_line,
+                column: start_column,
+            },
+            end: analyzer::base::Position {
+                line: end_line,
+                column: end_column,
+            },
+        };
+        let hints = self.analyzer.inlay_hints(path, range);
+        to_value(&hints).unwrap_or_default()
+    }
+
+    pub fn document_links(&self, path: &str) -> JsValue {
+        let links = self.analyzer.document_links(path);
+        to_value(&links).unwrap_or_default()
+    }
+
+    pub fn prepare_type_hierarchy(&self, path: &str, line: u32, column: u32) -> JsValue {
+        let pos = analyzer::base::Position { line, column };
+        match self.analyzer.prepare_type_hierarchy(path, pos) {
+            Some(item) => to_value(&item).unwrap_or_default(),
+            None => JsValue::null(),
+        }
+    }
+
+    pub fn supertypes(&self, path: &str, name: &str) -> JsValue {
+        let items = self.analyzer.supertypes(path, name);
+        to_value(&items).unwrap_or_default()
+    }
+
+    pub fn subtypes(&self, path: &str, name: &str) -> JsValue {
+        let items = self.analyzer.subtypes(path, name);
+        to_value(&items).unwrap_or_default()
+    }
+
+    pub fn prepare_call_hierarchy(&self, path: &str, line: u32, column: u32) -> JsValue {
+        let pos = analyzer::base::Position { line, column };
+        match self.analyzer.prepare_call_hierarchy(path, pos) {
+            Some(item) => to_value(&item).unwrap_or_default(),
+            None => JsValue::null(),
+        }
+    }
+
+    pub fn incoming_calls(&self, path: &str, line: u32, column: u32) -> JsValue {
+        let pos = analyzer::base::Position { line, column };
+        let calls = self.analyzer.incoming_calls(path, pos);
+        to_value(&calls).unwrap_or_default()
+    }
+
+    pub fn outgoing_calls(&self, path: &str, line: u32, column: u32) -> JsValue {
+        let pos = analyzer::base::Position { line, column };
+        let calls = self.analyzer.outgoing_calls(path, pos);
+        to_value(&calls).unwrap_or_default()
+    }
+
+    pub fn workspace_symbols(&self, query: &str) -> JsValue {
+        let symbols = self.analyzer.workspace_symbols(query);
+        to_value(&symbols).unwrap_or_default()
+    }
+
+    pub fn prepare_rename(&self, path: &str, line: u32, column: u32) -> JsValue {
+        let pos = analyzer::base::Position { line, column };
+
+        match self.analyzer.prepare_rename(path, pos) {
+            Some(range) => to_value(&range).unwrap_or_default(),
+            None => JsValue::null(),
+        }
+    }
+
+    pub fn rename(
+        &self,
+        path: &str,
+        line: u32,
+        column: u32,
+        new_name: &str,
+    ) -> Result<JsValue, JsValue> {
+        let pos = analyzer::base::Position { line, column };
+
+        match self.analyzer.rename(path, pos, new_name) {
+            Ok(edit) => Ok(to_value(&edit).unwrap_or_default()),
+            Err(e) => Err(JsValue::from_str(&e)),
+        }
+    }
+
+    pub fn code_actions(
+        &self,
+        path: &str,
+        start_line: u32,
+        start_column: u32,
+        end_line: u32,
+        end_column: u32,
+    ) -> JsValue {
+        let range = analyzer::base::Range {
+            start: analyzer::base::Position {
+                line: start_line,
+                column: start_column,
+            },
+            end: analyzer::base::Position {
+                line: end_line,
+                column: end_column,
+            },
+        };
+        let actions = self.analyzer.code_actions(path, &range);
+        to_value(&actions).unwrap_or_default()
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn extract_typedef(
+        &self,
+        path: &str,
+        start_line: u32,
+        start_column: u32,
+        end_line: u32,
+        end_column: u32,
+        name: &str,
+    ) -> Result<JsValue, JsValue> {
+        let range = analyzer::base::Range {
+            start: analyzer::base::Position {
+                line: start_line,
+                column: start_column,
+            },
+            end: analyzer::base::Position {
+                line: end_line,
+                column: end_column,
+            },
+        };
+
+        match self.analyzer.extract_typedef(path, &range, name) {
+            Ok(edit) => Ok(to_value(&edit).unwrap_or_default()),
+            Err(e) => Err(JsValue::from_str(&e)),
+        }
+    }
+
+    pub fn move_definition(
+        &self,
+        path: &str,
+        line: u32,
+        column: u32,
+        destination: &str,
+    ) -> Result<JsValue, JsValue> {
+        let pos = analyzer::base::Position { line, column };
+
+        match self.analyzer.move_definition(path, pos, destination) {
+            Ok(edit) => Ok(to_value(&edit).unwrap_or_default()),
+            Err(e) => Err(JsValue::from_str(&e)),
+        }
+    }
+
+    pub fn hover(&self, path: &str, line: u32, column: u32) -> JsValue {
+        let pos = analyzer::base::Position { line, column };
+
+        match self.analyzer.hover(path, pos) {
+            Some(hover) => to_value(&hover).unwrap_or_default(),
+            None => JsValue::null(),
+        }
+    }
+
     pub fn types_for_completion(&self, path: &str, line: u32, column: u32) -> JsValue {
         let pos = analyzer::base::Position { line, column };
         let completions = self.analyzer.types_for_completion(path, pos);
@@ -85,8 +422,8 @@ impl Analyzer {
                 .unwrap_or_default()
                 .as_string()
                 .unwrap_or_default();
-            if error.len() > 0 {
-                return Err(io::Error::new(io::ErrorKind::Other, error));
+            if !error.is_empty() {
+                return Err(io::Error::other(error));
             }
 
             let result = content.as_string().unwrap_or_default();
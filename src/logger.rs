@@ -54,7 +54,6 @@ pub fn init() {
         // set custom logger
         if let Err(e) = log::set_boxed_logger(Box::new(CustomLogger)) {
             eprintln!("Failed to set custom logger: {}", e);
-            return;
         }
     });
 }
@@ -1,4 +1,4 @@
-use std::io;
+use std::{collections::HashMap, io};
 
 use bytes::BytesMut;
 use serde::{Deserialize, Serialize};
@@ -8,13 +8,15 @@ use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
 use thrift_analyzer::analyzer::base;
 
-// represents request message or notification message
+// represents request message or notification message, or response message to a
+// request sent to the client, which has no method
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct BaseMessage {
     pub jsonrpc: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<i32>,
+    #[serde(default)]
     pub method: String,
     pub params: Option<Value>,
 }
@@ -139,15 +141,43 @@ pub struct Diagnostic {
     pub severity: Option<u32>,
     pub source: Option<String>,
     pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<DiagnosticTag>>,
 }
 
 impl From<base::Error> for Diagnostic {
     fn from(value: base::Error) -> Self {
+        let severity = match value.severity {
+            base::Severity::Error => 1,
+            base::Severity::Warning => 2,
+        };
+        let tags = if value.tags.is_empty() {
+            None
+        } else {
+            Some(value.tags.into_iter().map(DiagnosticTag::from).collect())
+        };
+
         Diagnostic {
             range: value.range.into(),
-            severity: Some(1),
+            severity: Some(severity),
             source: Some(env!("CARGO_PKG_NAME").to_string()),
             message: value.message,
+            tags,
+        }
+    }
+}
+
+#[repr(i32)]
+#[derive(Debug, Clone, Serialize_repr, Deserialize_repr)]
+pub enum DiagnosticTag {
+    Unnecessary = 1,
+    Deprecated = 2,
+}
+
+impl From<base::DiagnosticTag> for DiagnosticTag {
+    fn from(value: base::DiagnosticTag) -> Self {
+        match value {
+            base::DiagnosticTag::Deprecated => DiagnosticTag::Deprecated,
         }
     }
 }
@@ -168,6 +198,15 @@ impl From<base::Range> for Range {
     }
 }
 
+impl From<Range> for base::Range {
+    fn from(value: Range) -> Self {
+        base::Range {
+            start: value.start.into(),
+            end: value.end.into(),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct Position {
@@ -178,25 +217,33 @@ pub struct Position {
 impl From<base::Position> for Position {
     fn from(value: base::Position) -> Self {
         Position {
-            line: value.line as u32 - 1,
-            character: value.column as u32 - 1,
+            line: value.line - 1,
+            character: value.column - 1,
         }
     }
 }
 
-impl Into<base::Position> for Position {
-    fn into(self) -> base::Position {
+impl From<Position> for base::Position {
+    fn from(value: Position) -> Self {
         base::Position {
-            line: self.line as u32 + 1,
-            column: self.character as u32 + 1,
+            line: value.line + 1,
+            column: value.character + 1,
         }
     }
 }
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SemanticTokensOptions {
     pub legend: SemanticTokensLegend,
-    pub full: Option<bool>,
+    pub full: Option<SemanticTokensFullOptions>,
+    pub range: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SemanticTokensFullOptions {
+    pub delta: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -215,9 +262,72 @@ pub struct SemanticTokensParams {
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SemanticTokens {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result_id: Option<String>,
     pub data: Vec<u32>,
 }
 
+impl From<base::SemanticTokens> for SemanticTokens {
+    fn from(value: base::SemanticTokens) -> Self {
+        SemanticTokens {
+            result_id: Some(value.result_id),
+            data: value.data,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SemanticTokensRangeParams {
+    pub text_document: TextDocumentIdentifier,
+    pub range: Range,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SemanticTokensDeltaParams {
+    pub text_document: TextDocumentIdentifier,
+    pub previous_result_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SemanticTokensDelta {
+    pub result_id: String,
+    pub edits: Vec<SemanticTokensEdit>,
+}
+
+impl From<base::SemanticTokensDelta> for SemanticTokensDelta {
+    fn from(value: base::SemanticTokensDelta) -> Self {
+        SemanticTokensDelta {
+            result_id: value.result_id,
+            edits: value
+                .edits
+                .into_iter()
+                .map(SemanticTokensEdit::from)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SemanticTokensEdit {
+    pub start: u32,
+    pub delete_count: u32,
+    pub data: Vec<u32>,
+}
+
+impl From<base::SemanticTokensEdit> for SemanticTokensEdit {
+    fn from(value: base::SemanticTokensEdit) -> Self {
+        SemanticTokensEdit {
+            start: value.start,
+            delete_count: value.delete_count,
+            data: value.data,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DefinitionParams {
@@ -225,6 +335,13 @@ pub struct DefinitionParams {
     pub position: Position,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeDefinitionParams {
+    pub text_document: TextDocumentIdentifier,
+    pub position: Position,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Location {
@@ -232,6 +349,567 @@ pub struct Location {
     pub range: Range,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReferenceParams {
+    pub text_document: TextDocumentIdentifier,
+    pub position: Position,
+    pub context: ReferenceContext,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReferenceContext {
+    pub include_declaration: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrepareRenameParams {
+    pub text_document: TextDocumentIdentifier,
+    pub position: Position,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenameParams {
+    pub text_document: TextDocumentIdentifier,
+    pub position: Position,
+    pub new_name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextEdit {
+    pub range: Range,
+    pub new_text: String,
+}
+
+impl From<base::TextEdit> for TextEdit {
+    fn from(value: base::TextEdit) -> Self {
+        TextEdit {
+            range: value.range.into(),
+            new_text: value.new_text,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceEdit {
+    pub changes: HashMap<String, Vec<TextEdit>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeActionParams {
+    pub text_document: TextDocumentIdentifier,
+    pub range: Range,
+    pub context: CodeActionContext,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeActionContext {
+    /// Kinds of the requested actions, all kinds if missing.
+    pub only: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeAction {
+    pub title: String,
+    pub kind: CodeActionKind,
+    pub diagnostics: Vec<Diagnostic>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub edit: Option<WorkspaceEdit>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum CodeActionKind {
+    #[serde(rename = "quickfix")]
+    QuickFix,
+    #[serde(rename = "refactor.extract")]
+    RefactorExtract,
+    #[serde(rename = "refactor.inline")]
+    RefactorInline,
+    #[serde(rename = "refactor.move")]
+    RefactorMove,
+    #[serde(rename = "refactor.rewrite")]
+    RefactorRewrite,
+}
+
+impl CodeActionKind {
+    /// Returns true if the kind is a given kind or one of its sub-kinds, e.g.
+    /// `refactor.extract` is in `refactor`.
+    pub fn is_in(&self, kind: &str) -> bool {
+        match serde_json::to_value(self) {
+            Ok(Value::String(value)) => value == kind || value.starts_with(&format!("{}.", kind)),
+            _ => false,
+        }
+    }
+}
+
+impl From<base::CodeActionKind> for CodeActionKind {
+    fn from(value: base::CodeActionKind) -> Self {
+        match value {
+            base::CodeActionKind::QuickFix => CodeActionKind::QuickFix,
+            base::CodeActionKind::RefactorExtract => CodeActionKind::RefactorExtract,
+            base::CodeActionKind::RefactorInline => CodeActionKind::RefactorInline,
+            base::CodeActionKind::RefactorMove => CodeActionKind::RefactorMove,
+            base::CodeActionKind::RefactorRewrite => CodeActionKind::RefactorRewrite,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecuteCommandParams {
+    pub command: String,
+    pub arguments: Option<Vec<Value>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApplyWorkspaceEditParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    pub edit: WorkspaceEdit,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentSymbolParams {
+    pub text_document: TextDocumentIdentifier,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentSymbol {
+    pub name: String,
+    pub detail: Option<String>,
+    pub kind: SymbolKind,
+    pub range: Range,
+    pub selection_range: Range,
+    pub children: Vec<DocumentSymbol>,
+}
+
+impl From<base::DocumentSymbol> for DocumentSymbol {
+    fn from(value: base::DocumentSymbol) -> Self {
+        DocumentSymbol {
+            name: value.name,
+            detail: value.detail,
+            kind: value.kind.into(),
+            range: value.range.into(),
+            selection_range: value.selection_range.into(),
+            children: value
+                .children
+                .into_iter()
+                .map(DocumentSymbol::from)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceSymbolParams {
+    pub query: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SymbolInformation {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub location: Location,
+    pub container_name: Option<String>,
+}
+
+#[repr(i32)]
+#[derive(Debug, Serialize_repr, Deserialize_repr)]
+pub enum SymbolKind {
+    File = 1,
+    Module = 2,
+    Namespace = 3,
+    Package = 4,
+    Class = 5,
+    Method = 6,
+    Property = 7,
+    Field = 8,
+    Constructor = 9,
+    Enum = 10,
+    Interface = 11,
+    Function = 12,
+    Variable = 13,
+    Constant = 14,
+    String = 15,
+    Number = 16,
+    Boolean = 17,
+    Array = 18,
+    Object = 19,
+    Key = 20,
+    Null = 21,
+    EnumMember = 22,
+    Struct = 23,
+    Event = 24,
+    Operator = 25,
+    TypeParameter = 26,
+}
+
+impl From<base::SymbolKind> for SymbolKind {
+    fn from(value: base::SymbolKind) -> Self {
+        match value {
+            base::SymbolKind::File => SymbolKind::File,
+            base::SymbolKind::Module => SymbolKind::Module,
+            base::SymbolKind::Namespace => SymbolKind::Namespace,
+            base::SymbolKind::Class => SymbolKind::Class,
+            base::SymbolKind::Method => SymbolKind::Method,
+            base::SymbolKind::Field => SymbolKind::Field,
+            base::SymbolKind::Enum => SymbolKind::Enum,
+            base::SymbolKind::Interface => SymbolKind::Interface,
+            base::SymbolKind::Constant => SymbolKind::Constant,
+            base::SymbolKind::EnumMember => SymbolKind::EnumMember,
+            base::SymbolKind::Struct => SymbolKind::Struct,
+            base::SymbolKind::TypeParameter => SymbolKind::TypeParameter,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentLinkParams {
+    pub text_document: TextDocumentIdentifier,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentLink {
+    pub range: Range,
+    pub target: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeHierarchyPrepareParams {
+    pub text_document: TextDocumentIdentifier,
+    pub position: Position,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeHierarchySupertypesParams {
+    pub item: TypeHierarchyItem,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeHierarchySubtypesParams {
+    pub item: TypeHierarchyItem,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeHierarchyItem {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub detail: Option<String>,
+    pub uri: String,
+    pub range: Range,
+    pub selection_range: Range,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallHierarchyPrepareParams {
+    pub text_document: TextDocumentIdentifier,
+    pub position: Position,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallHierarchyIncomingCallsParams {
+    pub item: CallHierarchyItem,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallHierarchyOutgoingCallsParams {
+    pub item: CallHierarchyItem,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallHierarchyItem {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub detail: Option<String>,
+    pub uri: String,
+    pub range: Range,
+    pub selection_range: Range,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallHierarchyIncomingCall {
+    pub from: CallHierarchyItem,
+    pub from_ranges: Vec<Range>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallHierarchyOutgoingCall {
+    pub to: CallHierarchyItem,
+    pub from_ranges: Vec<Range>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentHighlightParams {
+    pub text_document: TextDocumentIdentifier,
+    pub position: Position,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentHighlight {
+    pub range: Range,
+    pub kind: DocumentHighlightKind,
+}
+
+impl From<base::DocumentHighlight> for DocumentHighlight {
+    fn from(value: base::DocumentHighlight) -> Self {
+        DocumentHighlight {
+            range: value.range.into(),
+            kind: value.kind.into(),
+        }
+    }
+}
+
+#[repr(i32)]
+#[derive(Debug, Serialize_repr, Deserialize_repr)]
+pub enum DocumentHighlightKind {
+    Text = 1,
+    Read = 2,
+    Write = 3,
+}
+
+impl From<base::DocumentHighlightKind> for DocumentHighlightKind {
+    fn from(value: base::DocumentHighlightKind) -> Self {
+        match value {
+            base::DocumentHighlightKind::Text => DocumentHighlightKind::Text,
+            base::DocumentHighlightKind::Read => DocumentHighlightKind::Read,
+            base::DocumentHighlightKind::Write => DocumentHighlightKind::Write,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentFormattingParams {
+    pub text_document: TextDocumentIdentifier,
+    pub options: FormattingOptions,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentRangeFormattingParams {
+    pub text_document: TextDocumentIdentifier,
+    pub range: Range,
+    pub options: FormattingOptions,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentOnTypeFormattingParams {
+    pub text_document: TextDocumentIdentifier,
+    pub position: Position,
+    pub ch: String,
+    pub options: FormattingOptions,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormattingOptions {
+    pub tab_size: u32,
+    pub insert_spaces: bool,
+}
+
+impl From<FormattingOptions> for base::FormattingOptions {
+    fn from(value: FormattingOptions) -> Self {
+        base::FormattingOptions {
+            tab_size: value.tab_size,
+            insert_spaces: value.insert_spaces,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FoldingRangeParams {
+    pub text_document: TextDocumentIdentifier,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FoldingRange {
+    /// Zero-based line number.
+    pub start_line: u32,
+    /// Zero-based line number.
+    pub end_line: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<FoldingRangeKind>,
+}
+
+impl From<base::FoldingRange> for FoldingRange {
+    fn from(value: base::FoldingRange) -> Self {
+        FoldingRange {
+            start_line: value.start_line - 1,
+            end_line: value.end_line - 1,
+            kind: value.kind.map(FoldingRangeKind::from),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FoldingRangeKind {
+    Comment,
+    Imports,
+    Region,
+}
+
+impl From<base::FoldingRangeKind> for FoldingRangeKind {
+    fn from(value: base::FoldingRangeKind) -> Self {
+        match value {
+            base::FoldingRangeKind::Comment => FoldingRangeKind::Comment,
+            base::FoldingRangeKind::Imports => FoldingRangeKind::Imports,
+            base::FoldingRangeKind::Region => FoldingRangeKind::Region,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SelectionRangeParams {
+    pub text_document: TextDocumentIdentifier,
+    pub positions: Vec<Position>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SelectionRange {
+    pub range: Range,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<Box<SelectionRange>>,
+}
+
+impl From<base::SelectionRange> for SelectionRange {
+    fn from(value: base::SelectionRange) -> Self {
+        SelectionRange {
+            range: value.range.into(),
+            parent: value
+                .parent
+                .map(|parent| Box::new(SelectionRange::from(*parent))),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeLensParams {
+    pub text_document: TextDocumentIdentifier,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeLens {
+    pub range: Range,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<Command>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+// data kept in a code lens until it is resolved
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeLensData {
+    pub uri: String,
+    pub kind: base::CodeLensKind,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Command {
+    pub title: String,
+    pub command: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arguments: Option<Vec<Value>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InlayHintParams {
+    pub text_document: TextDocumentIdentifier,
+    pub range: Range,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InlayHint {
+    pub position: Position,
+    pub label: String,
+    pub padding_left: bool,
+    pub padding_right: bool,
+}
+
+impl From<base::InlayHint> for InlayHint {
+    fn from(value: base::InlayHint) -> Self {
+        InlayHint {
+            position: value.position.into(),
+            label: value.label,
+            padding_left: value.padding_left,
+            padding_right: value.padding_right,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HoverParams {
+    pub text_document: TextDocumentIdentifier,
+    pub position: Position,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Hover {
+    pub contents: MarkupContent,
+    pub range: Option<Range>,
+}
+
+impl From<base::Hover> for Hover {
+    fn from(value: base::Hover) -> Self {
+        Hover {
+            contents: MarkupContent {
+                kind: "markdown".to_string(),
+                value: value.contents,
+            },
+            range: Some(value.range.into()),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarkupContent {
+    pub kind: String,
+    pub value: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CompletionParams {
@@ -6,22 +6,51 @@ use std::path::Path;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use url::Url;
 
-use thrift_analyzer::analyzer::Analyzer;
+use thrift_analyzer::analyzer::{base, Analyzer};
 
 use io::{MessageReader, MessageWriter};
 use lsp::{
-    BaseMessage, BaseResponse, CompletionItem, CompletionItemKind, CompletionParams,
-    DefinitionParams, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
-    DidOpenTextDocumentParams, InitializeParams, InitializeResult, Location,
-    PublishDiagnosticsParams, ResponseError, SemanticTokens, SemanticTokensLegend,
-    SemanticTokensOptions, SemanticTokensParams, ServerInfo,
+    ApplyWorkspaceEditParams, BaseMessage, BaseResponse, CallHierarchyIncomingCall,
+    CallHierarchyIncomingCallsParams, CallHierarchyItem, CallHierarchyOutgoingCall,
+    CallHierarchyOutgoingCallsParams, CallHierarchyPrepareParams, CodeAction, CodeActionParams,
+    CodeLens, CodeLensData, CodeLensParams, Command, CompletionItem, CompletionItemKind,
+    CompletionParams, DefinitionParams, Diagnostic, DidChangeTextDocumentParams,
+    DidCloseTextDocumentParams, DidOpenTextDocumentParams, DocumentFormattingParams,
+    DocumentHighlight, DocumentHighlightParams, DocumentLink, DocumentLinkParams,
+    DocumentOnTypeFormattingParams, DocumentRangeFormattingParams, DocumentSymbol,
+    DocumentSymbolParams, ExecuteCommandParams, FoldingRange, FoldingRangeParams, Hover,
+    HoverParams, InitializeParams, InitializeResult, InlayHint, InlayHintParams, Location,
+    Position, PrepareRenameParams, PublishDiagnosticsParams, Range, ReferenceParams, RenameParams,
+    ResponseError, SelectionRange, SelectionRangeParams, SemanticTokens, SemanticTokensDelta,
+    SemanticTokensDeltaParams, SemanticTokensFullOptions, SemanticTokensLegend,
+    SemanticTokensOptions, SemanticTokensParams, SemanticTokensRangeParams, ServerInfo,
+    SymbolInformation, TextEdit, TypeDefinitionParams, TypeHierarchyItem,
+    TypeHierarchyPrepareParams, TypeHierarchySubtypesParams, TypeHierarchySupertypesParams,
+    WorkspaceEdit, WorkspaceSymbolParams,
 };
 
+/// Command extracting a type into a typedef. Its arguments are the URI of the document,
+/// the range of the type and the name of the typedef, asked to the user by the client.
+const EXTRACT_TYPEDEF_COMMAND: &str = "thrift-ls.extractTypedef";
+
+/// Command moving a definition to another file. Its arguments are the URI of the
+/// document, the position of the definition name and the URI of the destination file,
+/// chosen by the user in the client.
+const MOVE_DEFINITION_COMMAND: &str = "thrift-ls.moveDefinition";
+
+/// Name of an extracted typedef when the client does not ask the user for one.
+const DEFAULT_TYPEDEF_NAME: &str = "NewType";
+
 pub struct LanguageServer<R, W> {
     reader: MessageReader<R>,
     writer: MessageWriter<W>,
     analyzer: Analyzer,
     initialized: bool,
+    hierarchical_document_symbol_support: bool,
+    // workspace folders, indexed once the client is initialized
+    workspace_roots: Vec<String>,
+    // ID of the next request sent to the client
+    next_request_id: i32,
 }
 
 impl<R: AsyncReadExt + Unpin, W: AsyncWriteExt + Unpin> LanguageServer<R, W> {
@@ -31,6 +60,9 @@ impl<R: AsyncReadExt + Unpin, W: AsyncWriteExt + Unpin> LanguageServer<R, W> {
             writer: MessageWriter::new(writer),
             analyzer: Analyzer::new(),
             initialized: false,
+            hierarchical_document_symbol_support: false,
+            workspace_roots: Vec::new(),
+            next_request_id: 1,
         }
     }
 
@@ -51,7 +83,7 @@ impl<R: AsyncReadExt + Unpin, W: AsyncWriteExt + Unpin> LanguageServer<R, W> {
                     }
                 }
                 "initialized" => {
-                    // do nothing
+                    self.index_workspace();
                 }
                 "shutdown" => {
                     if let Some(response) = self.handle_shutdown(message) {
@@ -76,12 +108,96 @@ impl<R: AsyncReadExt + Unpin, W: AsyncWriteExt + Unpin> LanguageServer<R, W> {
                 "textDocument/semanticTokens/full" => {
                     self.semantic_tokens_full(message).await;
                 }
+                "textDocument/semanticTokens/full/delta" => {
+                    self.semantic_tokens_full_delta(message).await;
+                }
+                "textDocument/semanticTokens/range" => {
+                    self.semantic_tokens_range(message).await;
+                }
                 "textDocument/definition" => {
                     self.definition(message).await;
                 }
+                "textDocument/typeDefinition" => {
+                    self.type_definition(message).await;
+                }
                 "textDocument/completion" => {
                     self.completion(message).await;
                 }
+                "textDocument/hover" => {
+                    self.hover(message).await;
+                }
+                "textDocument/references" => {
+                    self.references(message).await;
+                }
+                "textDocument/documentHighlight" => {
+                    self.document_highlight(message).await;
+                }
+                "textDocument/documentSymbol" => {
+                    self.document_symbol(message).await;
+                }
+                "textDocument/foldingRange" => {
+                    self.folding_range(message).await;
+                }
+                "textDocument/selectionRange" => {
+                    self.selection_range(message).await;
+                }
+                "textDocument/codeLens" => {
+                    self.code_lens(message).await;
+                }
+                "codeLens/resolve" => {
+                    self.code_lens_resolve(message).await;
+                }
+                "textDocument/inlayHint" => {
+                    self.inlay_hint(message).await;
+                }
+                "textDocument/documentLink" => {
+                    self.document_link(message).await;
+                }
+                "textDocument/prepareTypeHierarchy" => {
+                    self.prepare_type_hierarchy(message).await;
+                }
+                "typeHierarchy/supertypes" => {
+                    self.supertypes(message).await;
+                }
+                "typeHierarchy/subtypes" => {
+                    self.subtypes(message).await;
+                }
+                "textDocument/prepareCallHierarchy" => {
+                    self.prepare_call_hierarchy(message).await;
+                }
+                "callHierarchy/incomingCalls" => {
+                    self.incoming_calls(message).await;
+                }
+                "callHierarchy/outgoingCalls" => {
+                    self.outgoing_calls(message).await;
+                }
+                "workspace/symbol" => {
+                    self.workspace_symbol(message).await;
+                }
+                "textDocument/prepareRename" => {
+                    self.prepare_rename(message).await;
+                }
+                "textDocument/rename" => {
+                    self.rename(message).await;
+                }
+                "textDocument/codeAction" => {
+                    self.code_action(message).await;
+                }
+                "workspace/executeCommand" => {
+                    self.execute_command(message).await;
+                }
+                "" => {
+                    // a response to a request sent to the client, e.g. workspace/applyEdit
+                }
+                "textDocument/formatting" => {
+                    self.formatting(message).await;
+                }
+                "textDocument/rangeFormatting" => {
+                    self.range_formatting(message).await;
+                }
+                "textDocument/onTypeFormatting" => {
+                    self.on_type_formatting(message).await;
+                }
                 method => {
                     if method.starts_with("$/") {
                         if !message.is_notification() {
@@ -106,8 +222,17 @@ impl<R: AsyncReadExt + Unpin, W: AsyncWriteExt + Unpin> LanguageServer<R, W> {
         Ok(())
     }
 
+    /// Index the files of the workspace folders, so their symbols can be found before
+    /// they are opened. Reading every file takes a while in a large workspace, so this
+    /// waits for the `initialized` notification, after the response to `initialize`.
+    fn index_workspace(&mut self) {
+        for root in std::mem::take(&mut self.workspace_roots) {
+            self.analyzer.index_workspace(&root);
+        }
+    }
+
     fn handle_initialize(&mut self, message: BaseMessage) -> Option<BaseResponse> {
-        let _params = serde_json::from_value::<InitializeParams>(message.params?).ok()?;
+        let params = serde_json::from_value::<InitializeParams>(message.params?).ok()?;
         if self.initialized {
             return Some(BaseResponse {
                 jsonrpc: "2.0".to_string(),
@@ -122,13 +247,48 @@ impl<R: AsyncReadExt + Unpin, W: AsyncWriteExt + Unpin> LanguageServer<R, W> {
         }
 
         self.initialized = true;
+        self.hierarchical_document_symbol_support = params
+            .capabilities
+            .as_ref()
+            .and_then(|c| {
+                c.pointer("/textDocument/documentSymbol/hierarchicalDocumentSymbolSupport")
+            })
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        // workspace files are indexed after the response, see `index_workspace`
+        let mut roots: Vec<String> = params
+            .workspace_folders
+            .iter()
+            .flatten()
+            .filter_map(|folder| folder.get("uri")?.as_str())
+            .filter_map(parse_uri_to_path)
+            .collect();
+        if roots.is_empty() {
+            if let Some(root) = params.root_uri.as_deref().and_then(parse_uri_to_path) {
+                roots.push(root);
+            } else if let Some(root) = params.root_path {
+                roots.push(root);
+            }
+        }
+        self.workspace_roots = roots;
+
+        if let Some(key) = params
+            .initialization_options
+            .as_ref()
+            .and_then(|options| options.get("deprecationAnnotation"))
+            .and_then(|v| v.as_str())
+        {
+            self.analyzer.set_deprecation_annotation(key);
+        }
 
         let semantic_tokens_options = SemanticTokensOptions {
             legend: SemanticTokensLegend {
                 token_types: self.analyzer.semantic_token_types(),
                 token_modifiers: self.analyzer.semantic_token_modifiers(),
             },
-            full: Some(true),
+            full: Some(SemanticTokensFullOptions { delta: true }),
+            range: Some(true),
         };
 
         let result = InitializeResult {
@@ -136,10 +296,48 @@ impl<R: AsyncReadExt + Unpin, W: AsyncWriteExt + Unpin> LanguageServer<R, W> {
                 "textDocumentSync": 1, // Documents are synced by always sending the full content of the document.
                 "semanticTokensProvider": semantic_tokens_options,
                 "definitionProvider": true,
+                "typeDefinitionProvider": true,
                 "completionProvider": {
                     "resolveProvider": false,
                     "triggerCharacters": ["."],
                 },
+                "hoverProvider": true,
+                "referencesProvider": true,
+                "documentHighlightProvider": true,
+                "documentSymbolProvider": true,
+                "workspaceSymbolProvider": true,
+                "foldingRangeProvider": true,
+                "selectionRangeProvider": true,
+                "inlayHintProvider": true,
+                "codeLensProvider": {
+                    "resolveProvider": true,
+                },
+                "typeHierarchyProvider": true,
+                "callHierarchyProvider": true,
+                "documentLinkProvider": {
+                    "resolveProvider": false,
+                },
+                "renameProvider": {
+                    "prepareProvider": true,
+                },
+                "codeActionProvider": {
+                    "codeActionKinds": [
+                        "quickfix",
+                        "refactor.extract",
+                        "refactor.inline",
+                        "refactor.move",
+                        "refactor.rewrite",
+                    ],
+                },
+                "executeCommandProvider": {
+                    "commands": [EXTRACT_TYPEDEF_COMMAND, MOVE_DEFINITION_COMMAND],
+                },
+                "documentFormattingProvider": true,
+                "documentRangeFormattingProvider": true,
+                "documentOnTypeFormattingProvider": {
+                    "firstTriggerCharacter": "\n",
+                    "moreTriggerCharacter": ["}"],
+                },
             }),
             server_info: Some(ServerInfo {
                 name: env!("CARGO_PKG_NAME").to_string(),
@@ -268,16 +466,106 @@ impl<R: AsyncReadExt + Unpin, W: AsyncWriteExt + Unpin> LanguageServer<R, W> {
             None => return,
         };
 
+        let tokens = match self.analyzer.semantic_tokens(&path) {
+            Some(tokens) => SemanticTokens::from(tokens.clone()),
+            None => SemanticTokens {
+                result_id: None,
+                data: vec![],
+            },
+        };
+
+        let response = BaseResponse {
+            jsonrpc: "2.0".to_string(),
+            id: message.id,
+            result: serde_json::to_value(tokens).ok(),
+            error: None,
+        };
+
+        if let Err(e) = self.writer.write_message(&response).await {
+            log::error!("Failed to write response: {}", e);
+        }
+    }
+
+    pub async fn semantic_tokens_full_delta(&mut self, message: BaseMessage) {
+        let params = match message.params {
+            Some(params) => match serde_json::from_value::<SemanticTokensDeltaParams>(params) {
+                Ok(params) => params,
+                Err(e) => {
+                    log::error!("Failed to parse semantic tokens delta params: {}", e);
+                    return;
+                }
+            },
+            None => {
+                log::error!("Missing params in semantic tokens delta request");
+                return;
+            }
+        };
+
+        let path = match parse_uri_to_path(&params.text_document.uri) {
+            Some(path) => path,
+            None => return,
+        };
+
+        // all tokens if the previous result is unknown
+        let result = match self
+            .analyzer
+            .semantic_tokens_delta(&path, &params.previous_result_id)
+        {
+            Some(delta) => serde_json::to_value(SemanticTokensDelta::from(delta)),
+            None => match self.analyzer.semantic_tokens(&path) {
+                Some(tokens) => serde_json::to_value(SemanticTokens::from(tokens.clone())),
+                None => serde_json::to_value(SemanticTokens {
+                    result_id: None,
+                    data: vec![],
+                }),
+            },
+        };
+
+        let response = BaseResponse {
+            jsonrpc: "2.0".to_string(),
+            id: message.id,
+            result: result.ok(),
+            error: None,
+        };
+
+        if let Err(e) = self.writer.write_message(&response).await {
+            log::error!("Failed to write response: {}", e);
+        }
+    }
+
+    pub async fn semantic_tokens_range(&mut self, message: BaseMessage) {
+        let params = match message.params {
+            Some(params) => match serde_json::from_value::<SemanticTokensRangeParams>(params) {
+                Ok(params) => params,
+                Err(e) => {
+                    log::error!("Failed to parse semantic tokens range params: {}", e);
+                    return;
+                }
+            },
+            None => {
+                log::error!("Missing params in semantic tokens range request");
+                return;
+            }
+        };
+
+        let path = match parse_uri_to_path(&params.text_document.uri) {
+            Some(path) => path,
+            None => return,
+        };
+
         let tokens = self
             .analyzer
-            .semantic_tokens(&path)
-            .cloned()
+            .semantic_tokens_range(&path, params.range.into())
             .unwrap_or_default();
 
         let response = BaseResponse {
             jsonrpc: "2.0".to_string(),
             id: message.id,
-            result: serde_json::to_value(SemanticTokens { data: tokens }).ok(),
+            result: serde_json::to_value(SemanticTokens {
+                result_id: None,
+                data: tokens,
+            })
+            .ok(),
             error: None,
         };
 
@@ -326,6 +614,46 @@ impl<R: AsyncReadExt + Unpin, W: AsyncWriteExt + Unpin> LanguageServer<R, W> {
         }
     }
 
+    pub async fn type_definition(&mut self, message: BaseMessage) {
+        let params = match message.params {
+            Some(params) => match serde_json::from_value::<TypeDefinitionParams>(params) {
+                Ok(params) => params,
+                Err(e) => {
+                    log::error!("Failed to parse type definition params: {}", e);
+                    return;
+                }
+            },
+            None => {
+                log::error!("Missing params in type definition request");
+                return;
+            }
+        };
+
+        let path = match parse_uri_to_path(&params.text_document.uri) {
+            Some(x) => x,
+            None => return,
+        };
+
+        let location = self
+            .analyzer
+            .type_definition(&path, params.position.into())
+            .map(|location| Location {
+                uri: path_to_uri(&location.path),
+                range: location.range.into(),
+            });
+
+        let response = BaseResponse {
+            jsonrpc: "2.0".to_string(),
+            id: message.id,
+            result: serde_json::to_value(location).ok(),
+            error: None,
+        };
+
+        if let Err(e) = self.writer.write_message(&response).await {
+            log::error!("Failed to write response: {}", e);
+        }
+    }
+
     pub async fn completion(&mut self, message: BaseMessage) {
         let params = match message.params {
             Some(params) => match serde_json::from_value::<CompletionParams>(params) {
@@ -395,50 +723,1237 @@ impl<R: AsyncReadExt + Unpin, W: AsyncWriteExt + Unpin> LanguageServer<R, W> {
         }
     }
 
-    async fn sync_document(&mut self, uri: &str, content: &str) {
-        let path = match parse_uri_to_path(&uri) {
-            Some(x) => x,
+    pub async fn hover(&mut self, message: BaseMessage) {
+        let params = match message.params {
+            Some(params) => match serde_json::from_value::<HoverParams>(params) {
+                Ok(params) => params,
+                Err(e) => {
+                    log::error!("Failed to parse hover params: {}", e);
+                    return;
+                }
+            },
+            None => {
+                log::error!("Missing params in hover request");
+                return;
+            }
+        };
+
+        let path = match parse_uri_to_path(&params.text_document.uri) {
+            Some(path) => path,
             None => return,
         };
 
-        self.analyzer.sync_document(&path, content);
+        let hover = self
+            .analyzer
+            .hover(&path, params.position.into())
+            .map(Hover::from);
+
+        let response = BaseResponse {
+            jsonrpc: "2.0".to_string(),
+            id: message.id,
+            result: serde_json::to_value(hover).ok(),
+            error: None,
+        };
+
+        if let Err(e) = self.writer.write_message(&response).await {
+            log::error!("Failed to write response: {}", e);
+        }
     }
 
-    async fn remove_document(&mut self, uri: &str) {
-        let path = match parse_uri_to_path(&uri) {
-            Some(x) => x,
+    pub async fn references(&mut self, message: BaseMessage) {
+        let params = match message.params {
+            Some(params) => match serde_json::from_value::<ReferenceParams>(params) {
+                Ok(params) => params,
+                Err(e) => {
+                    log::error!("Failed to parse references params: {}", e);
+                    return;
+                }
+            },
+            None => {
+                log::error!("Missing params in references request");
+                return;
+            }
+        };
+
+        let path = match parse_uri_to_path(&params.text_document.uri) {
+            Some(path) => path,
             None => return,
         };
-        self.analyzer.remove_document(&path);
-    }
 
-    async fn publish_diagnostics(&mut self) {
-        let errors_map = self.analyzer.errors();
+        let locations: Vec<Location> = self
+            .analyzer
+            .references(
+                &path,
+                params.position.into(),
+                params.context.include_declaration,
+            )
+            .into_iter()
+            .map(|location| Location {
+                uri: path_to_uri(&location.path),
+                range: location.range.into(),
+            })
+            .collect();
 
-        for (path, errors) in errors_map.iter() {
-            let mut diagnostics_params = PublishDiagnosticsParams {
-                uri: path_to_uri(&path),
-                diagnostics: Vec::with_capacity(errors.len()),
-            };
-            for error in errors {
-                diagnostics_params.diagnostics.push(error.clone().into());
-            }
+        let response = BaseResponse {
+            jsonrpc: "2.0".to_string(),
+            id: message.id,
+            result: serde_json::to_value(locations).ok(),
+            error: None,
+        };
 
-            let message = BaseMessage {
-                jsonrpc: "2.0".to_string(),
-                id: None,
-                method: "textDocument/publishDiagnostics".to_string(),
-                params: serde_json::to_value(diagnostics_params).ok(),
-            };
-            if let Err(e) = self.writer.write_message(&message).await {
-                log::error!("Failed to write diagnostics: {}", e);
+        if let Err(e) = self.writer.write_message(&response).await {
+            log::error!("Failed to write response: {}", e);
+        }
+    }
+
+    pub async fn document_highlight(&mut self, message: BaseMessage) {
+        let params = match message.params {
+            Some(params) => match serde_json::from_value::<DocumentHighlightParams>(params) {
+                Ok(params) => params,
+                Err(e) => {
+                    log::error!("Failed to parse document highlight params: {}", e);
+                    return;
+                }
+            },
+            None => {
+                log::error!("Missing params in document highlight request");
+                return;
             }
+        };
+
+        let path = match parse_uri_to_path(&params.text_document.uri) {
+            Some(path) => path,
+            None => return,
+        };
+
+        let highlights: Vec<DocumentHighlight> = self
+            .analyzer
+            .document_highlights(&path, params.position.into())
+            .into_iter()
+            .map(DocumentHighlight::from)
+            .collect();
+
+        let response = BaseResponse {
+            jsonrpc: "2.0".to_string(),
+            id: message.id,
+            result: serde_json::to_value(highlights).ok(),
+            error: None,
+        };
+
+        if let Err(e) = self.writer.write_message(&response).await {
+            log::error!("Failed to write response: {}", e);
         }
     }
+
+    pub async fn folding_range(&mut self, message: BaseMessage) {
+        let params = match message.params {
+            Some(params) => match serde_json::from_value::<FoldingRangeParams>(params) {
+                Ok(params) => params,
+                Err(e) => {
+                    log::error!("Failed to parse folding range params: {}", e);
+                    return;
+                }
+            },
+            None => {
+                log::error!("Missing params in folding range request");
+                return;
+            }
+        };
+
+        let path = match parse_uri_to_path(&params.text_document.uri) {
+            Some(path) => path,
+            None => return,
+        };
+
+        let ranges: Vec<FoldingRange> = self
+            .analyzer
+            .folding_ranges(&path)
+            .into_iter()
+            .map(FoldingRange::from)
+            .collect();
+
+        let response = BaseResponse {
+            jsonrpc: "2.0".to_string(),
+            id: message.id,
+            result: serde_json::to_value(ranges).ok(),
+            error: None,
+        };
+
+        if let Err(e) = self.writer.write_message(&response).await {
+            log::error!("Failed to write response: {}", e);
+        }
+    }
+
+    pub async fn selection_range(&mut self, message: BaseMessage) {
+        let params = match message.params {
+            Some(params) => match serde_json::from_value::<SelectionRangeParams>(params) {
+                Ok(params) => params,
+                Err(e) => {
+                    log::error!("Failed to parse selection range params: {}", e);
+                    return;
+                }
+            },
+            None => {
+                log::error!("Missing params in selection range request");
+                return;
+            }
+        };
+
+        let path = match parse_uri_to_path(&params.text_document.uri) {
+            Some(path) => path,
+            None => return,
+        };
+
+        let positions: Vec<base::Position> = params
+            .positions
+            .into_iter()
+            .map(base::Position::from)
+            .collect();
+        let selection_ranges: Vec<SelectionRange> = self
+            .analyzer
+            .selection_ranges(&path, &positions)
+            .into_iter()
+            .map(SelectionRange::from)
+            .collect();
+
+        let response = BaseResponse {
+            jsonrpc: "2.0".to_string(),
+            id: message.id,
+            result: serde_json::to_value(selection_ranges).ok(),
+            error: None,
+        };
+
+        if let Err(e) = self.writer.write_message(&response).await {
+            log::error!("Failed to write response: {}", e);
+        }
+    }
+
+    pub async fn code_lens(&mut self, message: BaseMessage) {
+        let params = match message.params {
+            Some(params) => match serde_json::from_value::<CodeLensParams>(params) {
+                Ok(params) => params,
+                Err(e) => {
+                    log::error!("Failed to parse code lens params: {}", e);
+                    return;
+                }
+            },
+            None => {
+                log::error!("Missing params in code lens request");
+                return;
+            }
+        };
+
+        let path = match parse_uri_to_path(&params.text_document.uri) {
+            Some(path) => path,
+            None => return,
+        };
+
+        // titles are computed in codeLens/resolve
+        let code_lenses: Vec<CodeLens> = self
+            .analyzer
+            .code_lenses(&path)
+            .into_iter()
+            .map(|code_lens| CodeLens {
+                range: code_lens.range.into(),
+                command: None,
+                data: serde_json::to_value(CodeLensData {
+                    uri: params.text_document.uri.clone(),
+                    kind: code_lens.kind,
+                })
+                .ok(),
+            })
+            .collect();
+
+        let response = BaseResponse {
+            jsonrpc: "2.0".to_string(),
+            id: message.id,
+            result: serde_json::to_value(code_lenses).ok(),
+            error: None,
+        };
+
+        if let Err(e) = self.writer.write_message(&response).await {
+            log::error!("Failed to write response: {}", e);
+        }
+    }
+
+    pub async fn code_lens_resolve(&mut self, message: BaseMessage) {
+        let params = match message.params {
+            Some(params) => match serde_json::from_value::<CodeLens>(params) {
+                Ok(params) => params,
+                Err(e) => {
+                    log::error!("Failed to parse code lens resolve params: {}", e);
+                    return;
+                }
+            },
+            None => {
+                log::error!("Missing params in code lens resolve request");
+                return;
+            }
+        };
+
+        let data = match params
+            .data
+            .and_then(|data| serde_json::from_value::<CodeLensData>(data).ok())
+        {
+            Some(data) => data,
+            None => {
+                log::error!("Missing data in code lens resolve request");
+                return;
+            }
+        };
+
+        let path = match parse_uri_to_path(&data.uri) {
+            Some(path) => path,
+            None => return,
+        };
+
+        let code_lens = self.analyzer.resolve_code_lens(
+            &path,
+            base::CodeLens {
+                range: params.range.into(),
+                kind: data.kind,
+                title: None,
+                locations: vec![],
+            },
+        );
+        let locations: Vec<Location> = code_lens
+            .locations
+            .into_iter()
+            .map(|location| Location {
+                uri: path_to_uri(&location.path),
+                range: location.range.into(),
+            })
+            .collect();
+        let position: Position = code_lens.range.start.into();
+        let code_lens = CodeLens {
+            range: code_lens.range.into(),
+            command: code_lens.title.map(|title| Command {
+                title,
+                // registered by the VS Code extension, opens the references peek
+                command: "thrift-ls.showReferences".to_string(),
+                arguments: Some(vec![
+                    serde_json::json!(data.uri),
+                    serde_json::json!(position),
+                    serde_json::json!(locations),
+                ]),
+            }),
+            data: None,
+        };
+
+        let response = BaseResponse {
+            jsonrpc: "2.0".to_string(),
+            id: message.id,
+            result: serde_json::to_value(code_lens).ok(),
+            error: None,
+        };
+
+        if let Err(e) = self.writer.write_message(&response).await {
+            log::error!("Failed to write response: {}", e);
+        }
+    }
+
+    pub async fn inlay_hint(&mut self, message: BaseMessage) {
+        let params = match message.params {
+            Some(params) => match serde_json::from_value::<InlayHintParams>(params) {
+                Ok(params) => params,
+                Err(e) => {
+                    log::error!("Failed to parse inlay hint params: {}", e);
+                    return;
+                }
+            },
+            None => {
+                log::error!("Missing params in inlay hint request");
+                return;
+            }
+        };
+
+        let path = match parse_uri_to_path(&params.text_document.uri) {
+            Some(path) => path,
+            None => return,
+        };
+
+        let hints: Vec<InlayHint> = self
+            .analyzer
+            .inlay_hints(&path, params.range.into())
+            .into_iter()
+            .map(InlayHint::from)
+            .collect();
+
+        let response = BaseResponse {
+            jsonrpc: "2.0".to_string(),
+            id: message.id,
+            result: serde_json::to_value(hints).ok(),
+            error: None,
+        };
+
+        if let Err(e) = self.writer.write_message(&response).await {
+            log::error!("Failed to write response: {}", e);
+        }
+    }
+
+    pub async fn document_symbol(&mut self, message: BaseMessage) {
+        let params = match message.params {
+            Some(params) => match serde_json::from_value::<DocumentSymbolParams>(params) {
+                Ok(params) => params,
+                Err(e) => {
+                    log::error!("Failed to parse document symbol params: {}", e);
+                    return;
+                }
+            },
+            None => {
+                log::error!("Missing params in document symbol request");
+                return;
+            }
+        };
+
+        let path = match parse_uri_to_path(&params.text_document.uri) {
+            Some(path) => path,
+            None => return,
+        };
+
+        let symbols = self.analyzer.document_symbols(&path);
+        let result = if self.hierarchical_document_symbol_support {
+            let symbols: Vec<DocumentSymbol> =
+                symbols.into_iter().map(DocumentSymbol::from).collect();
+            serde_json::to_value(symbols).ok()
+        } else {
+            let mut information = Vec::new();
+            flatten_document_symbols(&params.text_document.uri, symbols, None, &mut information);
+            serde_json::to_value(information).ok()
+        };
+
+        let response = BaseResponse {
+            jsonrpc: "2.0".to_string(),
+            id: message.id,
+            result,
+            error: None,
+        };
+
+        if let Err(e) = self.writer.write_message(&response).await {
+            log::error!("Failed to write response: {}", e);
+        }
+    }
+
+    pub async fn document_link(&mut self, message: BaseMessage) {
+        let params = match message.params {
+            Some(params) => match serde_json::from_value::<DocumentLinkParams>(params) {
+                Ok(params) => params,
+                Err(e) => {
+                    log::error!("Failed to parse document link params: {}", e);
+                    return;
+                }
+            },
+            None => {
+                log::error!("Missing params in document link request");
+                return;
+            }
+        };
+
+        let path = match parse_uri_to_path(&params.text_document.uri) {
+            Some(path) => path,
+            None => return,
+        };
+
+        let links: Vec<DocumentLink> = self
+            .analyzer
+            .document_links(&path)
+            .into_iter()
+            .map(|link| DocumentLink {
+                range: link.range.into(),
+                target: Some(path_to_uri(&link.path)),
+            })
+            .collect();
+
+        let response = BaseResponse {
+            jsonrpc: "2.0".to_string(),
+            id: message.id,
+            result: serde_json::to_value(links).ok(),
+            error: None,
+        };
+
+        if let Err(e) = self.writer.write_message(&response).await {
+            log::error!("Failed to write response: {}", e);
+        }
+    }
+
+    pub async fn prepare_type_hierarchy(&mut self, message: BaseMessage) {
+        let params = match message.params {
+            Some(params) => match serde_json::from_value::<TypeHierarchyPrepareParams>(params) {
+                Ok(params) => params,
+                Err(e) => {
+                    log::error!("Failed to parse prepare type hierarchy params: {}", e);
+                    return;
+                }
+            },
+            None => {
+                log::error!("Missing params in prepare type hierarchy request");
+                return;
+            }
+        };
+
+        let path = match parse_uri_to_path(&params.text_document.uri) {
+            Some(path) => path,
+            None => return,
+        };
+
+        let items: Option<Vec<TypeHierarchyItem>> = self
+            .analyzer
+            .prepare_type_hierarchy(&path, params.position.into())
+            .map(|item| vec![to_lsp_type_hierarchy_item(item)]);
+
+        let response = BaseResponse {
+            jsonrpc: "2.0".to_string(),
+            id: message.id,
+            result: serde_json::to_value(items).ok(),
+            error: None,
+        };
+
+        if let Err(e) = self.writer.write_message(&response).await {
+            log::error!("Failed to write response: {}", e);
+        }
+    }
+
+    pub async fn supertypes(&mut self, message: BaseMessage) {
+        let params = match message.params {
+            Some(params) => match serde_json::from_value::<TypeHierarchySupertypesParams>(params) {
+                Ok(params) => params,
+                Err(e) => {
+                    log::error!("Failed to parse supertypes params: {}", e);
+                    return;
+                }
+            },
+            None => {
+                log::error!("Missing params in supertypes request");
+                return;
+            }
+        };
+
+        let path = match parse_uri_to_path(&params.item.uri) {
+            Some(path) => path,
+            None => return,
+        };
+
+        let items: Vec<TypeHierarchyItem> = self
+            .analyzer
+            .supertypes(&path, &params.item.name)
+            .into_iter()
+            .map(to_lsp_type_hierarchy_item)
+            .collect();
+
+        let response = BaseResponse {
+            jsonrpc: "2.0".to_string(),
+            id: message.id,
+            result: serde_json::to_value(items).ok(),
+            error: None,
+        };
+
+        if let Err(e) = self.writer.write_message(&response).await {
+            log::error!("Failed to write response: {}", e);
+        }
+    }
+
+    pub async fn subtypes(&mut self, message: BaseMessage) {
+        let params = match message.params {
+            Some(params) => match serde_json::from_value::<TypeHierarchySubtypesParams>(params) {
+                Ok(params) => params,
+                Err(e) => {
+                    log::error!("Failed to parse subtypes params: {}", e);
+                    return;
+                }
+            },
+            None => {
+                log::error!("Missing params in subtypes request");
+                return;
+            }
+        };
+
+        let path = match parse_uri_to_path(&params.item.uri) {
+            Some(path) => path,
+            None => return,
+        };
+
+        let items: Vec<TypeHierarchyItem> = self
+            .analyzer
+            .subtypes(&path, &params.item.name)
+            .into_iter()
+            .map(to_lsp_type_hierarchy_item)
+            .collect();
+
+        let response = BaseResponse {
+            jsonrpc: "2.0".to_string(),
+            id: message.id,
+            result: serde_json::to_value(items).ok(),
+            error: None,
+        };
+
+        if let Err(e) = self.writer.write_message(&response).await {
+            log::error!("Failed to write response: {}", e);
+        }
+    }
+
+    pub async fn prepare_call_hierarchy(&mut self, message: BaseMessage) {
+        let params = match message.params {
+            Some(params) => match serde_json::from_value::<CallHierarchyPrepareParams>(params) {
+                Ok(params) => params,
+                Err(e) => {
+                    log::error!("Failed to parse prepare call hierarchy params: {}", e);
+                    return;
+                }
+            },
+            None => {
+                log::error!("Missing params in prepare call hierarchy request");
+                return;
+            }
+        };
+
+        let path = match parse_uri_to_path(&params.text_document.uri) {
+            Some(path) => path,
+            None => return,
+        };
+
+        let items: Option<Vec<CallHierarchyItem>> = self
+            .analyzer
+            .prepare_call_hierarchy(&path, params.position.into())
+            .map(|item| vec![to_lsp_call_hierarchy_item(item)]);
+
+        let response = BaseResponse {
+            jsonrpc: "2.0".to_string(),
+            id: message.id,
+            result: serde_json::to_value(items).ok(),
+            error: None,
+        };
+
+        if let Err(e) = self.writer.write_message(&response).await {
+            log::error!("Failed to write response: {}", e);
+        }
+    }
+
+    pub async fn incoming_calls(&mut self, message: BaseMessage) {
+        let params = match message.params {
+            Some(params) => {
+                match serde_json::from_value::<CallHierarchyIncomingCallsParams>(params) {
+                    Ok(params) => params,
+                    Err(e) => {
+                        log::error!("Failed to parse incoming calls params: {}", e);
+                        return;
+                    }
+                }
+            }
+            None => {
+                log::error!("Missing params in incoming calls request");
+                return;
+            }
+        };
+
+        let path = match parse_uri_to_path(&params.item.uri) {
+            Some(path) => path,
+            None => return,
+        };
+
+        let calls: Vec<CallHierarchyIncomingCall> = self
+            .analyzer
+            .incoming_calls(&path, params.item.selection_range.start.into())
+            .into_iter()
+            .map(|call| CallHierarchyIncomingCall {
+                from: to_lsp_call_hierarchy_item(call.from),
+                from_ranges: call.from_ranges.into_iter().map(Range::from).collect(),
+            })
+            .collect();
+
+        let response = BaseResponse {
+            jsonrpc: "2.0".to_string(),
+            id: message.id,
+            result: serde_json::to_value(calls).ok(),
+            error: None,
+        };
+
+        if let Err(e) = self.writer.write_message(&response).await {
+            log::error!("Failed to write response: {}", e);
+        }
+    }
+
+    pub async fn outgoing_calls(&mut self, message: BaseMessage) {
+        let params = match message.params {
+            Some(params) => {
+                match serde_json::from_value::<CallHierarchyOutgoingCallsParams>(params) {
+                    Ok(params) => params,
+                    Err(e) => {
+                        log::error!("Failed to parse outgoing calls params: {}", e);
+                        return;
+                    }
+                }
+            }
+            None => {
+                log::error!("Missing params in outgoing calls request");
+                return;
+            }
+        };
+
+        let path = match parse_uri_to_path(&params.item.uri) {
+            Some(path) => path,
+            None => return,
+        };
+
+        let calls: Vec<CallHierarchyOutgoingCall> = self
+            .analyzer
+            .outgoing_calls(&path, params.item.selection_range.start.into())
+            .into_iter()
+            .map(|call| CallHierarchyOutgoingCall {
+                to: to_lsp_call_hierarchy_item(call.to),
+                from_ranges: call.from_ranges.into_iter().map(Range::from).collect(),
+            })
+            .collect();
+
+        let response = BaseResponse {
+            jsonrpc: "2.0".to_string(),
+            id: message.id,
+            result: serde_json::to_value(calls).ok(),
+            error: None,
+        };
+
+        if let Err(e) = self.writer.write_message(&response).await {
+            log::error!("Failed to write response: {}", e);
+        }
+    }
+
+    pub async fn workspace_symbol(&mut self, message: BaseMessage) {
+        let params = match message.params {
+            Some(params) => match serde_json::from_value::<WorkspaceSymbolParams>(params) {
+                Ok(params) => params,
+                Err(e) => {
+                    log::error!("Failed to parse workspace symbol params: {}", e);
+                    return;
+                }
+            },
+            None => {
+                log::error!("Missing params in workspace symbol request");
+                return;
+            }
+        };
+
+        let symbols: Vec<SymbolInformation> = self
+            .analyzer
+            .workspace_symbols(&params.query)
+            .into_iter()
+            .map(|symbol| SymbolInformation {
+                name: symbol.name,
+                kind: symbol.kind.into(),
+                location: Location {
+                    uri: path_to_uri(&symbol.location.path),
+                    range: symbol.location.range.into(),
+                },
+                container_name: symbol.container_name,
+            })
+            .collect();
+
+        let response = BaseResponse {
+            jsonrpc: "2.0".to_string(),
+            id: message.id,
+            result: serde_json::to_value(symbols).ok(),
+            error: None,
+        };
+
+        if let Err(e) = self.writer.write_message(&response).await {
+            log::error!("Failed to write response: {}", e);
+        }
+    }
+
+    pub async fn prepare_rename(&mut self, message: BaseMessage) {
+        let params = match message.params {
+            Some(params) => match serde_json::from_value::<PrepareRenameParams>(params) {
+                Ok(params) => params,
+                Err(e) => {
+                    log::error!("Failed to parse prepare rename params: {}", e);
+                    return;
+                }
+            },
+            None => {
+                log::error!("Missing params in prepare rename request");
+                return;
+            }
+        };
+
+        let path = match parse_uri_to_path(&params.text_document.uri) {
+            Some(path) => path,
+            None => return,
+        };
+
+        let range = self
+            .analyzer
+            .prepare_rename(&path, params.position.into())
+            .map(Range::from);
+
+        let response = BaseResponse {
+            jsonrpc: "2.0".to_string(),
+            id: message.id,
+            result: serde_json::to_value(range).ok(),
+            error: None,
+        };
+
+        if let Err(e) = self.writer.write_message(&response).await {
+            log::error!("Failed to write response: {}", e);
+        }
+    }
+
+    pub async fn rename(&mut self, message: BaseMessage) {
+        let params = match message.params {
+            Some(params) => match serde_json::from_value::<RenameParams>(params) {
+                Ok(params) => params,
+                Err(e) => {
+                    log::error!("Failed to parse rename params: {}", e);
+                    return;
+                }
+            },
+            None => {
+                log::error!("Missing params in rename request");
+                return;
+            }
+        };
+
+        let path = match parse_uri_to_path(&params.text_document.uri) {
+            Some(path) => path,
+            None => return,
+        };
+
+        let response = match self
+            .analyzer
+            .rename(&path, params.position.into(), &params.new_name)
+        {
+            Ok(edit) => BaseResponse {
+                jsonrpc: "2.0".to_string(),
+                id: message.id,
+                result: serde_json::to_value(to_lsp_workspace_edit(edit)).ok(),
+                error: None,
+            },
+            Err(e) => BaseResponse {
+                jsonrpc: "2.0".to_string(),
+                id: message.id,
+                result: None,
+                error: Some(ResponseError {
+                    code: -32803,
+                    message: e,
+                    data: None,
+                }),
+            },
+        };
+
+        if let Err(e) = self.writer.write_message(&response).await {
+            log::error!("Failed to write response: {}", e);
+        }
+    }
+
+    pub async fn code_action(&mut self, message: BaseMessage) {
+        let params = match message.params {
+            Some(params) => match serde_json::from_value::<CodeActionParams>(params) {
+                Ok(params) => params,
+                Err(e) => {
+                    log::error!("Failed to parse code action params: {}", e);
+                    return;
+                }
+            },
+            None => {
+                log::error!("Missing params in code action request");
+                return;
+            }
+        };
+
+        let path = match parse_uri_to_path(&params.text_document.uri) {
+            Some(path) => path,
+            None => return,
+        };
+
+        let only = params.context.only;
+        let actions: Vec<CodeAction> = self
+            .analyzer
+            .code_actions(&path, &params.range.into())
+            .into_iter()
+            .map(to_lsp_code_action)
+            .filter(|action| {
+                only.as_ref()
+                    .is_none_or(|only| only.iter().any(|kind| action.kind.is_in(kind)))
+            })
+            .collect();
+
+        let response = BaseResponse {
+            jsonrpc: "2.0".to_string(),
+            id: message.id,
+            result: serde_json::to_value(actions).ok(),
+            error: None,
+        };
+
+        if let Err(e) = self.writer.write_message(&response).await {
+            log::error!("Failed to write response: {}", e);
+        }
+    }
+
+    pub async fn execute_command(&mut self, message: BaseMessage) {
+        let params = match message.params {
+            Some(params) => match serde_json::from_value::<ExecuteCommandParams>(params) {
+                Ok(params) => params,
+                Err(e) => {
+                    log::error!("Failed to parse execute command params: {}", e);
+                    return;
+                }
+            },
+            None => {
+                log::error!("Missing params in execute command request");
+                return;
+            }
+        };
+
+        let arguments = params.arguments.unwrap_or_default();
+        let result = match params.command.as_str() {
+            EXTRACT_TYPEDEF_COMMAND => self.extract_typedef(&arguments),
+            MOVE_DEFINITION_COMMAND => self.move_definition(&arguments),
+            command => Err(format!("Unknown command: {}", command)),
+        };
+
+        let response = match result {
+            Ok(edit) => {
+                self.apply_edit(edit).await;
+                BaseResponse {
+                    jsonrpc: "2.0".to_string(),
+                    id: message.id,
+                    result: Some(serde_json::Value::Null),
+                    error: None,
+                }
+            }
+            Err(e) => BaseResponse {
+                jsonrpc: "2.0".to_string(),
+                id: message.id,
+                result: None,
+                error: Some(ResponseError {
+                    code: -32803,
+                    message: e,
+                    data: None,
+                }),
+            },
+        };
+
+        if let Err(e) = self.writer.write_message(&response).await {
+            log::error!("Failed to write response: {}", e);
+        }
+    }
+
+    fn extract_typedef(&self, arguments: &[serde_json::Value]) -> Result<WorkspaceEdit, String> {
+        let (uri, range) = match arguments {
+            [uri, range, ..] => (
+                uri.as_str(),
+                serde_json::from_value::<Range>(range.clone()).ok(),
+            ),
+            _ => (None, None),
+        };
+        let (path, range) = match (uri.and_then(parse_uri_to_path), range) {
+            (Some(path), Some(range)) => (path, range),
+            _ => return Err("Invalid arguments".to_string()),
+        };
+        let name = arguments
+            .get(2)
+            .and_then(|name| name.as_str())
+            .unwrap_or(DEFAULT_TYPEDEF_NAME);
+
+        self.analyzer
+            .extract_typedef(&path, &range.into(), name)
+            .map(to_lsp_workspace_edit)
+    }
+
+    fn move_definition(&self, arguments: &[serde_json::Value]) -> Result<WorkspaceEdit, String> {
+        let (uri, position) = match arguments {
+            [uri, position, ..] => (
+                uri.as_str(),
+                serde_json::from_value::<Position>(position.clone()).ok(),
+            ),
+            _ => (None, None),
+        };
+        let (path, position) = match (uri.and_then(parse_uri_to_path), position) {
+            (Some(path), Some(position)) => (path, position),
+            _ => return Err("Invalid arguments".to_string()),
+        };
+        let destination = arguments
+            .get(2)
+            .and_then(|destination| destination.as_str())
+            .and_then(parse_uri_to_path)
+            .ok_or("Missing destination file".to_string())?;
+
+        self.analyzer
+            .move_definition(&path, position.into(), &destination)
+            .map(to_lsp_workspace_edit)
+    }
+
+    /// Ask the client to apply an edit. The response of the client is ignored.
+    async fn apply_edit(&mut self, edit: WorkspaceEdit) {
+        let id = self.next_request_id;
+        self.next_request_id += 1;
+
+        let message = BaseMessage {
+            jsonrpc: "2.0".to_string(),
+            id: Some(id),
+            method: "workspace/applyEdit".to_string(),
+            params: serde_json::to_value(ApplyWorkspaceEditParams { label: None, edit }).ok(),
+        };
+        if let Err(e) = self.writer.write_message(&message).await {
+            log::error!("Failed to write apply edit request: {}", e);
+        }
+    }
+
+    pub async fn formatting(&mut self, message: BaseMessage) {
+        let params = match message.params {
+            Some(params) => match serde_json::from_value::<DocumentFormattingParams>(params) {
+                Ok(params) => params,
+                Err(e) => {
+                    log::error!("Failed to parse formatting params: {}", e);
+                    return;
+                }
+            },
+            None => {
+                log::error!("Missing params in formatting request");
+                return;
+            }
+        };
+
+        let path = match parse_uri_to_path(&params.text_document.uri) {
+            Some(path) => path,
+            None => return,
+        };
+
+        let edits: Vec<TextEdit> = self
+            .analyzer
+            .formatting(&path, &params.options.into())
+            .into_iter()
+            .map(TextEdit::from)
+            .collect();
+
+        let response = BaseResponse {
+            jsonrpc: "2.0".to_string(),
+            id: message.id,
+            result: serde_json::to_value(edits).ok(),
+            error: None,
+        };
+
+        if let Err(e) = self.writer.write_message(&response).await {
+            log::error!("Failed to write response: {}", e);
+        }
+    }
+
+    pub async fn range_formatting(&mut self, message: BaseMessage) {
+        let params = match message.params {
+            Some(params) => match serde_json::from_value::<DocumentRangeFormattingParams>(params) {
+                Ok(params) => params,
+                Err(e) => {
+                    log::error!("Failed to parse range formatting params: {}", e);
+                    return;
+                }
+            },
+            None => {
+                log::error!("Missing params in range formatting request");
+                return;
+            }
+        };
+
+        let path = match parse_uri_to_path(&params.text_document.uri) {
+            Some(path) => path,
+            None => return,
+        };
+
+        let edits: Vec<TextEdit> = self
+            .analyzer
+            .range_formatting(&path, &params.range.into(), &params.options.into())
+            .into_iter()
+            .map(TextEdit::from)
+            .collect();
+
+        let response = BaseResponse {
+            jsonrpc: "2.0".to_string(),
+            id: message.id,
+            result: serde_json::to_value(edits).ok(),
+            error: None,
+        };
+
+        if let Err(e) = self.writer.write_message(&response).await {
+            log::error!("Failed to write response: {}", e);
+        }
+    }
+
+    pub async fn on_type_formatting(&mut self, message: BaseMessage) {
+        let params = match message.params {
+            Some(params) => {
+                match serde_json::from_value::<DocumentOnTypeFormattingParams>(params) {
+                    Ok(params) => params,
+                    Err(e) => {
+                        log::error!("Failed to parse on type formatting params: {}", e);
+                        return;
+                    }
+                }
+            }
+            None => {
+                log::error!("Missing params in on type formatting request");
+                return;
+            }
+        };
+
+        let path = match parse_uri_to_path(&params.text_document.uri) {
+            Some(path) => path,
+            None => return,
+        };
+
+        let edits: Vec<TextEdit> = self
+            .analyzer
+            .on_type_formatting(
+                &path,
+                params.position.into(),
+                &params.ch,
+                &params.options.into(),
+            )
+            .into_iter()
+            .map(TextEdit::from)
+            .collect();
+
+        let response = BaseResponse {
+            jsonrpc: "2.0".to_string(),
+            id: message.id,
+            result: serde_json::to_value(edits).ok(),
+            error: None,
+        };
+
+        if let Err(e) = self.writer.write_message(&response).await {
+            log::error!("Failed to write response: {}", e);
+        }
+    }
+
+    async fn sync_document(&mut self, uri: &str, content: &str) {
+        let path = match parse_uri_to_path(uri) {
+            Some(x) => x,
+            None => return,
+        };
+
+        self.analyzer.sync_document(&path, content);
+    }
+
+    async fn remove_document(&mut self, uri: &str) {
+        let path = match parse_uri_to_path(uri) {
+            Some(x) => x,
+            None => return,
+        };
+        self.analyzer.remove_document(&path);
+    }
+
+    async fn publish_diagnostics(&mut self) {
+        let errors_map = self.analyzer.errors();
+
+        for (path, errors) in errors_map.iter() {
+            let mut diagnostics_params = PublishDiagnosticsParams {
+                uri: path_to_uri(path),
+                diagnostics: Vec::with_capacity(errors.len()),
+            };
+            for error in errors {
+                diagnostics_params.diagnostics.push(error.clone().into());
+            }
+
+            let message = BaseMessage {
+                jsonrpc: "2.0".to_string(),
+                id: None,
+                method: "textDocument/publishDiagnostics".to_string(),
+                params: serde_json::to_value(diagnostics_params).ok(),
+            };
+            if let Err(e) = self.writer.write_message(&message).await {
+                log::error!("Failed to write diagnostics: {}", e);
+            }
+        }
+    }
+}
+
+fn flatten_document_symbols(
+    uri: &str,
+    symbols: Vec<base::DocumentSymbol>,
+    container_name: Option<&str>,
+    result: &mut Vec<SymbolInformation>,
+) {
+    for symbol in symbols {
+        result.push(SymbolInformation {
+            name: symbol.name.clone(),
+            kind: symbol.kind.into(),
+            location: Location {
+                uri: uri.to_string(),
+                range: symbol.range.into(),
+            },
+            container_name: container_name.map(|name| name.to_string()),
+        });
+        flatten_document_symbols(uri, symbol.children, Some(&symbol.name), result);
+    }
+}
+
+fn to_lsp_workspace_edit(edit: base::WorkspaceEdit) -> WorkspaceEdit {
+    WorkspaceEdit {
+        changes: edit
+            .changes
+            .into_iter()
+            .map(|(path, edits)| {
+                (
+                    path_to_uri(&path),
+                    edits.into_iter().map(TextEdit::from).collect(),
+                )
+            })
+            .collect(),
+    }
+}
+
+fn to_lsp_code_action(action: base::CodeAction) -> CodeAction {
+    let command = action.command.map(|command| match command {
+        base::CodeActionCommand::ExtractTypedef { path, range } => Command {
+            title: action.title.clone(),
+            command: EXTRACT_TYPEDEF_COMMAND.to_string(),
+            arguments: Some(vec![
+                serde_json::json!(path_to_uri(&path)),
+                serde_json::json!(Range::from(range)),
+            ]),
+        },
+        base::CodeActionCommand::MoveDefinition { path, position } => Command {
+            title: action.title.clone(),
+            command: MOVE_DEFINITION_COMMAND.to_string(),
+            arguments: Some(vec![
+                serde_json::json!(path_to_uri(&path)),
+                serde_json::json!(Position::from(position)),
+            ]),
+        },
+    });
+
+    CodeAction {
+        title: action.title,
+        kind: action.kind.into(),
+        diagnostics: action
+            .diagnostics
+            .into_iter()
+            .map(Diagnostic::from)
+            .collect(),
+        edit: action.edit.map(to_lsp_workspace_edit),
+        command,
+    }
+}
+
+fn to_lsp_type_hierarchy_item(item: base::TypeHierarchyItem) -> TypeHierarchyItem {
+    TypeHierarchyItem {
+        name: item.name,
+        kind: item.kind.into(),
+        detail: item.detail,
+        uri: path_to_uri(&item.path),
+        range: item.range.into(),
+        selection_range: item.selection_range.into(),
+    }
+}
+
+fn to_lsp_call_hierarchy_item(item: base::CallHierarchyItem) -> CallHierarchyItem {
+    CallHierarchyItem {
+        name: item.name,
+        kind: item.kind.into(),
+        detail: item.detail,
+        uri: path_to_uri(&item.path),
+        range: item.range.into(),
+        selection_range: item.selection_range.into(),
+    }
 }
 
 fn parse_uri_to_path(uri: &str) -> Option<String> {
-    let url = match Url::parse(&uri) {
+    let url = match Url::parse(uri) {
         Ok(url) => url,
         Err(e) => {
             log::error!("Parse uri failed, err: {}", e);